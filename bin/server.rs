@@ -1,8 +1,11 @@
-use server::Server;
+use server::{Allocator, Config, Server};
 use tokio::runtime::Builder;
-use tracing::Level;
+use tracing::{error, Level};
 use tracing_subscriber::fmt::Subscriber;
 
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
 fn main() {
     Subscriber::builder()
         .with_file(true)
@@ -17,7 +20,13 @@ fn main() {
         .unwrap()
         .block_on(async {
             let addr = "127.0.0.1:9694".parse().unwrap();
-            let server = Server::new(addr);
-            server.run().await
+            let config = match Config::default().parse_args(std::env::args().skip(1)) {
+                Ok(config) => config,
+                Err(e) => return error!("{}", e),
+            };
+            match Server::new(addr, config) {
+                Ok(server) => server.run().await,
+                Err(e) => error!("{}", e),
+            }
         });
 }
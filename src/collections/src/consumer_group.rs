@@ -0,0 +1,177 @@
+use crate::memory::HeapSize;
+use crate::stream::StreamId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem::size_of;
+use std::ops::Bound;
+
+/// An entry delivered to a consumer and not acknowledged yet.
+#[derive(Debug, Clone)]
+pub struct PendingEntry<V> {
+    pub consumer: V,
+    pub delivered_ms: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Consumer {
+    /// Last time the consumer issued a command.
+    pub seen_ms: u64,
+    /// Last time the consumer was delivered or claimed an entry.
+    pub active_ms: Option<u64>,
+    pending: BTreeSet<StreamId>,
+}
+
+impl Consumer {
+    fn new(now_ms: u64) -> Self {
+        Self {
+            seen_ms: now_ms,
+            active_ms: None,
+            pending: BTreeSet::new(),
+        }
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Pending IDs of this consumer greater than `id`.
+    pub fn pending_after(&self, id: StreamId) -> impl Iterator<Item = StreamId> + '_ {
+        self.pending
+            .range((Bound::Excluded(id), Bound::Unbounded))
+            .copied()
+    }
+}
+
+/// Options of `XCLAIM`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaimOptions {
+    pub idle: Option<u64>,
+    pub time: Option<u64>,
+    pub retry_count: Option<u64>,
+    pub force: bool,
+    pub just_id: bool,
+    pub last_id: Option<StreamId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsumerGroup<V> {
+    pub(crate) last_delivered: StreamId,
+    pub(crate) entries_read: Option<u64>,
+    pub(crate) pending: BTreeMap<StreamId, PendingEntry<V>>,
+    pub(crate) consumers: BTreeMap<V, Consumer>,
+}
+
+impl<V> ConsumerGroup<V>
+where
+    V: Clone + Ord,
+{
+    pub(crate) fn new(last_delivered: StreamId, entries_read: Option<u64>) -> Self {
+        Self {
+            last_delivered,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    pub fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    pub fn entries_read(&self) -> Option<u64> {
+        self.entries_read
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = (&StreamId, &PendingEntry<V>)> {
+        self.pending.iter()
+    }
+
+    pub fn consumers(&self) -> impl Iterator<Item = (&V, &Consumer)> {
+        self.consumers.iter()
+    }
+
+    pub fn consumer(&self, name: &V) -> Option<&Consumer> {
+        self.consumers.get(name)
+    }
+
+    /// Looks a consumer up, creating it on first use, and marks it as seen.
+    pub(crate) fn touch(&mut self, name: V, now_ms: u64) -> &mut Consumer {
+        let consumer = self
+            .consumers
+            .entry(name)
+            .or_insert_with(|| Consumer::new(now_ms));
+        consumer.seen_ms = now_ms;
+        consumer
+    }
+
+    /// `false` when the consumer already exists.
+    pub(crate) fn create_consumer(&mut self, name: V, now_ms: u64) -> bool {
+        if self.consumers.contains_key(&name) {
+            return false;
+        }
+        self.consumers.insert(name, Consumer::new(now_ms));
+        true
+    }
+
+    /// Removes a consumer with its pending entries, returning how many were pending.
+    pub(crate) fn delete_consumer(&mut self, name: &V) -> usize {
+        match self.consumers.remove(name) {
+            Some(consumer) => {
+                for id in consumer.pending.iter() {
+                    self.pending.remove(id);
+                }
+                consumer.pending.len()
+            }
+            None => 0,
+        }
+    }
+
+    /// Hands `id` to `consumer`, taking it away from its previous owner if any.
+    /// The consumer is expected to have been touched already.
+    pub(crate) fn assign(&mut self, id: StreamId, consumer: V, delivered_ms: u64, count: u64) {
+        self.unassign(&id);
+        if let Some(owner) = self.consumers.get_mut(&consumer) {
+            owner.pending.insert(id);
+        }
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer,
+                delivered_ms,
+                delivery_count: count,
+            },
+        );
+    }
+
+    pub(crate) fn unassign(&mut self, id: &StreamId) -> Option<PendingEntry<V>> {
+        let entry = self.pending.remove(id)?;
+        if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+            consumer.pending.remove(id);
+        }
+        Some(entry)
+    }
+}
+
+impl<V> ConsumerGroup<V>
+where
+    V: HeapSize,
+{
+    /// Heap bytes of the PEL and the consumers, B-tree nodes counted by their entries.
+    pub(crate) fn memory_usage(&self) -> usize {
+        let pending = self.pending.len() * size_of::<(StreamId, PendingEntry<V>)>();
+        let consumers = self
+            .consumers
+            .iter()
+            .map(|(name, consumer)| {
+                size_of::<(V, Consumer)>()
+                    + name.heap_size()
+                    + consumer.pending.len() * size_of::<StreamId>()
+            })
+            .sum::<usize>();
+        pending + consumers
+    }
+}
@@ -0,0 +1,413 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+pub const LONGITUDE_MIN: f64 = -180.0;
+pub const LONGITUDE_MAX: f64 = 180.0;
+/// Limits of the web mercator projection, the poles can not be indexed.
+pub const LATITUDE_MIN: f64 = -85.05112878;
+pub const LATITUDE_MAX: f64 = 85.05112878;
+
+/// Precision of the hash stored as score, 26 bits for each coordinate.
+const STEP_MAX: u8 = 26;
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+const BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug)]
+pub struct ParseUnitError;
+
+impl Display for ParseUnitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "unsupported unit provided. please use M, KM, FT, MI")
+    }
+}
+
+impl Error for ParseUnitError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+impl GeoUnit {
+    fn meters(self) -> f64 {
+        match self {
+            Self::Meters => 1.0,
+            Self::Kilometers => 1000.0,
+            Self::Feet => 0.3048,
+            Self::Miles => 1609.34,
+        }
+    }
+
+    pub fn to_meters(self, value: f64) -> f64 {
+        value * self.meters()
+    }
+
+    pub fn from_meters(self, meters: f64) -> f64 {
+        meters / self.meters()
+    }
+}
+
+impl FromStr for GeoUnit {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "m" => Ok(Self::Meters),
+            "km" => Ok(Self::Kilometers),
+            "ft" => Ok(Self::Feet),
+            "mi" => Ok(Self::Miles),
+            _ => Err(ParseUnitError),
+        }
+    }
+}
+
+/// Interleaved latitude (even bits) and longitude (odd bits) cell index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HashBits {
+    bits: u64,
+    step: u8,
+}
+
+/// A cell of the grid, as coordinate ranges.
+struct Area {
+    longitude: (f64, f64),
+    latitude: (f64, f64),
+}
+
+fn interleave(latitude: u32, longitude: u32) -> u64 {
+    fn spread(value: u32) -> u64 {
+        let mut value = value as u64;
+        value = (value | (value << 16)) & 0x0000FFFF0000FFFF;
+        value = (value | (value << 8)) & 0x00FF00FF00FF00FF;
+        value = (value | (value << 4)) & 0x0F0F0F0F0F0F0F0F;
+        value = (value | (value << 2)) & 0x3333333333333333;
+        (value | (value << 1)) & 0x5555555555555555
+    }
+    spread(latitude) | (spread(longitude) << 1)
+}
+
+fn deinterleave(bits: u64) -> (u32, u32) {
+    fn squash(value: u64) -> u32 {
+        let mut value = value & 0x5555555555555555;
+        value = (value | (value >> 1)) & 0x3333333333333333;
+        value = (value | (value >> 2)) & 0x0F0F0F0F0F0F0F0F;
+        value = (value | (value >> 4)) & 0x00FF00FF00FF00FF;
+        value = (value | (value >> 8)) & 0x0000FFFF0000FFFF;
+        ((value | (value >> 16)) & 0x00000000FFFFFFFF) as u32
+    }
+    (squash(bits), squash(bits >> 1))
+}
+
+fn encode_in(longitude: f64, latitude: f64, latitude_range: (f64, f64), step: u8) -> HashBits {
+    let cells = (1u64 << step) as f64;
+    let latitude_offset = (latitude - latitude_range.0) / (latitude_range.1 - latitude_range.0);
+    let longitude_offset = (longitude - LONGITUDE_MIN) / (LONGITUDE_MAX - LONGITUDE_MIN);
+    // the upper limits belong to the last cell
+    let max = (1u64 << step) - 1;
+    let latitude_offset = ((latitude_offset * cells) as u64).min(max) as u32;
+    let longitude_offset = ((longitude_offset * cells) as u64).min(max) as u32;
+    HashBits {
+        bits: interleave(latitude_offset, longitude_offset),
+        step,
+    }
+}
+
+impl HashBits {
+    fn new(longitude: f64, latitude: f64, step: u8) -> Self {
+        encode_in(longitude, latitude, (LATITUDE_MIN, LATITUDE_MAX), step)
+    }
+
+    fn area(&self) -> Area {
+        let (latitude, longitude) = deinterleave(self.bits);
+        let cells = (1u64 << self.step) as f64;
+        let latitude_scale = LATITUDE_MAX - LATITUDE_MIN;
+        let longitude_scale = LONGITUDE_MAX - LONGITUDE_MIN;
+        Area {
+            longitude: (
+                LONGITUDE_MIN + (longitude as f64 / cells) * longitude_scale,
+                LONGITUDE_MIN + ((longitude as f64 + 1.0) / cells) * longitude_scale,
+            ),
+            latitude: (
+                LATITUDE_MIN + (latitude as f64 / cells) * latitude_scale,
+                LATITUDE_MIN + ((latitude as f64 + 1.0) / cells) * latitude_scale,
+            ),
+        }
+    }
+
+    /// Moves east (`1`) or west (`-1`), wrapping around the globe.
+    fn move_x(self, direction: i8) -> Self {
+        let mask = 0xaaaaaaaaaaaaaaaau64 >> (64 - self.step as u32 * 2);
+        let ones = 0x5555555555555555u64 >> (64 - self.step as u32 * 2);
+        let y = self.bits & 0x5555555555555555;
+        let mut x = self.bits & 0xaaaaaaaaaaaaaaaa;
+        if direction > 0 {
+            x = x.wrapping_add(ones + 1);
+        } else {
+            x = (x | ones).wrapping_sub(ones + 1);
+        }
+        Self {
+            bits: (x & mask) | y,
+            step: self.step,
+        }
+    }
+
+    /// Moves north (`1`) or south (`-1`).
+    fn move_y(self, direction: i8) -> Self {
+        let mask = 0x5555555555555555u64 >> (64 - self.step as u32 * 2);
+        let ones = 0xaaaaaaaaaaaaaaaau64 >> (64 - self.step as u32 * 2);
+        let x = self.bits & 0xaaaaaaaaaaaaaaaa;
+        let mut y = self.bits & 0x5555555555555555;
+        if direction > 0 {
+            y = y.wrapping_add(ones + 1);
+        } else {
+            y = (y | ones).wrapping_sub(ones + 1);
+        }
+        Self {
+            bits: x | (y & mask),
+            step: self.step,
+        }
+    }
+
+    /// Scores of the members inside this cell, the upper bound excluded.
+    fn score_range(&self) -> (f64, f64) {
+        let shift = (STEP_MAX - self.step) as u32 * 2;
+        (
+            (self.bits << shift) as f64,
+            ((self.bits + 1) << shift) as f64,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+impl GeoPoint {
+    pub fn new(longitude: f64, latitude: f64) -> Self {
+        Self {
+            longitude,
+            latitude,
+        }
+    }
+
+    /// The center of the cell a sorted set score stands for.
+    pub fn from_score(score: f64) -> Self {
+        let area = HashBits {
+            bits: score as u64,
+            step: STEP_MAX,
+        }
+        .area();
+        Self {
+            longitude: ((area.longitude.0 + area.longitude.1) / 2.0)
+                .clamp(LONGITUDE_MIN, LONGITUDE_MAX),
+            latitude: ((area.latitude.0 + area.latitude.1) / 2.0).clamp(LATITUDE_MIN, LATITUDE_MAX),
+        }
+    }
+
+    /// Whether the point can be indexed, as `GEOADD` requires.
+    pub fn is_valid(&self) -> bool {
+        (LONGITUDE_MIN..=LONGITUDE_MAX).contains(&self.longitude)
+            && (LATITUDE_MIN..=LATITUDE_MAX).contains(&self.latitude)
+    }
+
+    /// The sorted set score the point is stored with.
+    pub fn score(&self) -> f64 {
+        HashBits::new(self.longitude, self.latitude, STEP_MAX).bits as f64
+    }
+
+    /// The standard 11 characters geohash, which spans latitudes up to the poles.
+    pub fn geohash(&self) -> String {
+        let bits = encode_in(self.longitude, self.latitude, (-90.0, 90.0), STEP_MAX).bits;
+        (0..11)
+            .map(|i| {
+                // the last character only has 2 of its 5 bits, they are left as 0
+                let index = if i == 10 {
+                    0
+                } else {
+                    (bits >> (52 - (i + 1) * 5)) & 0x1f
+                };
+                BASE32[index as usize] as char
+            })
+            .collect()
+    }
+
+    /// Great circle distance in meters.
+    pub fn distance(&self, other: &GeoPoint) -> f64 {
+        let (latitude1, latitude2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let u = ((latitude2 - latitude1) / 2.0).sin();
+        let v = ((other.longitude.to_radians() - self.longitude.to_radians()) / 2.0).sin();
+        2.0 * EARTH_RADIUS_IN_METERS
+            * (u * u + latitude1.cos() * latitude2.cos() * v * v)
+                .sqrt()
+                .asin()
+    }
+}
+
+fn steps_by_radius(mut range: f64, latitude: f64) -> u8 {
+    if range == 0.0 {
+        return STEP_MAX;
+    }
+    let mut step: i32 = 1;
+    while range < MERCATOR_MAX {
+        range *= 2.0;
+        step += 1;
+    }
+    // cells shrink towards the poles, so a coarser one is needed there
+    step -= 2;
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+    step.clamp(1, STEP_MAX as i32) as u8
+}
+
+/// The area searched by `GEOSEARCH`, sizes in meters.
+#[derive(Debug, Clone, Copy)]
+pub enum GeoShape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+impl GeoShape {
+    /// The distance to `point` when it lies inside the shape centered on `center`.
+    pub fn distance(&self, center: &GeoPoint, point: &GeoPoint) -> Option<f64> {
+        match self {
+            Self::Radius(radius) => {
+                let distance = center.distance(point);
+                (distance <= *radius).then_some(distance)
+            }
+            Self::Box { width, height } => {
+                let latitude_distance = EARTH_RADIUS_IN_METERS
+                    * (point.latitude.to_radians() - center.latitude.to_radians()).abs();
+                if latitude_distance > height / 2.0 {
+                    return None;
+                }
+                let longitude_distance =
+                    GeoPoint::new(center.longitude, point.latitude).distance(point);
+                if longitude_distance > width / 2.0 {
+                    return None;
+                }
+                Some(center.distance(point))
+            }
+        }
+    }
+
+    /// Longitude and latitude limits of the shape, as west, south, east and north.
+    fn bounds(&self, center: &GeoPoint) -> (f64, f64, f64, f64) {
+        let (width, height) = match self {
+            Self::Radius(radius) => (*radius, *radius),
+            Self::Box { width, height } => (width / 2.0, height / 2.0),
+        };
+        let (longitude, latitude) = (center.longitude, center.latitude);
+        let latitude_delta = (height / EARTH_RADIUS_IN_METERS).to_degrees();
+        let longitude_delta_top =
+            (width / EARTH_RADIUS_IN_METERS / (latitude + latitude_delta).to_radians().cos())
+                .to_degrees();
+        let longitude_delta_bottom =
+            (width / EARTH_RADIUS_IN_METERS / (latitude - latitude_delta).to_radians().cos())
+                .to_degrees();
+        // the widest side is the one closer to the equator
+        let longitude_delta = if latitude < 0.0 {
+            longitude_delta_bottom
+        } else {
+            longitude_delta_top
+        };
+        (
+            longitude - longitude_delta,
+            latitude - latitude_delta,
+            longitude + longitude_delta,
+            latitude + latitude_delta,
+        )
+    }
+
+    /// Score ranges of the cells covering the shape, upper bounds excluded.
+    pub fn score_ranges(&self, center: &GeoPoint) -> Vec<(f64, f64)> {
+        let (longitude, latitude) = (center.longitude, center.latitude);
+        let (west, south, east, north) = self.bounds(center);
+        let radius = match self {
+            Self::Radius(radius) => *radius,
+            Self::Box { width, height } => ((width / 2.0).powi(2) + (height / 2.0).powi(2)).sqrt(),
+        };
+
+        let mut step = steps_by_radius(radius, latitude);
+        let mut hash = HashBits::new(longitude, latitude, step);
+        // the neighbours may still fall short of the bounds, go one level coarser then
+        if step > 1 {
+            let north_area = hash.move_y(1).area();
+            let south_area = hash.move_y(-1).area();
+            let east_area = hash.move_x(1).area();
+            let west_area = hash.move_x(-1).area();
+            if north_area.latitude.1 < north
+                || south_area.latitude.0 > south
+                || east_area.longitude.1 < east
+                || west_area.longitude.0 > west
+            {
+                step -= 1;
+                hash = HashBits::new(longitude, latitude, step);
+            }
+        }
+
+        let area = hash.area();
+        let mut cells = vec![hash];
+        // with large cells a neighbour is only needed when the shape crosses into it
+        let coarse = step < 2;
+        let needs_north = coarse || area.latitude.1 < north;
+        let needs_south = coarse || area.latitude.0 > south;
+        let needs_east = coarse || area.longitude.1 < east;
+        let needs_west = coarse || area.longitude.0 > west;
+        for (x, y, needed) in [
+            (0, 1, needs_north),
+            (0, -1, needs_south),
+            (1, 0, needs_east),
+            (-1, 0, needs_west),
+            (1, 1, needs_north && needs_east),
+            (-1, 1, needs_north && needs_west),
+            (1, -1, needs_south && needs_east),
+            (-1, -1, needs_south && needs_west),
+        ] {
+            if !needed {
+                continue;
+            }
+            let mut neighbour = hash;
+            if x != 0 {
+                neighbour = neighbour.move_x(x);
+            }
+            if y != 0 {
+                neighbour = neighbour.move_y(y);
+            }
+            // at the coarsest steps neighbours wrap onto each other
+            if !cells.contains(&neighbour) {
+                cells.push(neighbour);
+            }
+        }
+        cells.iter().map(|cell| cell.score_range()).collect()
+    }
+}
+
+#[test]
+fn test_geo_point() {
+    let palermo = GeoPoint::new(13.361389, 38.115556);
+    let catania = GeoPoint::new(15.087269, 37.502669);
+    assert_eq!(palermo.score(), 3479099956230698.0);
+    assert_eq!(palermo.geohash(), "sqc8b49rny0");
+
+    let decoded = GeoPoint::from_score(catania.score());
+    assert!((decoded.longitude - catania.longitude).abs() < 1e-5);
+    assert!((decoded.latitude - catania.latitude).abs() < 1e-5);
+    assert!((palermo.distance(&catania) - 166274.1516).abs() < 1.0);
+
+    let score = catania.score();
+    assert!(GeoShape::Radius(200_000.0)
+        .score_ranges(&GeoPoint::new(15.0, 37.0))
+        .iter()
+        .any(|(min, max)| *min <= score && score < *max));
+}
@@ -1,7 +1,28 @@
+mod consumer_group;
+mod geo;
 mod list;
+mod memory;
+mod scan;
 mod set;
+mod skiplist;
+mod sorted_set;
+mod stream;
 mod strings;
 
+pub use consumer_group::{ClaimOptions, Consumer, ConsumerGroup, PendingEntry};
+pub use geo::{
+    GeoPoint, GeoShape, GeoUnit, ParseUnitError, LATITUDE_MAX, LATITUDE_MIN, LONGITUDE_MAX,
+    LONGITUDE_MIN,
+};
 pub use list::List;
+pub use memory::HeapSize;
+pub use scan::{may_rehash, sample_table, scan_table};
 pub use set::Set;
+pub use sorted_set::{
+    AddFlags, AddResult, LexBound, LexRange, ParseRangeError, RangeSpec, ScoreBound, ScoreRange,
+    SortedSet,
+};
+pub use stream::{
+    AddId, AutoClaim, GroupEntry, Stream, StreamEntry, StreamError, StreamId, Trim, TrimStrategy,
+};
 pub use strings::Strings;
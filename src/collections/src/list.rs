@@ -1,8 +1,11 @@
+use crate::memory::{sampled, HeapSize};
 use std::cmp::Eq;
 use std::collections::{vec_deque::Iter, VecDeque};
 use std::iter::{Extend, IntoIterator};
+use std::mem::size_of;
 use std::ops::Range;
 
+#[derive(Clone)]
 pub struct List<V> {
     inner: VecDeque<V>,
 }
@@ -76,3 +79,18 @@ impl<V> List<V> {
         self.inner.len()
     }
 }
+
+impl<V> List<V>
+where
+    V: HeapSize,
+{
+    /// Heap bytes, the elements' own allocations estimated from `samples` of them.
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        self.inner.capacity() * size_of::<V>()
+            + sampled(
+                self.inner.iter().map(HeapSize::heap_size),
+                self.inner.len(),
+                samples,
+            )
+    }
+}
@@ -0,0 +1,60 @@
+use std::mem::size_of;
+use std::sync::Arc;
+
+/// Bytes a value owns on the heap, leaving out its own inline size.
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T> HeapSize for Arc<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        // the strong and weak counts sit next to the value
+        2 * size_of::<usize>() + size_of::<T>() + (**self).heap_size()
+    }
+}
+
+/// Size of `len` items extrapolated from the sizes of the first `samples` of them,
+/// all of them when `samples` is 0, the way `MEMORY USAGE ... SAMPLES` works.
+pub(crate) fn sampled<I>(sizes: I, len: usize, samples: usize) -> usize
+where
+    I: Iterator<Item = usize>,
+{
+    let samples = if samples == 0 { len } else { samples.min(len) };
+    if samples == 0 {
+        return 0;
+    }
+    sizes.take(samples).sum::<usize>() * len / samples
+}
+
+/// Allocation of a hashbrown table of `capacity`, one control byte per bucket.
+pub(crate) fn table_size<T>(capacity: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    // tables are kept at most 7/8 full
+    let buckets = (capacity * 8 / 7).next_power_of_two();
+    buckets * (size_of::<T>() + 1)
+}
+
+#[test]
+fn test_heap_size() {
+    let value = Arc::new(String::with_capacity(10));
+    assert_eq!(
+        value.heap_size(),
+        2 * size_of::<usize>() + size_of::<String>() + 10
+    );
+
+    let sizes = [2, 4];
+    assert_eq!(sampled(sizes.into_iter(), sizes.len(), 0), 6);
+    assert_eq!(sampled(sizes.into_iter(), 10, 1), 20);
+    assert_eq!(sampled(sizes.into_iter(), 0, 5), 0);
+}
@@ -0,0 +1,143 @@
+use hashbrown::raw::RawTable;
+use hashbrown::HashMap;
+
+/// Cursors keep the bucket index in the low bits, then the log2 of the bucket count
+/// and the low bits of the rehash generation, 59 bits in all so that callers have
+/// 5 bits left to prefix their own position. A table would have to rehash 2^21 times
+/// between two calls for a stale cursor to pass for a current one.
+const INDEX_BITS: u32 = 32;
+const SIZE_BITS: u32 = 6;
+const GENERATION_BITS: u32 = 21;
+
+/// The entry in bucket `index`, which must be below the bucket count.
+fn full_bucket<T>(table: &RawTable<T>, index: usize) -> Option<&T> {
+    assert!(index < table.buckets());
+    // SAFETY: the index is in bounds, and a full bucket holds an initialized entry
+    // borrowed from the table
+    unsafe {
+        if table.is_bucket_full(index) {
+            Some(table.bucket(index).as_ref())
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether inserting a new key may move the entries of `map`, by resizing it or by
+/// rehashing it in place to reclaim tombstones. Owners of a scanned table bump their
+/// rehash generation when this holds before an insert.
+pub fn may_rehash<K, V, S>(map: &HashMap<K, V, S>) -> bool {
+    map.capacity() == map.len()
+}
+
+/// Walks the buckets of `map` from `cursor`, handing `f` at most `count` entries, and
+/// returns the cursor to resume from, 0 once the last bucket has been visited.
+///
+/// Entries never move while the table keeps its size and its `rehashes` generation, so
+/// an entry present during the whole walk is seen at least once. When either changed
+/// the walk starts over, which may only repeat entries.
+pub fn scan_table<K, V, S, F>(
+    map: &HashMap<K, V, S>,
+    rehashes: u64,
+    cursor: u64,
+    count: usize,
+    mut f: F,
+) -> u64
+where
+    F: FnMut(&K, &V),
+{
+    let table = map.raw_table();
+    let buckets = table.buckets();
+    debug_assert!(buckets <= 1 << INDEX_BITS);
+    let header =
+        buckets.trailing_zeros() as u64 | (rehashes & ((1 << GENERATION_BITS) - 1)) << SIZE_BITS;
+
+    let mut index = (cursor & ((1 << INDEX_BITS) - 1)) as usize;
+    if cursor >> INDEX_BITS != header || index >= buckets {
+        index = 0;
+    }
+
+    // runs of empty buckets are bounded too, like Redis does
+    let (mut visited, mut empty) = (0, 0);
+    while index < buckets && visited < count && empty < count * 10 {
+        match full_bucket(table, index) {
+            Some((key, value)) => {
+                f(key, value);
+                visited += 1;
+            }
+            None => empty += 1,
+        }
+        index += 1;
+    }
+
+    if index == buckets {
+        0
+    } else {
+        index as u64 | header << INDEX_BITS
+    }
+}
+
+/// Hands `f` up to `count` entries found from bucket `start` on, wrapping around, to
+/// sample a table from a random position.
+pub fn sample_table<K, V, S, F>(map: &HashMap<K, V, S>, start: usize, count: usize, mut f: F)
+where
+    F: FnMut(&K, &V),
+{
+    let table = map.raw_table();
+    let buckets = table.buckets();
+    let mut visited = 0;
+    for offset in 0..buckets {
+        if visited == count {
+            break;
+        }
+        if let Some((key, value)) = full_bucket(table, (start + offset) & (buckets - 1)) {
+            f(key, value);
+            visited += 1;
+        }
+    }
+}
+
+#[test]
+fn test_scan_table() {
+    let mut map: HashMap<u32, ()> = (0..1000).map(|i| (i, ())).collect();
+    let mut seen = Vec::new();
+    let mut cursor = 0;
+    loop {
+        cursor = scan_table(&map, 0, cursor, 10, |key, _| seen.push(*key));
+        if cursor == 0 {
+            break;
+        }
+        // keys added and removed along the way don't hide the others
+        map.insert(1000 + seen.len() as u32, ());
+        map.remove(&(seen.len() as u32 % 7 + 2000));
+    }
+    for i in 0..1000 {
+        assert!(seen.contains(&i));
+    }
+
+    // a different generation starts over
+    let cursor = scan_table(&map, 0, 0, 10, |_, _| {});
+    let mut first = Vec::new();
+    scan_table(&map, 1, cursor, 10, |key, _| first.push(*key));
+    let mut expected = Vec::new();
+    scan_table(&map, 1, 0, 10, |key, _| expected.push(*key));
+    assert_eq!(first, expected);
+
+    // so does one far enough ahead to have wrapped a narrow generation field
+    let mut first = Vec::new();
+    scan_table(&map, 1 << 11, cursor, 10, |key, _| first.push(*key));
+    assert_eq!(first, expected);
+}
+
+#[test]
+fn test_sample_table() {
+    let map: HashMap<u32, ()> = (0..100).map(|i| (i, ())).collect();
+    let mut seen = Vec::new();
+    sample_table(&map, 12345, 5, |key, _| seen.push(*key));
+    assert_eq!(seen.len(), 5);
+
+    let mut seen = Vec::new();
+    sample_table(&map, 7, 1000, |key, _| seen.push(*key));
+    seen.sort();
+    assert_eq!(seen, (0..100).collect::<Vec<u32>>());
+}
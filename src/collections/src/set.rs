@@ -1,16 +1,22 @@
-use hashbrown::{hash_set::Iter, HashSet};
+use crate::memory::{sampled, table_size, HeapSize};
+use crate::scan::{may_rehash, scan_table};
+use hashbrown::{hash_map::Keys, HashMap};
 use std::cmp::Eq;
 use std::hash::Hash;
 use std::iter::IntoIterator;
 
+#[derive(Clone)]
 pub struct Set<V> {
-    inner: HashSet<V>,
+    inner: HashMap<V, ()>,
+    /// Times the members may have moved inside `inner`, for `SSCAN`.
+    rehashes: u64,
 }
 
 impl<V> Set<V> {
     pub fn new() -> Self {
         Self {
-            inner: HashSet::new(),
+            inner: HashMap::new(),
+            rehashes: 0,
         }
     }
 
@@ -18,8 +24,33 @@ impl<V> Set<V> {
         self.inner.len()
     }
 
-    pub fn smembers(&self) -> Iter<'_, V> {
-        self.inner.iter()
+    pub fn smembers(&self) -> Keys<'_, V, ()> {
+        self.inner.keys()
+    }
+
+    /// Hands `f` at most `count` members from `cursor`, see `scan_table`.
+    pub fn scan<F>(&self, cursor: u64, count: usize, mut f: F) -> u64
+    where
+        F: FnMut(&V),
+    {
+        scan_table(&self.inner, self.rehashes, cursor, count, |member, _| {
+            f(member)
+        })
+    }
+}
+
+impl<V> Set<V>
+where
+    V: HeapSize,
+{
+    /// Heap bytes, the members' own allocations estimated from `samples` of them.
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        table_size::<(V, ())>(self.inner.capacity())
+            + sampled(
+                self.inner.keys().map(HeapSize::heap_size),
+                self.inner.len(),
+                samples,
+            )
     }
 }
 
@@ -33,7 +64,16 @@ where
     {
         items
             .into_iter()
-            .map(|item| if self.inner.insert(item) { 1 } else { 0 })
+            .map(|item| {
+                if self.inner.contains_key(&item) {
+                    return 0;
+                }
+                if may_rehash(&self.inner) {
+                    self.rehashes += 1;
+                }
+                self.inner.insert(item, ());
+                1
+            })
             .sum()
     }
 }
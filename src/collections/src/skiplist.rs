@@ -0,0 +1,349 @@
+use std::cmp::Ordering;
+use std::mem::size_of;
+
+const MAX_LEVEL: usize = 32;
+const HEAD: usize = 0;
+
+#[derive(Clone)]
+struct Level {
+    forward: Option<usize>,
+    span: usize,
+}
+
+#[derive(Clone)]
+struct Node<V> {
+    member: Option<V>,
+    score: f64,
+    backward: Option<usize>,
+    levels: Vec<Level>,
+}
+
+impl<V> Node<V> {
+    fn new(member: Option<V>, score: f64, level: usize) -> Self {
+        Self {
+            member,
+            score,
+            backward: None,
+            levels: (0..level)
+                .map(|_| Level {
+                    forward: None,
+                    span: 0,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Skiplist ordered by `(score, member)`, the same layout redis uses for `zset`.
+///
+/// Nodes live in an arena and link to each other by index, every forward link
+/// also records how many nodes it jumps over so that rank queries are `O(log n)`.
+#[derive(Clone)]
+pub(crate) struct SkipList<V> {
+    nodes: Vec<Node<V>>,
+    free: Vec<usize>,
+    tail: Option<usize>,
+    level: usize,
+    len: usize,
+    seed: u64,
+}
+
+impl<V> SkipList<V>
+where
+    V: Ord,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: vec![Node::new(None, 0f64, MAX_LEVEL)],
+            free: Vec::new(),
+            tail: None,
+            level: 1,
+            len: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Heap bytes of the nodes, leaving out what the members own.
+    pub(crate) fn memory_usage(&self) -> usize {
+        // with a 1/4 chance to grow a level, nodes have 4/3 levels on average
+        self.nodes.capacity() * size_of::<Node<V>>()
+            + self.free.capacity() * size_of::<usize>()
+            + self.len * 4 / 3 * size_of::<Level>()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        loop {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 7;
+            self.seed ^= self.seed << 17;
+            if level < MAX_LEVEL && (self.seed & 0xffff) < 0xffff / 4 {
+                level += 1;
+            } else {
+                return level;
+            }
+        }
+    }
+
+    fn compare(&self, index: usize, score: f64, member: &V) -> Ordering {
+        let node = &self.nodes[index];
+        match node.score.partial_cmp(&score) {
+            Some(Ordering::Equal) | None => node.member.as_ref().unwrap().cmp(member),
+            Some(ordering) => ordering,
+        }
+    }
+
+    fn forward(&self, index: usize, level: usize) -> Option<usize> {
+        self.nodes[index].levels[level].forward
+    }
+
+    fn span(&self, index: usize, level: usize) -> usize {
+        self.nodes[index].levels[level].span
+    }
+
+    fn alloc(&mut self, member: V, score: f64, level: usize) -> usize {
+        let node = Node::new(Some(member), score, level);
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, score: f64, member: V) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0usize; MAX_LEVEL];
+
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            while let Some(next) = self.forward(x, i) {
+                if self.compare(next, score, &member) == Ordering::Less {
+                    rank[i] += self.span(x, i);
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+            update[i] = x;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let node = self.alloc(member, score, level);
+        for i in 0..level {
+            let prev = update[i];
+            let passed = rank[0] - rank[i];
+            self.nodes[node].levels[i].forward = self.forward(prev, i);
+            self.nodes[node].levels[i].span = self.span(prev, i) - passed;
+            self.nodes[prev].levels[i].forward = Some(node);
+            self.nodes[prev].levels[i].span = passed + 1;
+        }
+        for (i, prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[*prev].levels[i].span += 1;
+        }
+
+        self.nodes[node].backward = if update[0] == HEAD {
+            None
+        } else {
+            Some(update[0])
+        };
+        match self.forward(node, 0) {
+            Some(next) => self.nodes[next].backward = Some(node),
+            None => self.tail = Some(node),
+        }
+        self.len += 1;
+    }
+
+    fn unlink(&mut self, index: usize, update: &[usize; MAX_LEVEL]) -> V {
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.forward(prev, i) == Some(index) {
+                self.nodes[prev].levels[i].span += self.span(index, i);
+                self.nodes[prev].levels[i].span -= 1;
+                self.nodes[prev].levels[i].forward = self.forward(index, i);
+            } else {
+                self.nodes[prev].levels[i].span -= 1;
+            }
+        }
+
+        let backward = self.nodes[index].backward;
+        match self.forward(index, 0) {
+            Some(next) => self.nodes[next].backward = backward,
+            None => self.tail = backward,
+        }
+
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+
+        self.free.push(index);
+        let node = &mut self.nodes[index];
+        node.levels.clear();
+        node.member.take().unwrap()
+    }
+
+    pub(crate) fn remove(&mut self, score: f64, member: &V) -> Option<V> {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if self.compare(next, score, member) == Ordering::Less {
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+            update[i] = x;
+        }
+
+        match self.forward(x, 0) {
+            Some(next) if self.compare(next, score, member) == Ordering::Equal => {
+                Some(self.unlink(next, &update))
+            }
+            _ => None,
+        }
+    }
+
+    /// 1-based rank of the element, `None` when it is not in the list.
+    pub(crate) fn rank(&self, score: f64, member: &V) -> Option<usize> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if self.compare(next, score, member) != Ordering::Greater {
+                    rank += self.span(x, i);
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+            if x != HEAD && self.compare(x, score, member) == Ordering::Equal {
+                return Some(rank);
+            }
+        }
+        None
+    }
+
+    /// Node at the 1-based `rank`.
+    pub(crate) fn by_rank(&self, rank: usize) -> Option<usize> {
+        let mut traversed = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if traversed + self.span(x, i) <= rank {
+                    traversed += self.span(x, i);
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+            if traversed == rank {
+                return if x == HEAD { None } else { Some(x) };
+            }
+        }
+        None
+    }
+
+    /// First node for which `is_before` no longer holds.
+    pub(crate) fn first_where<F>(&self, is_before: F) -> Option<usize>
+    where
+        F: Fn(f64, &V) -> bool,
+    {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if is_before(self.score(next), self.member(next)) {
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+        }
+        self.forward(x, 0)
+    }
+
+    /// Last node for which `is_within` still holds.
+    pub(crate) fn last_where<F>(&self, is_within: F) -> Option<usize>
+    where
+        F: Fn(f64, &V) -> bool,
+    {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(x, i) {
+                if is_within(self.score(next), self.member(next)) {
+                    x = next;
+                } else {
+                    break;
+                }
+            }
+        }
+        if x == HEAD {
+            None
+        } else {
+            Some(x)
+        }
+    }
+
+    pub(crate) fn first(&self) -> Option<usize> {
+        self.forward(HEAD, 0)
+    }
+
+    pub(crate) fn last(&self) -> Option<usize> {
+        self.tail
+    }
+
+    pub(crate) fn next(&self, index: usize) -> Option<usize> {
+        self.forward(index, 0)
+    }
+
+    pub(crate) fn prev(&self, index: usize) -> Option<usize> {
+        self.nodes[index].backward
+    }
+
+    pub(crate) fn score(&self, index: usize) -> f64 {
+        self.nodes[index].score
+    }
+
+    pub(crate) fn member(&self, index: usize) -> &V {
+        self.nodes[index].member.as_ref().unwrap()
+    }
+}
+
+#[test]
+fn test_skiplist_rank() {
+    let mut list = SkipList::new();
+    for i in (0..100).rev() {
+        list.insert(i as f64, i);
+    }
+    assert_eq!(list.len(), 100);
+    assert_eq!(list.rank(0f64, &0), Some(1));
+    assert_eq!(list.rank(57f64, &57), Some(58));
+    assert_eq!(list.rank(57f64, &58), None);
+    assert_eq!(list.by_rank(100).map(|index| *list.member(index)), Some(99));
+
+    for i in (0..100).step_by(2) {
+        assert_eq!(list.remove(i as f64, &i), Some(i));
+    }
+    assert_eq!(list.len(), 50);
+    assert_eq!(list.rank(57f64, &57), Some(29));
+    assert_eq!(list.by_rank(1).map(|index| *list.member(index)), Some(1));
+    assert_eq!(list.last().map(|index| *list.member(index)), Some(99));
+}
@@ -0,0 +1,570 @@
+use crate::memory::{sampled, table_size, HeapSize};
+use crate::scan::{may_rehash, scan_table};
+use crate::skiplist::SkipList;
+use hashbrown::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::hash::Hash;
+use std::iter::IntoIterator;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ParseRangeError {
+    Score,
+    Lex,
+}
+
+impl Display for ParseRangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Score => write!(f, "min or max is not a float"),
+            Self::Lex => write!(f, "min or max not valid string range item"),
+        }
+    }
+}
+
+impl Error for ParseRangeError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreBound {
+    value: f64,
+    exclusive: bool,
+}
+
+impl ScoreBound {
+    pub fn new(value: f64, exclusive: bool) -> Self {
+        Self { value, exclusive }
+    }
+}
+
+impl FromStr for ScoreBound {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, exclusive) = match s.strip_prefix('(') {
+            Some(value) => (value, true),
+            None => (s, false),
+        };
+        let value = f64::from_str(value).map_err(|_| ParseRangeError::Score)?;
+        if value.is_nan() {
+            return Err(ParseRangeError::Score);
+        }
+        Ok(Self { value, exclusive })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreRange {
+    min: ScoreBound,
+    max: ScoreBound,
+}
+
+impl ScoreRange {
+    pub fn new(min: ScoreBound, max: ScoreBound) -> Self {
+        Self { min, max }
+    }
+
+    fn gte_min(&self, score: f64) -> bool {
+        if self.min.exclusive {
+            score > self.min.value
+        } else {
+            score >= self.min.value
+        }
+    }
+
+    fn lte_max(&self, score: f64) -> bool {
+        if self.max.exclusive {
+            score < self.max.value
+        } else {
+            score <= self.max.value
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min.value > self.max.value
+            || (self.min.value == self.max.value && (self.min.exclusive || self.max.exclusive))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LexBound<V> {
+    Min,
+    Max,
+    Inclusive(V),
+    Exclusive(V),
+}
+
+impl<V> FromStr for LexBound<V>
+where
+    V: From<String>,
+{
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(Self::Min),
+            "+" => Ok(Self::Max),
+            _ => {
+                if let Some(value) = s.strip_prefix('[') {
+                    Ok(Self::Inclusive(V::from(value.to_string())))
+                } else if let Some(value) = s.strip_prefix('(') {
+                    Ok(Self::Exclusive(V::from(value.to_string())))
+                } else {
+                    Err(ParseRangeError::Lex)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LexRange<V> {
+    min: LexBound<V>,
+    max: LexBound<V>,
+}
+
+impl<V> LexRange<V>
+where
+    V: Ord,
+{
+    pub fn new(min: LexBound<V>, max: LexBound<V>) -> Self {
+        Self { min, max }
+    }
+
+    fn gte_min(&self, member: &V) -> bool {
+        match self.min {
+            LexBound::Min => true,
+            LexBound::Max => false,
+            LexBound::Inclusive(ref min) => member >= min,
+            LexBound::Exclusive(ref min) => member > min,
+        }
+    }
+
+    fn lte_max(&self, member: &V) -> bool {
+        match self.max {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(ref max) => member <= max,
+            LexBound::Exclusive(ref max) => member < max,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match (&self.min, &self.max) {
+            (LexBound::Max, _) | (_, LexBound::Min) => true,
+            (LexBound::Min, _) | (_, LexBound::Max) => false,
+            (LexBound::Inclusive(min), LexBound::Inclusive(max)) => min > max,
+            (LexBound::Inclusive(min), LexBound::Exclusive(max))
+            | (LexBound::Exclusive(min), LexBound::Inclusive(max))
+            | (LexBound::Exclusive(min), LexBound::Exclusive(max)) => min >= max,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RangeSpec<V> {
+    Rank(i64, i64),
+    Score(ScoreRange),
+    Lex(LexRange<V>),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AddFlags {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddResult {
+    Added(f64),
+    Updated(f64),
+    Unchanged(f64),
+    Ignored,
+    NaN,
+}
+
+#[derive(Clone)]
+pub struct SortedSet<V> {
+    dict: HashMap<V, f64>,
+    list: SkipList<V>,
+    /// Times the members may have moved inside `dict`, for `ZSCAN`.
+    rehashes: u64,
+}
+
+impl<V> Default for SortedSet<V>
+where
+    V: Hash + Eq + Ord + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> SortedSet<V>
+where
+    V: Hash + Eq + Ord + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            dict: HashMap::new(),
+            list: SkipList::new(),
+            rehashes: 0,
+        }
+    }
+
+    pub fn zcard(&self) -> usize {
+        self.dict.len()
+    }
+
+    pub fn score(&self, member: &V) -> Option<f64> {
+        self.dict.get(member).copied()
+    }
+
+    /// Heap bytes, the members' own allocations estimated from `samples` of them and
+    /// counted once though the dict and the skiplist both hold them.
+    pub fn memory_usage(&self, samples: usize) -> usize
+    where
+        V: HeapSize,
+    {
+        table_size::<(V, f64)>(self.dict.capacity())
+            + self.list.memory_usage()
+            + sampled(
+                self.dict.keys().map(HeapSize::heap_size),
+                self.dict.len(),
+                samples,
+            )
+    }
+
+    /// Hands `f` at most `count` members with their score from `cursor`, see `scan_table`.
+    pub fn scan<F>(&self, cursor: u64, count: usize, mut f: F) -> u64
+    where
+        F: FnMut(&V, f64),
+    {
+        scan_table(&self.dict, self.rehashes, cursor, count, |member, score| {
+            f(member, *score)
+        })
+    }
+
+    pub fn add(&mut self, member: V, score: f64, flags: AddFlags) -> AddResult {
+        self.upsert(member, |_| score, flags)
+    }
+
+    pub fn incr(&mut self, member: V, increment: f64, flags: AddFlags) -> AddResult {
+        self.upsert(member, |current| current + increment, flags)
+    }
+
+    fn upsert<F>(&mut self, member: V, score: F, flags: AddFlags) -> AddResult
+    where
+        F: Fn(f64) -> f64,
+    {
+        match self.dict.get(&member).copied() {
+            Some(current) => {
+                if flags.nx {
+                    return AddResult::Ignored;
+                }
+                let score = score(current);
+                if score.is_nan() {
+                    return AddResult::NaN;
+                }
+                if (flags.lt && score >= current) || (flags.gt && score <= current) {
+                    return AddResult::Ignored;
+                }
+                if score == current {
+                    return AddResult::Unchanged(score);
+                }
+                let member = self.list.remove(current, &member).unwrap();
+                self.list.insert(score, member.clone());
+                self.dict.insert(member, score);
+                AddResult::Updated(score)
+            }
+            None => {
+                if flags.xx {
+                    return AddResult::Ignored;
+                }
+                let score = score(0f64);
+                if score.is_nan() {
+                    return AddResult::NaN;
+                }
+                if may_rehash(&self.dict) {
+                    self.rehashes += 1;
+                }
+                self.list.insert(score, member.clone());
+                self.dict.insert(member, score);
+                AddResult::Added(score)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, member: &V) -> bool {
+        match self.dict.remove(member) {
+            Some(score) => {
+                self.list.remove(score, member);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn zrem<'a, I>(&mut self, members: I) -> usize
+    where
+        I: IntoIterator<Item = &'a V>,
+        V: 'a,
+    {
+        members
+            .into_iter()
+            .filter(|member| self.remove(member))
+            .count()
+    }
+
+    /// 0-based rank of `member`, counted from the highest score when `rev` is set.
+    pub fn rank(&self, member: &V, rev: bool) -> Option<usize> {
+        let score = self.dict.get(member)?;
+        let rank = self.list.rank(*score, member)?;
+        if rev {
+            Some(self.list.len() - rank)
+        } else {
+            Some(rank - 1)
+        }
+    }
+
+    fn rank_range(&self, mut start: i64, mut stop: i64) -> Option<(usize, usize)> {
+        let len = self.list.len() as i64;
+        if start < 0 {
+            start += len;
+        }
+        if stop < 0 {
+            stop += len;
+        }
+        if start < 0 {
+            start = 0;
+        }
+        if start > stop || start >= len {
+            return None;
+        }
+        if stop >= len {
+            stop = len - 1;
+        }
+        Some((start as usize, stop as usize))
+    }
+
+    fn first_in_score(&self, range: &ScoreRange) -> Option<usize> {
+        if range.is_empty() {
+            return None;
+        }
+        self.list
+            .first_where(|score, _| !range.gte_min(score))
+            .filter(|index| range.lte_max(self.list.score(*index)))
+    }
+
+    fn last_in_score(&self, range: &ScoreRange) -> Option<usize> {
+        if range.is_empty() {
+            return None;
+        }
+        self.list
+            .last_where(|score, _| range.lte_max(score))
+            .filter(|index| range.gte_min(self.list.score(*index)))
+    }
+
+    fn first_in_lex(&self, range: &LexRange<V>) -> Option<usize> {
+        if range.is_empty() {
+            return None;
+        }
+        self.list
+            .first_where(|_, member| !range.gte_min(member))
+            .filter(|index| range.lte_max(self.list.member(*index)))
+    }
+
+    fn last_in_lex(&self, range: &LexRange<V>) -> Option<usize> {
+        if range.is_empty() {
+            return None;
+        }
+        self.list
+            .last_where(|_, member| range.lte_max(member))
+            .filter(|index| range.gte_min(self.list.member(*index)))
+    }
+
+    fn walk<P>(
+        &self,
+        start: Option<usize>,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+        within: P,
+    ) -> Vec<(V, f64)>
+    where
+        P: Fn(usize) -> bool,
+    {
+        let mut result = Vec::new();
+        let mut cursor = start;
+        let mut skip = offset;
+        while let Some(index) = cursor {
+            if !within(index) || count.is_some_and(|count| result.len() >= count) {
+                break;
+            }
+            if skip > 0 {
+                skip -= 1;
+            } else {
+                result.push((self.list.member(index).clone(), self.list.score(index)));
+            }
+            cursor = if rev {
+                self.list.prev(index)
+            } else {
+                self.list.next(index)
+            };
+        }
+        result
+    }
+
+    /// Members in `spec`, walking from the highest score when `rev` is set and
+    /// skipping `offset` members before collecting up to `count` of them.
+    pub fn range(
+        &self,
+        spec: &RangeSpec<V>,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(V, f64)> {
+        match spec {
+            RangeSpec::Rank(start, stop) => match self.rank_range(*start, *stop) {
+                Some((start, stop)) => {
+                    let first = if rev {
+                        self.list.by_rank(self.list.len() - start)
+                    } else {
+                        self.list.by_rank(start + 1)
+                    };
+                    let total = stop - start + 1;
+                    let count = Some(count.map_or(total, |count| count.min(total)));
+                    self.walk(first, rev, offset, count, |_| true)
+                }
+                None => Vec::new(),
+            },
+            RangeSpec::Score(range) => {
+                if rev {
+                    let first = self.last_in_score(range);
+                    self.walk(first, rev, offset, count, |index| {
+                        range.gte_min(self.list.score(index))
+                    })
+                } else {
+                    let first = self.first_in_score(range);
+                    self.walk(first, rev, offset, count, |index| {
+                        range.lte_max(self.list.score(index))
+                    })
+                }
+            }
+            RangeSpec::Lex(range) => {
+                if rev {
+                    let first = self.last_in_lex(range);
+                    self.walk(first, rev, offset, count, |index| {
+                        range.gte_min(self.list.member(index))
+                    })
+                } else {
+                    let first = self.first_in_lex(range);
+                    self.walk(first, rev, offset, count, |index| {
+                        range.lte_max(self.list.member(index))
+                    })
+                }
+            }
+        }
+    }
+
+    fn count_between(&self, first: Option<usize>, last: Option<usize>) -> usize {
+        match (first, last) {
+            (Some(first), Some(last)) => {
+                let first = self
+                    .list
+                    .rank(self.list.score(first), self.list.member(first))
+                    .unwrap();
+                let last = self
+                    .list
+                    .rank(self.list.score(last), self.list.member(last))
+                    .unwrap();
+                if last >= first {
+                    last - first + 1
+                } else {
+                    0
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn count(&self, range: &ScoreRange) -> usize {
+        self.count_between(self.first_in_score(range), self.last_in_score(range))
+    }
+
+    pub fn lex_count(&self, range: &LexRange<V>) -> usize {
+        self.count_between(self.first_in_lex(range), self.last_in_lex(range))
+    }
+
+    pub fn remove_range(&mut self, spec: &RangeSpec<V>) -> usize {
+        let removed = self.range(spec, false, 0, None);
+        for (member, score) in removed.iter() {
+            self.dict.remove(member);
+            self.list.remove(*score, member);
+        }
+        removed.len()
+    }
+
+    /// Removes up to `count` members with the lowest scores, or the highest ones when `max` is set.
+    pub fn pop(&mut self, count: usize, max: bool) -> Vec<(V, f64)> {
+        let mut result = Vec::with_capacity(count.min(self.list.len()));
+        while result.len() < count {
+            let index = if max {
+                self.list.last()
+            } else {
+                self.list.first()
+            };
+            let (member, score) = match index {
+                Some(index) => (self.list.member(index).clone(), self.list.score(index)),
+                None => break,
+            };
+            self.dict.remove(&member);
+            self.list.remove(score, &member);
+            result.push((member, score));
+        }
+        result
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&V, f64)> {
+        self.dict.iter().map(|(member, score)| (member, *score))
+    }
+}
+
+#[test]
+fn test_sorted_set_range() {
+    let mut set = SortedSet::new();
+    for (member, score) in [("a", 1f64), ("b", 2f64), ("c", 3f64), ("d", 3f64)] {
+        set.add(member, score, AddFlags::default());
+    }
+
+    fn members(list: Vec<(&'static str, f64)>) -> Vec<&'static str> {
+        list.into_iter().map(|(member, _)| member).collect()
+    }
+
+    assert_eq!(
+        members(set.range(&RangeSpec::Rank(0, -1), false, 0, None)),
+        vec!["a", "b", "c", "d"]
+    );
+    assert_eq!(
+        members(set.range(&RangeSpec::Rank(0, 1), true, 0, None)),
+        vec!["d", "c"]
+    );
+
+    let range = ScoreRange::new("(1".parse().unwrap(), "3".parse().unwrap());
+    assert_eq!(set.count(&range), 3);
+    assert_eq!(
+        members(set.range(&RangeSpec::Score(range), true, 1, Some(1))),
+        vec!["c"]
+    );
+
+    assert_eq!(
+        set.incr("a", 10f64, AddFlags::default()),
+        AddResult::Updated(11f64)
+    );
+    assert_eq!(set.rank(&"a", false), Some(3));
+    assert_eq!(set.rank(&"a", true), Some(0));
+    assert_eq!(members(set.pop(2, false)), vec!["b", "c"]);
+    assert_eq!(set.zcard(), 2);
+}
@@ -0,0 +1,784 @@
+use crate::consumer_group::{ClaimOptions, ConsumerGroup};
+use crate::memory::{sampled, HeapSize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::mem::size_of;
+use std::ops::Bound;
+use std::str::FromStr;
+
+/// Entries are trimmed in chunks of this size with `~`, as redis only drops whole nodes.
+const NODE_ENTRIES: usize = 100;
+
+pub enum StreamError {
+    Invalid,
+    TooSmall,
+    Zero,
+    NoKey,
+    NoStream,
+    NoGroup { key: String, group: String },
+    BusyGroup,
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Invalid => write!(
+                f,
+                "ERR Invalid stream ID specified as stream command argument"
+            ),
+            Self::TooSmall => write!(
+                f,
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+            ),
+            Self::Zero => write!(f, "ERR The ID specified in XADD must be greater than 0-0"),
+            Self::NoKey => write!(f, "ERR no such key"),
+            Self::NoStream => write!(
+                f,
+                "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+            ),
+            Self::NoGroup { key, group } => write!(
+                f,
+                "NOGROUP No such key '{}' or consumer group '{}'",
+                key, group
+            ),
+            Self::BusyGroup => write!(f, "BUSYGROUP Consumer Group name already exists"),
+        }
+    }
+}
+
+impl Debug for StreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Error for StreamError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        Self { ms, seq }
+    }
+
+    /// Parses `<ms>-<seq>`, a bare `<ms>` takes `missing_seq` as its sequence.
+    pub fn parse(s: &str, missing_seq: u64) -> Result<Self, StreamError> {
+        let (ms, seq) = match s.split_once('-') {
+            Some((ms, seq)) => (
+                u64::from_str(ms).map_err(|_| StreamError::Invalid)?,
+                u64::from_str(seq).map_err(|_| StreamError::Invalid)?,
+            ),
+            None => (
+                u64::from_str(s).map_err(|_| StreamError::Invalid)?,
+                missing_seq,
+            ),
+        };
+        Ok(Self { ms, seq })
+    }
+
+    pub fn next(self) -> Option<Self> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(Self { ms: self.ms, seq }),
+            None => self.ms.checked_add(1).map(|ms| Self { ms, seq: 0 }),
+        }
+    }
+
+    pub fn prev(self) -> Option<Self> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(Self { ms: self.ms, seq }),
+            None => self.ms.checked_sub(1).map(|ms| Self { ms, seq: u64::MAX }),
+        }
+    }
+}
+
+impl FromStr for StreamId {
+    type Err = StreamError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, 0)
+    }
+}
+
+impl Display for StreamId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// The ID argument of `XADD`.
+#[derive(Debug, Clone, Copy)]
+pub enum AddId {
+    /// `*`
+    Auto,
+    /// `<ms>-*`
+    Partial(u64),
+    Explicit(StreamId),
+}
+
+impl FromStr for AddId {
+    type Err = StreamError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(Self::Auto);
+        }
+        match s.strip_suffix("-*") {
+            Some(ms) => Ok(Self::Partial(
+                u64::from_str(ms).map_err(|_| StreamError::Invalid)?,
+            )),
+            None => Ok(Self::Explicit(StreamId::parse(s, 0)?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TrimStrategy {
+    MaxLen(usize),
+    MinId(StreamId),
+}
+
+/// `MAXLEN|MINID [=|~] threshold [LIMIT count]` of `XADD` and `XTRIM`.
+#[derive(Debug, Clone, Copy)]
+pub struct Trim {
+    pub strategy: TrimStrategy,
+    pub approximate: bool,
+    pub limit: Option<usize>,
+}
+
+pub type StreamEntry<V> = (StreamId, Vec<V>);
+
+/// An entry read through a consumer group, `None` fields when it was deleted
+/// while still pending.
+pub type GroupEntry<V> = (StreamId, Option<Vec<V>>);
+
+/// `XAUTOCLAIM` result: the next cursor, the claimed entries and the pending
+/// IDs that no longer exist in the stream.
+pub type AutoClaim<V> = (StreamId, Vec<GroupEntry<V>>, Vec<StreamId>);
+
+#[derive(Clone)]
+pub struct Stream<V> {
+    entries: BTreeMap<StreamId, Vec<V>>,
+    last_id: StreamId,
+    max_deleted_id: StreamId,
+    entries_added: u64,
+    groups: BTreeMap<V, ConsumerGroup<V>>,
+}
+
+impl<V> Stream<V>
+where
+    V: Clone + HeapSize,
+{
+    /// Heap bytes, the entries estimated from `samples` of them, B-tree nodes counted
+    /// by their entries.
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        let entries = sampled(
+            self.entries.values().map(|fields| {
+                size_of::<(StreamId, Vec<V>)>()
+                    + fields.capacity() * size_of::<V>()
+                    + fields.iter().map(HeapSize::heap_size).sum::<usize>()
+            }),
+            self.entries.len(),
+            samples,
+        );
+        let groups = self
+            .groups
+            .iter()
+            .map(|(name, group)| {
+                size_of::<(V, ConsumerGroup<V>)>() + name.heap_size() + group.memory_usage()
+            })
+            .sum::<usize>();
+        entries + groups
+    }
+}
+
+impl<V> Stream<V>
+where
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            last_id: StreamId::MIN,
+            max_deleted_id: StreamId::MIN,
+            entries_added: 0,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn max_deleted_id(&self) -> StreamId {
+        self.max_deleted_id
+    }
+
+    pub fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    /// The first ID ever added that is still in the stream.
+    pub fn first_id(&self) -> StreamId {
+        self.entries.keys().next().copied().unwrap_or(StreamId::MIN)
+    }
+
+    pub fn first_entry(&self) -> Option<StreamEntry<V>> {
+        self.entries
+            .iter()
+            .next()
+            .map(|(id, fields)| (*id, fields.clone()))
+    }
+
+    pub fn last_entry(&self) -> Option<StreamEntry<V>> {
+        self.entries
+            .iter()
+            .next_back()
+            .map(|(id, fields)| (*id, fields.clone()))
+    }
+
+    pub fn get(&self, id: &StreamId) -> Option<&Vec<V>> {
+        self.entries.get(id)
+    }
+
+    /// Appends an entry, `fields` holds the field value pairs flattened.
+    pub fn add(&mut self, id: AddId, fields: Vec<V>, now_ms: u64) -> Result<StreamId, StreamError> {
+        let id = match id {
+            AddId::Auto => {
+                if now_ms > self.last_id.ms {
+                    StreamId::new(now_ms, 0)
+                } else {
+                    self.last_id.next().ok_or(StreamError::TooSmall)?
+                }
+            }
+            AddId::Partial(ms) => {
+                if ms > self.last_id.ms {
+                    StreamId::new(ms, 0)
+                } else if ms == self.last_id.ms {
+                    self.last_id.next().ok_or(StreamError::TooSmall)?
+                } else {
+                    return Err(StreamError::TooSmall);
+                }
+            }
+            AddId::Explicit(id) => {
+                if id == StreamId::MIN {
+                    return Err(StreamError::Zero);
+                }
+                if id <= self.last_id {
+                    return Err(StreamError::TooSmall);
+                }
+                id
+            }
+        };
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+        Ok(id)
+    }
+
+    /// Entries between `start` and `end` inclusive, from `end` downwards when `rev` is set.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Vec<StreamEntry<V>> {
+        if start > end {
+            return Vec::new();
+        }
+        let count = count.unwrap_or(usize::MAX);
+        let range = self.entries.range(start..=end);
+        if rev {
+            range
+                .rev()
+                .take(count)
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect()
+        } else {
+            range
+                .take(count)
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect()
+        }
+    }
+
+    /// Entries with an ID greater than `id`, as read by `XREAD`.
+    pub fn after(&self, id: StreamId, count: Option<usize>) -> Vec<StreamEntry<V>> {
+        self.entries
+            .range((Bound::Excluded(id), Bound::Unbounded))
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect()
+    }
+
+    pub fn delete<'a, I>(&mut self, ids: I) -> usize
+    where
+        I: IntoIterator<Item = &'a StreamId>,
+    {
+        let mut deleted = 0;
+        for id in ids {
+            if self.entries.remove(id).is_some() {
+                deleted += 1;
+                if *id > self.max_deleted_id {
+                    self.max_deleted_id = *id;
+                }
+            }
+        }
+        deleted
+    }
+
+    /// Evicts the oldest entries according to `trim`, returning how many were removed.
+    pub fn trim(&mut self, trim: &Trim) -> usize {
+        let mut removable = match trim.strategy {
+            TrimStrategy::MaxLen(max_len) => self.entries.len().saturating_sub(max_len),
+            TrimStrategy::MinId(min_id) => self.entries.range(..min_id).count(),
+        };
+        if trim.approximate {
+            removable -= removable % NODE_ENTRIES;
+        }
+        if let Some(limit) = trim.limit {
+            if limit > 0 {
+                removable = removable.min(limit);
+            }
+        }
+
+        for _ in 0..removable {
+            if let Some((id, _)) = self.entries.pop_first() {
+                if id > self.max_deleted_id {
+                    self.max_deleted_id = id;
+                }
+            }
+        }
+        removable
+    }
+}
+
+impl<V> Stream<V>
+where
+    V: Clone + Ord,
+{
+    pub fn groups(&self) -> impl Iterator<Item = (&V, &ConsumerGroup<V>)> {
+        self.groups.iter()
+    }
+
+    pub fn group(&self, name: &V) -> Option<&ConsumerGroup<V>> {
+        self.groups.get(name)
+    }
+
+    /// `id` of `None` stands for `$`. `false` when the group already exists.
+    pub fn create_group(
+        &mut self,
+        name: V,
+        id: Option<StreamId>,
+        entries_read: Option<u64>,
+    ) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+        let id = id.unwrap_or(self.last_id);
+        let entries_read = entries_read.or_else(|| self.estimate_entries_read(id));
+        self.groups
+            .insert(name, ConsumerGroup::new(id, entries_read));
+        true
+    }
+
+    /// `false` when there is no such group.
+    pub fn set_group_id(
+        &mut self,
+        name: &V,
+        id: Option<StreamId>,
+        entries_read: Option<u64>,
+    ) -> bool {
+        let id = id.unwrap_or(self.last_id);
+        let entries_read = entries_read.or_else(|| self.estimate_entries_read(id));
+        match self.groups.get_mut(name) {
+            Some(group) => {
+                group.last_delivered = id;
+                group.entries_read = entries_read;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn destroy_group(&mut self, name: &V) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// `None` when there is no such group.
+    pub fn create_consumer(&mut self, group: &V, consumer: V, now_ms: u64) -> Option<bool> {
+        self.groups
+            .get_mut(group)
+            .map(|group| group.create_consumer(consumer, now_ms))
+    }
+
+    /// `None` when there is no such group.
+    pub fn delete_consumer(&mut self, group: &V, consumer: &V) -> Option<usize> {
+        self.groups
+            .get_mut(group)
+            .map(|group| group.delete_consumer(consumer))
+    }
+
+    /// `XREADGROUP` on this stream: new entries when `id` is `None` (`>`), or the
+    /// consumer's own pending entries after `id` otherwise.
+    pub fn read_group(
+        &mut self,
+        group: &V,
+        consumer: V,
+        id: Option<StreamId>,
+        count: Option<usize>,
+        noack: bool,
+        now_ms: u64,
+    ) -> Option<Vec<GroupEntry<V>>> {
+        let count = count.filter(|count| *count > 0).unwrap_or(usize::MAX);
+        let last_delivered = self.groups.get(group)?.last_delivered;
+
+        match id {
+            None => {
+                let entries = self.after(last_delivered, Some(count));
+                for (id, _) in entries.iter() {
+                    let entries_read = {
+                        let group = &self.groups[group];
+                        match group.entries_read {
+                            Some(read) if !self.has_tombstones(group.last_delivered, *id) => {
+                                Some(read + 1)
+                            }
+                            _ => self.estimate_entries_read(*id),
+                        }
+                    };
+                    let group = self.groups.get_mut(group)?;
+                    group.last_delivered = *id;
+                    group.entries_read = entries_read;
+                }
+
+                let group = self.groups.get_mut(group)?;
+                let reader = group.touch(consumer.clone(), now_ms);
+                if !entries.is_empty() {
+                    reader.active_ms = Some(now_ms);
+                }
+                if !noack {
+                    for (id, _) in entries.iter() {
+                        group.assign(*id, consumer.clone(), now_ms, 1);
+                    }
+                }
+                Some(
+                    entries
+                        .into_iter()
+                        .map(|(id, fields)| (id, Some(fields)))
+                        .collect(),
+                )
+            }
+            Some(id) => {
+                let group = self.groups.get_mut(group)?;
+                let ids = group
+                    .touch(consumer, now_ms)
+                    .pending_after(id)
+                    .take(count)
+                    .collect::<Vec<StreamId>>();
+                for id in ids.iter() {
+                    if let Some(pending) = group.pending.get_mut(id) {
+                        pending.delivered_ms = now_ms;
+                        pending.delivery_count += 1;
+                    }
+                }
+                Some(
+                    ids.into_iter()
+                        .map(|id| (id, self.entries.get(&id).cloned()))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Acknowledges pending entries, returning how many were pending.
+    pub fn ack<'a, I>(&mut self, group: &V, ids: I) -> usize
+    where
+        I: IntoIterator<Item = &'a StreamId>,
+    {
+        match self.groups.get_mut(group) {
+            Some(group) => ids
+                .into_iter()
+                .filter(|id| group.unassign(id).is_some())
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// `XCLAIM`, `None` when there is no such group.
+    pub fn claim<'a, I>(
+        &mut self,
+        group: &V,
+        consumer: V,
+        min_idle_ms: u64,
+        ids: I,
+        options: &ClaimOptions,
+        now_ms: u64,
+    ) -> Option<Vec<GroupEntry<V>>>
+    where
+        I: IntoIterator<Item = &'a StreamId>,
+    {
+        let entries = &self.entries;
+        let group = self.groups.get_mut(group)?;
+        if let Some(last_id) = options.last_id {
+            if last_id > group.last_delivered {
+                group.last_delivered = last_id;
+            }
+        }
+        let delivered_ms = match (options.idle, options.time) {
+            (Some(idle), _) => now_ms.saturating_sub(idle),
+            (None, Some(time)) => time,
+            (None, None) => now_ms,
+        };
+        group.touch(consumer.clone(), now_ms);
+
+        let mut claimed = Vec::new();
+        for id in ids {
+            let fields = match entries.get(id) {
+                Some(fields) => fields,
+                None => {
+                    // deleted from the stream, there is nothing left to claim
+                    group.unassign(id);
+                    continue;
+                }
+            };
+            let delivery_count = match group.pending.get(id) {
+                Some(pending) => {
+                    if now_ms.saturating_sub(pending.delivered_ms) < min_idle_ms {
+                        continue;
+                    }
+                    pending.delivery_count
+                }
+                None if options.force => 0,
+                None => continue,
+            };
+            let delivery_count = match options.retry_count {
+                Some(retry_count) => retry_count,
+                None if options.just_id => delivery_count,
+                None => delivery_count + 1,
+            };
+            group.assign(*id, consumer.clone(), delivered_ms, delivery_count);
+            claimed.push((*id, (!options.just_id).then(|| fields.clone())));
+        }
+        if !claimed.is_empty() {
+            group.touch(consumer, now_ms).active_ms = Some(now_ms);
+        }
+        Some(claimed)
+    }
+
+    /// `XAUTOCLAIM`, scanning at most ten times `count` pending entries from `start`.
+    /// `None` when there is no such group.
+    #[allow(clippy::too_many_arguments)]
+    pub fn auto_claim(
+        &mut self,
+        group: &V,
+        consumer: V,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        just_id: bool,
+        now_ms: u64,
+    ) -> Option<AutoClaim<V>> {
+        let entries = &self.entries;
+        let group = self.groups.get_mut(group)?;
+        group.touch(consumer.clone(), now_ms);
+
+        let mut attempts = count.saturating_mul(10);
+        let mut claimed = Vec::new();
+        let mut deleted = Vec::new();
+        let mut cursor = group.pending.range(start..).map(|(id, _)| *id).next();
+        while let Some(id) = cursor {
+            if attempts == 0 || claimed.len() >= count {
+                break;
+            }
+            attempts -= 1;
+            cursor = id
+                .next()
+                .and_then(|next| group.pending.range(next..).map(|(id, _)| *id).next());
+
+            let fields = match entries.get(&id) {
+                Some(fields) => fields,
+                None => {
+                    group.unassign(&id);
+                    deleted.push(id);
+                    continue;
+                }
+            };
+            let pending = &group.pending[&id];
+            if now_ms.saturating_sub(pending.delivered_ms) < min_idle_ms {
+                continue;
+            }
+            let delivery_count = if just_id {
+                pending.delivery_count
+            } else {
+                pending.delivery_count + 1
+            };
+            group.assign(id, consumer.clone(), now_ms, delivery_count);
+            claimed.push((id, (!just_id).then(|| fields.clone())));
+        }
+        if !claimed.is_empty() {
+            group.touch(consumer, now_ms).active_ms = Some(now_ms);
+        }
+        Some((cursor.unwrap_or(StreamId::MIN), claimed, deleted))
+    }
+
+    /// Entries in the stream the group has not read yet, `None` when it cannot be
+    /// told because of deletions.
+    pub fn lag(&self, group: &ConsumerGroup<V>) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        match group.entries_read {
+            Some(read) if !self.has_tombstones(group.last_delivered, StreamId::MAX) => {
+                Some(self.entries_added.saturating_sub(read))
+            }
+            _ => self
+                .estimate_entries_read(group.last_delivered)
+                .map(|read| self.entries_added.saturating_sub(read)),
+        }
+    }
+
+    /// Whether an entry between `start` and `end` has been deleted.
+    fn has_tombstones(&self, start: StreamId, end: StreamId) -> bool {
+        if self.entries.is_empty() || self.max_deleted_id == StreamId::MIN {
+            return false;
+        }
+        let first = self.entries.keys().next().copied().unwrap_or(StreamId::MIN);
+        start.max(first) <= self.max_deleted_id && self.max_deleted_id <= end
+    }
+
+    /// How many entries were ever added up to `id`, when it can be told.
+    fn estimate_entries_read(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 || id >= self.last_id {
+            return Some(self.entries_added);
+        }
+        let first = match self.entries.keys().next() {
+            Some(first) => *first,
+            None => return Some(self.entries_added),
+        };
+        if self.max_deleted_id < first {
+            let before = self.entries_added - self.entries.len() as u64;
+            if id < first {
+                return Some(before);
+            }
+            if id == first {
+                return Some(before + 1);
+            }
+        }
+        None
+    }
+}
+
+impl<V> Default for Stream<V>
+where
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_stream_add_and_trim() {
+    let mut stream = Stream::new();
+    let first = stream.add(AddId::Auto, vec!["f", "v"], 5).unwrap();
+    assert_eq!(first, StreamId::new(5, 0));
+    assert_eq!(
+        stream.add(AddId::Auto, vec!["f", "v"], 4).unwrap(),
+        StreamId::new(5, 1)
+    );
+    assert!(stream
+        .add(AddId::Explicit(StreamId::new(5, 1)), vec![], 0)
+        .is_err());
+    assert_eq!(
+        stream.add(AddId::Partial(7), vec!["f", "v"], 0).unwrap(),
+        StreamId::new(7, 0)
+    );
+
+    for ms in 8..258 {
+        stream.add(AddId::Auto, vec!["f", "v"], ms).unwrap();
+    }
+    assert_eq!(stream.len(), 253);
+    let trim = Trim {
+        strategy: TrimStrategy::MaxLen(10),
+        approximate: true,
+        limit: None,
+    };
+    assert_eq!(stream.trim(&trim), 200);
+    assert_eq!(stream.after(StreamId::new(256, 0), None).len(), 1);
+    assert_eq!(
+        stream
+            .range(StreamId::MIN, StreamId::MAX, Some(2), true)
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_stream_consumer_group() {
+    let mut stream = Stream::new();
+    for ms in 1..=3 {
+        stream.add(AddId::Auto, vec!["f", "v"], ms).unwrap();
+    }
+    assert!(stream.create_group("g", Some(StreamId::MIN), None));
+    assert!(!stream.create_group("g", None, None));
+
+    let read = stream
+        .read_group(&"g", "alice", None, Some(2), false, 10)
+        .unwrap();
+    assert_eq!(read.len(), 2);
+    assert_eq!(stream.lag(stream.group(&"g").unwrap()), Some(1));
+    assert_eq!(stream.ack(&"g", [StreamId::new(1, 0)].iter()), 1);
+
+    // alice's history now only holds 2-0, bob takes it over once idle
+    let history = stream
+        .read_group(&"g", "alice", Some(StreamId::MIN), None, false, 20)
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    let options = ClaimOptions::default();
+    let ids = [StreamId::new(2, 0)];
+    assert!(stream
+        .claim(&"g", "bob", 100, ids.iter(), &options, 50)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        stream
+            .claim(&"g", "bob", 100, ids.iter(), &options, 120)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let group = stream.group(&"g").unwrap();
+    let pending = group.pending().next().unwrap().1;
+    assert_eq!((pending.consumer, pending.delivery_count), ("bob", 3));
+    assert_eq!(group.consumer(&"alice").unwrap().pending_len(), 0);
+
+    stream.delete([StreamId::new(2, 0)].iter());
+    let (cursor, claimed, deleted) = stream
+        .auto_claim(&"g", "carol", 0, StreamId::MIN, 10, false, 200)
+        .unwrap();
+    assert_eq!(
+        (cursor, claimed.len(), deleted.len()),
+        (StreamId::MIN, 0, 1)
+    );
+}
@@ -1,6 +1,8 @@
+use crate::memory::HeapSize;
 use std::cmp::Eq;
 use std::iter::IntoIterator;
 
+#[derive(Clone)]
 pub struct Strings<V> {
     inner: V,
 }
@@ -14,3 +16,12 @@ impl<V> Strings<V> {
         &self.inner
     }
 }
+
+impl<V> Strings<V>
+where
+    V: HeapSize,
+{
+    pub fn memory_usage(&self) -> usize {
+        self.inner.heap_size()
+    }
+}
@@ -0,0 +1,73 @@
+use crate::keyspace::random;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Counter a new key starts from, so that it isn't evicted before being used twice.
+const LFU_INIT_VAL: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10f64;
+/// Minutes of idleness taking one off the counter.
+const LFU_DECAY_TIME: u32 = 1;
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Seconds since the first call, the resolution of the access metadata.
+fn clock() -> u32 {
+    START.get_or_init(Instant::now).elapsed().as_secs() as u32
+}
+
+/// When a key was last accessed and how often, for eviction and `OBJECT`. Atomics let
+/// readers holding a shared slot lock record their access.
+pub(crate) struct Access {
+    last: AtomicU32,
+    /// Logarithmic counter as Redis keeps it, see `touch`.
+    frequency: AtomicU8,
+}
+
+impl Access {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: AtomicU32::new(clock()),
+            frequency: AtomicU8::new(LFU_INIT_VAL),
+        }
+    }
+
+    /// Records an access. The counter first loses one per idle period, then grows with
+    /// a probability that falls as it gets higher, so that 255 takes about a million hits.
+    pub(crate) fn touch(&self) {
+        let now = clock();
+        let mut counter = self.decayed(now);
+        if counter < u8::MAX {
+            let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+            let chance = 1f64 / (base * LFU_LOG_FACTOR + 1f64);
+            if (random() as f64 / u64::MAX as f64) < chance {
+                counter += 1;
+            }
+        }
+        self.frequency.store(counter, Ordering::Relaxed);
+        self.last.store(now, Ordering::Relaxed);
+    }
+
+    /// Seconds since the last access.
+    pub(crate) fn idle(&self) -> u32 {
+        clock().saturating_sub(self.last.load(Ordering::Relaxed))
+    }
+
+    /// The access counter, decayed for the time the key has been idle.
+    pub(crate) fn frequency(&self) -> u8 {
+        self.decayed(clock())
+    }
+
+    fn decayed(&self, now: u32) -> u8 {
+        let periods = now.saturating_sub(self.last.load(Ordering::Relaxed)) / 60 / LFU_DECAY_TIME;
+        let counter = self.frequency.load(Ordering::Relaxed);
+        counter.saturating_sub(periods.min(u8::MAX as u32) as u8)
+    }
+}
+
+impl Clone for Access {
+    /// A copy is a new key, accessed for the first time.
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
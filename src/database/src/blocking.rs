@@ -0,0 +1,126 @@
+use crate::key::Key;
+use hashbrown::HashMap;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+struct Waiter {
+    keys: Vec<Key>,
+    notify: Notify,
+    /// The key it was woken for, until it looks at the keys again.
+    woken: Mutex<Option<Key>>,
+}
+
+impl Waiter {
+    /// Always called with the queues locked, so a client is woken by one key only.
+    fn wake(&self, key: &Key) -> bool {
+        let mut woken = self.woken.lock();
+        if woken.is_some() {
+            return false;
+        }
+        *woken = Some(key.clone());
+        self.notify.notify_one();
+        true
+    }
+}
+
+/// Clients parked on keys by the blocking commands, served first come first served per key.
+/// A client stays in the queues until it leaves them, so that it keeps its place when
+/// woken for a key another client emptied first.
+#[derive(Default)]
+pub(crate) struct Blocking {
+    waiters: AtomicUsize,
+    keys: Mutex<HashMap<Key, VecDeque<Arc<Waiter>>>>,
+}
+
+impl Blocking {
+    fn park(&self, waiter: &Arc<Waiter>) {
+        let mut keys = self.keys.lock();
+        for key in waiter.keys.iter() {
+            keys.entry(key.clone())
+                .or_insert_with(VecDeque::new)
+                .push_back(waiter.clone());
+        }
+        self.waiters.fetch_add(1, Ordering::Release);
+    }
+
+    fn unpark(&self, waiter: &Arc<Waiter>) {
+        let mut keys = self.keys.lock();
+        for key in waiter.keys.iter() {
+            if let Some(queue) = keys.get_mut(key) {
+                queue.retain(|other| !Arc::ptr_eq(other, waiter));
+                if queue.is_empty() {
+                    keys.remove(key);
+                }
+            }
+        }
+        self.waiters.fetch_sub(1, Ordering::Release);
+    }
+
+    /// Wakes every client waiting on `key`, for writes that are not consumed by
+    /// the reader such as a stream append.
+    pub(crate) fn ready_all(&self, key: &Key) {
+        if self.waiters.load(Ordering::Acquire) == 0 {
+            return;
+        }
+
+        let keys = self.keys.lock();
+        for waiter in keys.get(key).into_iter().flatten() {
+            waiter.wake(key);
+        }
+    }
+
+    /// Wakes the client that has been waiting on `key` for the longest time, skipping
+    /// those woken already.
+    pub(crate) fn ready(&self, key: &Key) {
+        if self.waiters.load(Ordering::Acquire) == 0 {
+            return;
+        }
+
+        let keys = self.keys.lock();
+        for waiter in keys.get(key).into_iter().flatten() {
+            // a client parked on several keys may already have been woken by another one
+            if waiter.wake(key) {
+                break;
+            }
+        }
+    }
+}
+
+/// A client parked on a set of keys, it leaves the queues when dropped.
+pub struct Blocked {
+    waiter: Arc<Waiter>,
+    blocking: Arc<Blocking>,
+}
+
+impl Blocked {
+    pub(crate) fn new(blocking: Arc<Blocking>, keys: Vec<Key>) -> Self {
+        let waiter = Arc::new(Waiter {
+            keys,
+            notify: Notify::new(),
+            woken: Mutex::new(None),
+        });
+        blocking.park(&waiter);
+        Self { waiter, blocking }
+    }
+
+    /// Resolves once one of the keys may be served. The client looks at them again
+    /// then, and waits from the same place in the queues if another one was faster.
+    pub async fn wait(&self) {
+        self.waiter.notify.notified().await;
+        self.waiter.woken.lock().take();
+    }
+}
+
+impl Drop for Blocked {
+    fn drop(&mut self) {
+        self.blocking.unpark(&self.waiter);
+
+        // woken but gone before acting on it, hand the wakeup to the next client
+        if let Some(key) = self.waiter.woken.lock().take() {
+            self.blocking.ready(&key);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::notify::{KeyspaceEvents, Notifier, Publish};
+use crate::tracking::Tracking;
+use crate::Database;
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The numbered databases of a server. Clients look their database up by index on
+/// every command, so `SWAPDB` takes effect for all of them at once.
+#[derive(Clone)]
+pub struct Databases {
+    inner: Arc<RwLock<Vec<Database>>>,
+    pub(crate) tracking: Arc<Tracking>,
+}
+
+impl Databases {
+    pub fn new(len: usize) -> Self {
+        Self::build(len, None)
+    }
+
+    /// Databases publishing the keyspace events `events` enables through `publish`.
+    pub fn with_events(len: usize, events: KeyspaceEvents, publish: Publish) -> Self {
+        Self::build(len, Some(Arc::new(Notifier { events, publish })))
+    }
+
+    fn build(len: usize, notifier: Option<Arc<Notifier>>) -> Self {
+        let tracking = Arc::new(Tracking::default());
+        let databases = (0..len)
+            .map(|index| Database {
+                index: Arc::new(AtomicUsize::new(index)),
+                notifier: notifier.clone(),
+                tracking: tracking.clone(),
+                ..Database::default()
+            })
+            .collect();
+        Self {
+            inner: Arc::new(RwLock::new(databases)),
+            tracking,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Database> {
+        self.inner.read().get(index).cloned()
+    }
+
+    pub(crate) fn all(&self) -> Vec<Database> {
+        self.inner.read().clone()
+    }
+
+    /// `false` when either index is out of range.
+    pub fn swap(&self, first: usize, second: usize) -> bool {
+        let mut databases = self.inner.write();
+        if first >= databases.len() || second >= databases.len() {
+            return false;
+        }
+        databases.swap(first, second);
+        databases[first].index.store(first, Ordering::Relaxed);
+        databases[second].index.store(second, Ordering::Relaxed);
+        true
+    }
+
+    pub fn flush_all(&self, lazy: bool) {
+        for database in self.inner.read().iter() {
+            database.clear(lazy);
+        }
+        self.tracking.invalidate_all();
+    }
+}
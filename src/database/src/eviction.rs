@@ -0,0 +1,156 @@
+use crate::key::Key;
+use crate::keyspace::random;
+use crate::memory::used_memory;
+use crate::notify::EventClass;
+use crate::value::Value;
+use crate::{Database, Databases, SLOT_LEN};
+use collections::sample_table;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// What to evict once `maxmemory` is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    AllKeysRandom,
+    VolatileLru,
+    VolatileLfu,
+    VolatileRandom,
+    /// Keys set to expire the soonest first.
+    VolatileTtl,
+}
+
+#[derive(Debug)]
+pub struct ParsePolicyError;
+
+impl Display for ParsePolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "unknown maxmemory policy")
+    }
+}
+
+impl std::error::Error for ParsePolicyError {}
+
+impl FromStr for EvictionPolicy {
+    type Err = ParsePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "noeviction" => Ok(Self::NoEviction),
+            "allkeys-lru" => Ok(Self::AllKeysLru),
+            "allkeys-lfu" => Ok(Self::AllKeysLfu),
+            "allkeys-random" => Ok(Self::AllKeysRandom),
+            "volatile-lru" => Ok(Self::VolatileLru),
+            "volatile-lfu" => Ok(Self::VolatileLfu),
+            "volatile-random" => Ok(Self::VolatileRandom),
+            "volatile-ttl" => Ok(Self::VolatileTtl),
+            _ => Err(ParsePolicyError),
+        }
+    }
+}
+
+impl EvictionPolicy {
+    /// How good a candidate `value` is, the higher the sooner it goes. `None` when the
+    /// policy never evicts it.
+    fn score(&self, value: &Value<Arc<String>>) -> Option<u64> {
+        let volatile = value.expire_at().is_some();
+        match self {
+            Self::NoEviction => None,
+            Self::AllKeysLru => Some(value.idle_time() as u64),
+            Self::AllKeysLfu => Some(u8::MAX as u64 - value.frequency() as u64),
+            Self::AllKeysRandom => Some(random()),
+            Self::VolatileLru if volatile => Some(value.idle_time() as u64),
+            Self::VolatileLfu if volatile => Some(u8::MAX as u64 - value.frequency() as u64),
+            Self::VolatileRandom if volatile => Some(random()),
+            Self::VolatileTtl => value.expire_at().map(|expire_at| u64::MAX - expire_at),
+            _ => None,
+        }
+    }
+}
+
+impl Database {
+    /// The best candidate among up to `samples` keys read from a random position of a
+    /// random slot, moving on to the next slots while they hold nothing to evict.
+    fn sample(&self, policy: EvictionPolicy, samples: usize) -> Option<(u64, Key)> {
+        let first = random() as usize;
+        for offset in 0..SLOT_LEN as usize {
+            let map = self.slots[(first + offset) % SLOT_LEN as usize].read();
+            let mut best: Option<(u64, Key)> = None;
+            sample_table(&map, random() as usize, samples, |key, value| {
+                if let Some(score) = policy.score(value) {
+                    if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                        best = Some((score, key.clone()));
+                    }
+                }
+            });
+            if best.is_some() {
+                return best;
+            }
+        }
+        None
+    }
+}
+
+impl Databases {
+    /// Evicts keys until the memory in use is back under `maxmemory`, `false` when the
+    /// policy finds nothing left to evict.
+    pub fn evict(&self, maxmemory: usize, policy: EvictionPolicy, samples: usize) -> bool {
+        if policy == EvictionPolicy::NoEviction {
+            return used_memory() <= maxmemory;
+        }
+        while used_memory() > maxmemory {
+            let databases = self.all();
+            let best = databases
+                .iter()
+                .enumerate()
+                .filter_map(|(index, db)| {
+                    db.sample(policy, samples)
+                        .map(|(score, key)| (score, index, key))
+                })
+                .max_by_key(|(score, _, _)| *score);
+            let (_, index, key) = match best {
+                Some(best) => best,
+                None => return false,
+            };
+            let db = &databases[index];
+            // dropped once the slot is unlocked
+            let value = db.slots[Database::find_point(key.as_bytes())]
+                .write()
+                .remove(&key);
+            if value.is_some() {
+                db.notify(EventClass::Evicted, "evicted", &key);
+            }
+            drop(value);
+        }
+        true
+    }
+}
+
+#[test]
+fn test_sample() {
+    let s = String::from;
+    let mut db = Database::default();
+    assert!(db.sample(EvictionPolicy::AllKeysRandom, 5).is_none());
+    db.set(s("persistent"), s("1"), None, None);
+    assert!(db.sample(EvictionPolicy::NoEviction, 5).is_none());
+    assert!(db.sample(EvictionPolicy::VolatileTtl, 5).is_none());
+    assert!(db.sample(EvictionPolicy::VolatileLru, 5).is_none());
+    assert!(
+        db.sample(EvictionPolicy::AllKeysLru, 5)
+            .unwrap()
+            .1
+            .as_bytes()
+            == b"persistent"
+    );
+    db.set(s("volatile"), s("1"), Some(10), None);
+    assert!(
+        db.sample(EvictionPolicy::VolatileTtl, 5)
+            .unwrap()
+            .1
+            .as_bytes()
+            == b"volatile"
+    );
+}
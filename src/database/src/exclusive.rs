@@ -0,0 +1,84 @@
+use crate::slot::ExclusiveGuard;
+use crate::{Database, Databases, SLOT_LEN};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+/// Slots held for a transaction, other clients wait to reach them until it is dropped.
+/// The thread that took them still goes through, so the commands of the transaction
+/// must run on it without yielding, and it must be dropped there too: from any other
+/// thread the slots would stay open to the one that took them.
+pub struct Exclusive {
+    /// In locking order, released the other way around.
+    guards: Vec<ExclusiveGuard>,
+    /// Keeps the slots where they are while held.
+    _slots: Vec<Database>,
+    owner: ThreadId,
+}
+
+impl Exclusive {
+    /// Takes the slots of the keys, each in the database it goes with.
+    pub fn keys<I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = (Database, String)>,
+    {
+        Self::lock(
+            keys.into_iter()
+                .map(|(db, key)| {
+                    let point = Database::find_point(key.as_bytes());
+                    (db, point)
+                })
+                .collect(),
+        )
+    }
+
+    /// Takes every slot of every database.
+    pub fn all(databases: &Databases) -> Self {
+        Self::lock(
+            databases
+                .all()
+                .into_iter()
+                .flat_map(|db| (0..SLOT_LEN as usize).map(move |point| (db.clone(), point)))
+                .collect(),
+        )
+    }
+
+    fn lock(mut slots: Vec<(Database, usize)>) -> Self {
+        // in the order `write_pair` takes two slots in
+        let order = |(db, point): &(Database, usize)| (Arc::as_ptr(&db.slots) as usize, *point);
+        slots.sort_by_key(order);
+        slots.dedup_by_key(|slot| order(slot));
+        // a script run by `EXEC` finds some of them held already
+        slots.retain(|(db, point)| !db.slots[*point].held());
+        let guards = slots
+            .iter()
+            .map(|(db, point)| db.slots[*point].lock_exclusive())
+            .collect();
+        let mut databases = slots.into_iter().map(|(db, _)| db).collect::<Vec<_>>();
+        databases.dedup_by(|db, other| Arc::ptr_eq(&db.slots, &other.slots));
+        Self {
+            guards,
+            _slots: databases,
+            owner: thread::current().id(),
+        }
+    }
+}
+
+impl Database {
+    /// Whether this thread holds the slot of `key` through an `Exclusive`.
+    pub fn holds(&self, key: &str) -> bool {
+        self.slots[Database::find_point(key.as_bytes())].held()
+    }
+}
+
+impl Drop for Exclusive {
+    fn drop(&mut self) {
+        debug_assert_eq!(
+            thread::current().id(),
+            self.owner,
+            "transaction slots released from another thread"
+        );
+        while let Some(guard) = self.guards.pop() {
+            drop(guard);
+        }
+    }
+}
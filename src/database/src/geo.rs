@@ -0,0 +1,225 @@
+use crate::key::Key;
+use crate::notify::EventClass;
+use crate::value::Value;
+use crate::{Database, Error, TypeError};
+use collections::{
+    AddFlags, GeoPoint, GeoShape, GeoUnit, RangeSpec, ScoreBound, ScoreRange, SortedSet,
+};
+use std::cmp::Ordering;
+use std::convert::Into;
+use std::iter::IntoIterator;
+use std::sync::Arc;
+
+/// Where `GEOSEARCH` is centered.
+pub enum GeoOrigin {
+    Member(Arc<String>),
+    Point(GeoPoint),
+}
+
+pub struct GeoSearch {
+    pub origin: GeoOrigin,
+    pub shape: GeoShape,
+    /// `Some(true)` for `DESC`, `Some(false)` for `ASC`.
+    pub descending: Option<bool>,
+    pub count: Option<usize>,
+    /// Stops at the first `count` matches instead of the closest ones.
+    pub any: bool,
+}
+
+/// A member found by `GEOSEARCH`.
+pub struct GeoMatch {
+    pub member: Arc<String>,
+    /// In meters.
+    pub distance: f64,
+    pub score: f64,
+    pub point: GeoPoint,
+}
+
+fn search(set: &SortedSet<Arc<String>>, search: &GeoSearch) -> Result<Vec<GeoMatch>, Error> {
+    let center = match &search.origin {
+        GeoOrigin::Member(member) => {
+            GeoPoint::from_score(set.score(member).ok_or(Error::NoMember)?)
+        }
+        GeoOrigin::Point(point) => *point,
+    };
+
+    let mut matches = Vec::new();
+    'cells: for (min, max) in search.shape.score_ranges(&center) {
+        let range = ScoreRange::new(ScoreBound::new(min, false), ScoreBound::new(max, true));
+        for (member, score) in set.range(&RangeSpec::Score(range), false, 0, None) {
+            let point = GeoPoint::from_score(score);
+            if let Some(distance) = search.shape.distance(&center, &point) {
+                matches.push(GeoMatch {
+                    member,
+                    distance,
+                    score,
+                    point,
+                });
+                if search.any && Some(matches.len()) == search.count {
+                    break 'cells;
+                }
+            }
+        }
+    }
+
+    // COUNT alone returns the closest members
+    let descending = match search.descending {
+        Some(descending) => Some(descending),
+        None if search.count.is_some() && !search.any => Some(false),
+        None => None,
+    };
+    if let Some(descending) = descending {
+        matches.sort_by(|a, b| {
+            let ordering = a
+                .distance
+                .partial_cmp(&b.distance)
+                .unwrap_or(Ordering::Equal);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    if let Some(count) = search.count {
+        matches.truncate(count);
+    }
+    Ok(matches)
+}
+
+impl Database {
+    /// Returns how many members were added and how many existing ones moved.
+    pub fn geoadd<K, V, I>(
+        &mut self,
+        key: K,
+        members: I,
+        flags: AddFlags,
+    ) -> Result<(usize, usize), Error>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = (GeoPoint, V)>,
+        V: Into<Arc<String>>,
+    {
+        let members = members
+            .into_iter()
+            .map(|(point, member)| {
+                if point.is_valid() {
+                    Ok((point.score(), member))
+                } else {
+                    Err(Error::InvalidPoint(point))
+                }
+            })
+            .collect::<Result<Vec<(f64, V)>, Error>>()?;
+        self.zadd(key, members, flags)
+    }
+
+    pub fn geopos<K, V, I>(&self, key: K, members: I) -> Result<Vec<Option<GeoPoint>>, TypeError>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = V>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let set = map.get(&key).map(Value::with_sorted_set).transpose()?;
+        Ok(members
+            .into_iter()
+            .map(|member| {
+                set.and_then(|set| set.score(&member.into()))
+                    .map(GeoPoint::from_score)
+            })
+            .collect())
+    }
+
+    /// Distance in meters, `None` when either member is missing.
+    pub fn geodist<K, V>(&self, key: K, from: V, to: V) -> Result<Option<f64>, TypeError>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let set = match map.get(&key) {
+            Some(value) => value.with_sorted_set()?,
+            None => return Ok(None),
+        };
+        match (set.score(&from.into()), set.score(&to.into())) {
+            (Some(from), Some(to)) => Ok(Some(
+                GeoPoint::from_score(from).distance(&GeoPoint::from_score(to)),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn geohash<K, V, I>(&self, key: K, members: I) -> Result<Vec<Option<String>>, TypeError>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = V>,
+        V: Into<Arc<String>>,
+    {
+        self.geopos(key, members).map(|points| {
+            points
+                .into_iter()
+                .map(|point| point.map(|point| point.geohash()))
+                .collect()
+        })
+    }
+
+    pub fn geosearch<K>(&self, key: K, geo_search: &GeoSearch) -> Result<Vec<GeoMatch>, Error>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        match map.get(&key) {
+            Some(value) => search(value.with_sorted_set()?, geo_search),
+            None => match geo_search.origin {
+                GeoOrigin::Member(_) => Err(Error::NoMember),
+                GeoOrigin::Point(_) => Ok(Vec::new()),
+            },
+        }
+    }
+
+    /// `GEOSEARCHSTORE`, scoring the members with their distance in `store_distance`
+    /// units when set, or with their position otherwise.
+    pub fn geosearch_store<D, K>(
+        &mut self,
+        destination: D,
+        key: K,
+        geo_search: &GeoSearch,
+        store_distance: Option<GeoUnit>,
+    ) -> Result<usize, Error>
+    where
+        D: Into<Key>,
+        K: Into<Key>,
+    {
+        let matches = self.geosearch(key, geo_search)?;
+        let mut set = SortedSet::new();
+        for geo_match in matches {
+            let score = match store_distance {
+                Some(unit) => unit.from_meters(geo_match.distance),
+                None => geo_match.score,
+            };
+            set.add(geo_match.member, score, AddFlags::default());
+        }
+        let len = set.zcard();
+
+        let destination = destination.into();
+        let mut map = self.write(&destination);
+        if len == 0 {
+            if map.remove(&destination).is_some() {
+                drop(map);
+                self.notify(EventClass::Generic, "del", &destination);
+            }
+        } else {
+            map.insert(destination.clone(), Value::new_sorted_set(set));
+            drop(map);
+            self.notify(EventClass::SortedSet, "geosearchstore", &destination);
+            self.blocking.ready(&destination);
+        }
+        Ok(len)
+    }
+}
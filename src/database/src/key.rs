@@ -1,10 +1,11 @@
+use collections::HeapSize;
 use std::cmp::PartialEq;
 use std::convert::From;
 use std::hash::Hash;
 use std::ops::Deref;
 use std::sync::Arc;
 
-#[derive(Hash, Eq)]
+#[derive(Hash, Eq, Clone)]
 pub struct Key {
     inner: Arc<String>,
 }
@@ -28,6 +29,12 @@ impl PartialEq<Key> for Key {
     }
 }
 
+impl HeapSize for Key {
+    fn heap_size(&self) -> usize {
+        self.inner.heap_size()
+    }
+}
+
 impl From<String> for Key {
     fn from(target: String) -> Self {
         Self {
@@ -0,0 +1,409 @@
+use crate::glob::glob_match;
+use crate::key::Key;
+use crate::lazy_free::free_later;
+use crate::notify::EventClass;
+use crate::slot::{Map, SlotWriteGuard};
+use crate::value::Value;
+use crate::{Database, Error};
+use std::collections::hash_map::RandomState;
+use std::convert::Into;
+use std::hash::{BuildHasher, Hasher};
+use std::iter::IntoIterator;
+use std::mem;
+use std::sync::Arc;
+
+pub(crate) fn random() -> u64 {
+    // every RandomState is seeded differently
+    RandomState::new().build_hasher().finish()
+}
+
+impl Database {
+    /// Locks the slot of `first` here and the slot of `second` in `other`, ordered by
+    /// database then slot, so that callers locking the same pair the other way around
+    /// can't deadlock. The second guard is `None` when both keys share a slot. Expired
+    /// keys are dropped and the others count as accessed.
+    fn write_pair<'a>(
+        &'a self,
+        first: &Key,
+        other: &'a Database,
+        second: &Key,
+    ) -> (SlotWriteGuard<'a>, Option<SlotWriteGuard<'a>>) {
+        let first_point = (
+            Arc::as_ptr(&self.slots) as usize,
+            Self::find_point(first.as_bytes()),
+        );
+        let second_point = (
+            Arc::as_ptr(&other.slots) as usize,
+            Self::find_point(second.as_bytes()),
+        );
+        let (mut from, mut to) = if first_point == second_point {
+            (self.slots[first_point.1].write(), None)
+        } else if first_point < second_point {
+            let from = self.slots[first_point.1].write();
+            (from, Some(other.slots[second_point.1].write()))
+        } else {
+            let to = other.slots[second_point.1].write();
+            (self.slots[first_point.1].write(), Some(to))
+        };
+
+        self.expire(&mut from, first);
+        other.expire(to.as_deref_mut().unwrap_or(&mut from), second);
+        if let Some(value) = from.get(first) {
+            value.touch();
+        }
+        if to.is_some() || first != second {
+            if let Some(value) = to.as_deref().unwrap_or(&from).get(second) {
+                value.touch();
+            }
+        }
+        (from, to)
+    }
+
+    /// `RENAME`, or `RENAMENX` when `nx`, which only renames onto a missing key.
+    /// Both slots stay locked meanwhile so that no reader sees the key twice or not at all.
+    pub fn rename<K, D>(&mut self, key: K, destination: D, nx: bool) -> Result<bool, Error>
+    where
+        K: Into<Key>,
+        D: Into<Key>,
+    {
+        let key = key.into();
+        let destination = destination.into();
+        let (mut from, mut to) = self.write_pair(&key, self, &destination);
+
+        if !from.contains_key(&key) {
+            return Err(Error::NoSuchKey);
+        }
+        if key == destination {
+            return Ok(!nx);
+        }
+        if nx && to.as_deref().unwrap_or(&from).contains_key(&destination) {
+            return Ok(false);
+        }
+        let mut value = from.remove(&key).unwrap();
+        // a new value under `destination`, and one `WATCH` of the key can't mistake if
+        // it is renamed back
+        value.modified();
+        to.as_deref_mut()
+            .unwrap_or(&mut from)
+            .insert(destination.clone(), value);
+        drop(from);
+        drop(to);
+
+        self.notify(EventClass::Generic, "rename_from", &key);
+        self.notify(EventClass::Generic, "rename_to", &destination);
+        self.blocking.ready(&destination);
+        Ok(true)
+    }
+
+    /// `COPY` into `target`, `false` when the key is missing or when the destination
+    /// exists and `replace` isn't set.
+    pub fn copy<K, D>(
+        &self,
+        key: K,
+        target: &Database,
+        destination: D,
+        replace: bool,
+    ) -> Result<bool, Error>
+    where
+        K: Into<Key>,
+        D: Into<Key>,
+    {
+        let key = key.into();
+        let destination = destination.into();
+        if Arc::ptr_eq(&self.slots, &target.slots) && key == destination {
+            return Err(Error::SameObject);
+        }
+        let (mut from, mut to) = self.write_pair(&key, target, &destination);
+
+        let value = match from.get(&key) {
+            Some(value) => value.clone(),
+            None => return Ok(false),
+        };
+        let map = to.as_deref_mut().unwrap_or(&mut from);
+        if !replace && map.contains_key(&destination) {
+            return Ok(false);
+        }
+        map.insert(destination.clone(), value);
+        drop(from);
+        drop(to);
+
+        target.notify(EventClass::Generic, "copy_to", &destination);
+        target.blocking.ready(&destination);
+        Ok(true)
+    }
+
+    /// `MOVE`, `false` when the key is missing here or already exists in `target`.
+    pub fn move_to<K>(&self, key: K, target: &Database) -> Result<bool, Error>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        if Arc::ptr_eq(&self.slots, &target.slots) {
+            return Err(Error::SameObject);
+        }
+        let (mut from, to) = self.write_pair(&key, target, &key);
+        // different databases never share a slot
+        let mut to = to.unwrap();
+
+        if !from.contains_key(&key) || to.contains_key(&key) {
+            return Ok(false);
+        }
+        let mut value = from.remove(&key).unwrap();
+        value.modified();
+        to.insert(key.clone(), value);
+        drop(from);
+        drop(to);
+
+        self.notify(EventClass::Generic, "move_from", &key);
+        target.notify(EventClass::Generic, "move_to", &key);
+        target.blocking.ready(&key);
+        Ok(true)
+    }
+
+    /// Empties every slot. With `lazy` the old tables are dropped on the lazy free
+    /// thread so that the caller doesn't wait for the memory to be given back.
+    pub fn flush(&self, lazy: bool) {
+        self.clear(lazy);
+        self.tracking.invalidate_all();
+    }
+
+    pub(crate) fn clear(&self, lazy: bool) {
+        let tables = self
+            .slots
+            .iter()
+            .map(|slot| mem::take(&mut *slot.write()))
+            .collect::<Vec<Map>>();
+        if lazy {
+            free_later(tables);
+        }
+    }
+
+    /// Counts the given keys that exist, a key repeated is counted as many times.
+    pub fn exists<I, K>(&self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        keys.into_iter()
+            .map(|key| key.into())
+            .filter(|key| self.peek(key).contains_key(key))
+            .count()
+    }
+
+    /// The type name `TYPE` replies, `none` for a missing key.
+    pub fn key_type<K>(&self, key: K) -> &'static str
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.peek(&key);
+
+        map.get(&key).map_or("none", |value| value.type_name())
+    }
+
+    /// Reads the metadata of a key for `OBJECT`, without counting as an access.
+    pub fn object<K, F, T>(&self, key: K, f: F) -> Option<T>
+    where
+        K: Into<Key>,
+        F: FnOnce(&Value<Arc<String>>) -> T,
+    {
+        let key = key.into();
+        let map = self.peek(&key);
+
+        map.get(&key).map(f)
+    }
+
+    /// The version of a key, `None` while it is missing, for `WATCH`.
+    pub fn version<K>(&self, key: K) -> Option<u64>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.peek(&key);
+
+        map.get(&key).map(Value::version)
+    }
+
+    /// `TOUCH`, records an access to each key, as reading it does, and returns how many
+    /// exist.
+    pub fn touch<I, K>(&self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        keys.into_iter()
+            .map(|key| key.into())
+            .filter(|key| self.read(key).contains_key(key))
+            .count()
+    }
+
+    /// Keys matching a glob pattern, holding one slot lock at a time.
+    pub fn keys(&self, pattern: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        for slot in self.slots.iter() {
+            let map = slot.read();
+            keys.extend(
+                map.iter()
+                    .filter(|(key, value)| {
+                        !value.is_expired() && glob_match(pattern.as_bytes(), key.as_bytes())
+                    })
+                    .map(|(key, _)| key.to_string()),
+            );
+        }
+        keys
+    }
+
+    pub fn dbsize(&self) -> usize {
+        self.slots.iter().map(|slot| slot.read().len()).sum()
+    }
+
+    pub fn randomkey(&self) -> Option<String> {
+        loop {
+            let (key, expired) = self.random_entry()?;
+            if !expired {
+                return Some(key.to_string());
+            }
+            // dropped so that it can't be picked again
+            drop(self.peek(&key));
+        }
+    }
+
+    /// Any key with whether it expired, `None` when there is none.
+    fn random_entry(&self) -> Option<(Key, bool)> {
+        // slots are weighted by their size so that every key is as likely
+        let total = self.dbsize();
+        if total == 0 {
+            return None;
+        }
+        let mut index = (random() % total as u64) as usize;
+        for slot in self.slots.iter() {
+            let map = slot.read();
+            if index < map.len() {
+                return map
+                    .iter()
+                    .nth(index)
+                    .map(|(key, value)| (key.clone(), value.is_expired()));
+            }
+            index -= map.len();
+        }
+        // keys were removed meanwhile, settle for any key left
+        self.slots.iter().find_map(|slot| {
+            slot.read()
+                .iter()
+                .next()
+                .map(|(key, value)| (key.clone(), value.is_expired()))
+        })
+    }
+}
+
+#[test]
+fn test_keyspace() {
+    let s = String::from;
+    let mut db = Database::default();
+    db.set(s("a"), s("1"), None, None);
+    db.sadd(s("b"), [s("x")]);
+    db.rpush(s("ab"), [s("x")]);
+    assert_eq!(db.exists([s("a"), s("a"), s("c")]), 2);
+    assert_eq!(db.key_type(s("a")), "string");
+    assert_eq!(db.key_type(s("b")), "set");
+    assert_eq!(db.key_type(s("c")), "none");
+    let mut keys = db.keys("a*");
+    keys.sort();
+    assert_eq!(keys, [s("a"), s("ab")]);
+    assert_eq!(db.dbsize(), 3);
+    assert!(db
+        .randomkey()
+        .is_some_and(|key| ["a", "b", "ab"].contains(&key.as_str())));
+    db.delete([s("a"), s("b"), s("ab")]);
+    assert_eq!(db.randomkey(), None);
+}
+
+#[test]
+fn test_expire() {
+    use crate::ScanFilter;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    let s = String::from;
+    let mut db = Database::default();
+    db.set(s("a"), s("1"), None, Some(1));
+    db.set(s("b"), s("2"), None, Some(60_000));
+    sleep(Duration::from_millis(5));
+
+    // still in its slot, but gone for every command
+    assert_eq!(db.dbsize(), 2);
+    assert_eq!(db.keys("*"), [s("b")]);
+    let filter = ScanFilter {
+        pattern: None,
+        key_type: None,
+    };
+    assert_eq!(db.scan(0, 100, &filter).1, [s("b")]);
+    for _ in 0..10 {
+        assert_eq!(db.randomkey(), Some(s("b")));
+    }
+
+    db.set(s("a"), s("1"), None, Some(1));
+    sleep(Duration::from_millis(5));
+    assert_eq!(db.exists([s("a")]), 0);
+    assert_eq!(db.dbsize(), 1);
+    db.set(s("a"), s("1"), None, Some(1));
+    sleep(Duration::from_millis(5));
+    assert!(db.get(s("a")).is_none());
+    assert!(matches!(
+        db.rename(s("a"), s("c"), false),
+        Err(Error::NoSuchKey)
+    ));
+    assert!(db.get(s("b")).is_some());
+}
+
+#[test]
+fn test_touch() {
+    fn frequency(db: &Database, key: &str) -> u8 {
+        let key = Key::from(String::from(key));
+        db.peek(&key)[&key].frequency()
+    }
+
+    let s = String::from;
+    let mut db = Database::default();
+    db.set(s("a"), s("1"), None, None);
+    let initial = frequency(&db, "a");
+
+    // looking at a key isn't using it
+    db.exists([s("a")]);
+    db.key_type(s("a"));
+    db.memory_usage(s("a"), 0);
+    let key = Key::from(s("a"));
+    assert!(db.peek(&key)[&key].with_string().is_ok());
+    assert_eq!(frequency(&db, "a"), initial);
+
+    // the first access always counts
+    db.get(s("a"));
+    assert_eq!(frequency(&db, "a"), initial + 1);
+}
+
+#[test]
+fn test_version() {
+    use crate::AddFlags;
+
+    let s = String::from;
+    let mut db = Database::default();
+    db.zadd(s("z"), [(1.0, s("a"))], AddFlags::default())
+        .unwrap();
+    let version = db.version(s("z"));
+    // writes that change nothing keep it
+    db.zrem(s("z"), [s("missing")]).unwrap();
+    db.zadd(s("z"), [(1.0, s("a"))], AddFlags::default())
+        .unwrap();
+    assert!(db.lpop(s("z")).unwrap().is_err());
+    assert_eq!(db.version(s("z")), version);
+    db.zadd(s("z"), [(2.0, s("a"))], AddFlags::default())
+        .unwrap();
+    assert_ne!(db.version(s("z")), version);
+
+    let version = db.version(s("z"));
+    db.rename(s("z"), s("y"), false).unwrap();
+    db.rename(s("y"), s("z"), false).unwrap();
+    assert_ne!(db.version(s("z")), version);
+    assert!(db.copy(s("z"), &db, s("c"), false).unwrap());
+    assert_ne!(db.version(s("c")), db.version(s("z")));
+}
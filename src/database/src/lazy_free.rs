@@ -0,0 +1,61 @@
+use std::sync::mpsc::{channel, Sender};
+use std::sync::OnceLock;
+use std::thread;
+
+/// Values made of more allocations than this are freed in the background, smaller
+/// ones cost less to drop in place than to hand over.
+pub(crate) const LAZYFREE_THRESHOLD: usize = 64;
+
+type Garbage = Box<dyn Send>;
+
+static FREE: OnceLock<Sender<Garbage>> = OnceLock::new();
+
+/// Drops `garbage` on the lazy free thread, started on first use.
+pub(crate) fn free_later<T>(garbage: T)
+where
+    T: Send + 'static,
+{
+    let sender = FREE.get_or_init(|| {
+        let (sender, receiver) = channel::<Garbage>();
+        thread::Builder::new()
+            .name(String::from("lazy-free"))
+            .spawn(move || receiver.into_iter().for_each(drop))
+            .unwrap();
+        sender
+    });
+    // the thread never stops receiving
+    let _ = sender.send(Box::new(garbage));
+}
+
+#[test]
+fn test_free_later() {
+    struct Garbage(Sender<Option<String>>);
+
+    impl Drop for Garbage {
+        fn drop(&mut self) {
+            let _ = self.0.send(thread::current().name().map(String::from));
+        }
+    }
+
+    let (sender, receiver) = channel();
+    free_later(Garbage(sender));
+    assert_eq!(receiver.recv().unwrap().as_deref(), Some("lazy-free"));
+}
+
+#[test]
+fn test_unlink() {
+    use crate::{Database, Key};
+
+    let s = String::from;
+    let mut db = Database::default();
+    db.set(s("small"), s("1"), None, None);
+    db.rpush(s("large"), (0..1000).map(|i| i.to_string()));
+    let large: Key = s("large").into();
+    assert!(db.read(&large)[&large].free_effort() > LAZYFREE_THRESHOLD);
+    assert_eq!(db.unlink([s("small"), s("large"), s("missing")]), 2);
+    assert_eq!(db.dbsize(), 0);
+
+    db.set(s("a"), s("1"), None, None);
+    db.flush(true);
+    assert_eq!(db.dbsize(), 0);
+}
@@ -1,22 +1,60 @@
+mod access;
+mod blocking;
+mod databases;
+mod eviction;
+mod exclusive;
+mod geo;
+mod glob;
 mod key;
+mod keyspace;
+mod lazy_free;
+mod memory;
+mod notify;
+mod scan;
 mod slot;
+mod stream;
+mod tracking;
 mod value;
-
+mod zset;
+
+pub use crate::blocking::Blocked;
+use crate::blocking::Blocking;
+pub use crate::databases::Databases;
+pub use crate::eviction::EvictionPolicy;
+pub use crate::exclusive::Exclusive;
+pub use crate::geo::{GeoMatch, GeoOrigin, GeoSearch};
+pub use crate::glob::glob_match;
 pub use crate::key::Key;
-use crate::slot::Slot;
+use crate::lazy_free::{free_later, LAZYFREE_THRESHOLD};
+pub use crate::memory::{mark_startup, peak_memory, startup_memory, used_memory, Allocator};
+use crate::notify::{EventClass, Notifier};
+pub use crate::notify::{KeyspaceEvents, Publish};
+pub use crate::scan::ScanFilter;
+use crate::slot::{Map, Slot, SlotReadGuard, SlotWriteGuard};
+use crate::stream::now_ms;
+pub use crate::stream::{ConsumerInfo, GroupInfo, PendingInfo, PendingSummary, StreamInfo};
+use crate::tracking::{Caller, Tracking};
+pub use crate::tracking::{ClientTracking, Invalidation, TrackingOptions};
 pub use crate::value::{Item, Value};
+pub use crate::zset::{Aggregate, SetOperation};
+pub use collections::{
+    AddFlags, AddId, AutoClaim, ClaimOptions, GeoPoint, GeoShape, GeoUnit, GroupEntry, LexBound,
+    LexRange, ParseRangeError, ParseUnitError, RangeSpec, ScoreBound, ScoreRange, StreamEntry,
+    StreamError, StreamId, Trim, TrimStrategy,
+};
 use collections::{List, Set, Strings};
 
 use crc32fast;
-use hashbrown::HashMap;
-use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
+use std::collections::BTreeMap;
 use std::convert::Into;
 use std::default::Default;
-use std::error::Error;
+use std::error::Error as StdError;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::{Formatter, Result as FmtResult};
 use std::iter::IntoIterator;
+use std::ops::Deref;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 const SLOT_LEN: u32 = 32;
@@ -41,11 +79,66 @@ impl Debug for TypeError {
     }
 }
 
-impl Error for TypeError {}
+impl StdError for TypeError {}
+
+pub enum Error {
+    Type(TypeError),
+    NaN,
+    Stream(StreamError),
+    InvalidPoint(GeoPoint),
+    NoMember,
+    NoSuchKey,
+    SameObject,
+}
+
+impl From<TypeError> for Error {
+    fn from(inner: TypeError) -> Self {
+        Self::Type(inner)
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(inner: StreamError) -> Self {
+        Self::Stream(inner)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Type(inner) => Display::fmt(inner, f),
+            Self::NaN => write!(f, "ERR resulting score is not a number (NaN)"),
+            Self::Stream(inner) => Display::fmt(inner, f),
+            Self::InvalidPoint(point) => write!(
+                f,
+                "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                point.longitude, point.latitude
+            ),
+            Self::NoMember => write!(f, "ERR could not decode requested zset member"),
+            Self::NoSuchKey => write!(f, "ERR no such key"),
+            Self::SameObject => write!(f, "ERR source and destination objects are the same"),
+        }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl StdError for Error {}
 
 #[derive(Clone)]
 pub struct Database {
     slots: Arc<Vec<Slot>>,
+    blocking: Arc<Blocking>,
+    /// Its number, which `SWAPDB` changes, for the channels of keyspace events.
+    index: Arc<AtomicUsize>,
+    notifier: Option<Arc<Notifier>>,
+    tracking: Arc<Tracking>,
+    /// Set on the handles `ClientTracking::database` gives out.
+    caller: Option<Caller>,
 }
 
 impl Default for Database {
@@ -56,6 +149,11 @@ impl Default for Database {
                     .map(|_| Slot::new())
                     .collect::<Vec<Slot>>(),
             ),
+            blocking: Arc::new(Blocking::default()),
+            index: Arc::new(AtomicUsize::new(0)),
+            notifier: None,
+            tracking: Arc::new(Tracking::default()),
+            caller: None,
         }
     }
 }
@@ -65,14 +163,97 @@ impl Database {
         (crc32fast::hash(key) % SLOT_LEN) as usize
     }
 
-    fn read(&self, key: &String) -> RwLockReadGuard<'_, HashMap<Key, Value<Arc<String>>>> {
-        let point = Self::find_point(key.as_bytes());
-        (&self.slots[point]).read()
+    /// The slot of `key` for reading, with `key` dropped first if it expired, without
+    /// counting as an access, for the commands that only look at the key.
+    fn peek(&self, key: &Key) -> SlotReadGuard<'_> {
+        let slot = &self.slots[Self::find_point(key.as_bytes())];
+        let map = slot.read();
+        if !map.get(key).is_some_and(|value| value.is_expired()) {
+            return map;
+        }
+        drop(map);
+        self.expire(&mut slot.write(), key);
+        slot.read()
     }
 
-    fn write(&mut self, key: &String) -> RwLockWriteGuard<'_, HashMap<Key, Value<Arc<String>>>> {
+    /// Like `peek`, recording an access to `key`, and its read for the caller when
+    /// tracked.
+    fn read(&self, key: &Key) -> SlotReadGuard<'_> {
+        let map = self.peek(key);
+        if let Some(value) = map.get(key) {
+            value.touch();
+        }
+        self.record_read(key);
+        map
+    }
+
+    /// The slot of `key` for writing, with `key` dropped first if it expired and its
+    /// access recorded otherwise.
+    fn write(&mut self, key: &Key) -> SlotWriteGuard<'_> {
         let point = Self::find_point(key.as_bytes());
-        (&self.slots[point]).write()
+        let mut map = (&self.slots[point]).write();
+        self.expire(&mut map, key);
+        if let Some(value) = map.get(key) {
+            value.touch();
+        }
+        map
+    }
+
+    /// Removes `key` from `map`, the table of its slot, once its time to live is over.
+    /// Keys only expire this way, when a command reaches them, and `expired` is
+    /// published then.
+    fn expire(&self, map: &mut Map, key: &Key) {
+        if map.get(key).is_some_and(|value| value.is_expired()) {
+            map.remove(key);
+            self.notify(EventClass::Expired, "expired", key);
+        }
+    }
+
+    /// Locks the slots of `keys` with `lock`, each once and in slot order, so that a
+    /// command reaching several keys sees and leaves them together without deadlocking
+    /// another one. The guards are by slot.
+    ///
+    /// Expired keys are dropped before locking: a key expiring meanwhile is still seen,
+    /// as if the command ran at that moment.
+    fn lock_slots<'a, G>(&'a self, keys: &[Key], lock: impl Fn(&'a Slot) -> G) -> BTreeMap<usize, G>
+    where
+        G: Deref<Target = Map>,
+    {
+        for key in keys {
+            drop(self.peek(key));
+        }
+        let mut points = keys
+            .iter()
+            .map(|key| Self::find_point(key.as_bytes()))
+            .collect::<Vec<_>>();
+        points.sort_unstable();
+        points.dedup();
+        let guards = points
+            .into_iter()
+            .map(|point| (point, lock(&self.slots[point])))
+            .collect::<BTreeMap<_, _>>();
+        for (index, key) in keys.iter().enumerate() {
+            if keys[..index].contains(key) {
+                continue;
+            }
+            if let Some(value) = guards[&Self::find_point(key.as_bytes())].get(key) {
+                value.touch();
+            }
+            self.record_read(key);
+        }
+        guards
+    }
+
+    /// Parks the caller on `keys` until a write makes one of them ready to be served.
+    pub fn block<I, K>(&self, keys: I) -> Blocked
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        Blocked::new(
+            self.blocking.clone(),
+            keys.into_iter().map(|key| key.into()).collect(),
+        )
     }
 
     pub fn get<K>(&self, key: K) -> Option<Result<Arc<String>, TypeError>>
@@ -98,10 +279,17 @@ impl Database {
     {
         let key = key.into();
         let value = Strings::set(value.into());
-        let value = Value::new_string(value);
+        let mut value = Value::new_string(value);
+        let ttl = expire_seconds
+            .map(|seconds| seconds as u128 * 1000)
+            .or(expire_milliseconds);
+        value.set_expire_at(ttl.map(|ttl| (now_ms() as u128 + ttl) as u64));
 
         let mut map = self.write(&key);
-        map.insert(key, value);
+        map.insert(key.clone(), value);
+        drop(map);
+
+        self.notify(EventClass::String, "set", &key);
     }
 
     pub fn delete<I, K>(&mut self, keys: I) -> usize
@@ -113,7 +301,30 @@ impl Database {
             .filter_map(|key| {
                 let key = key.into();
                 let mut map = self.write(&key);
-                map.remove(&key)
+                let value = map.remove(&key)?;
+                drop(map);
+                self.notify(EventClass::Generic, "del", &key);
+                Some(value)
+            })
+            .count()
+    }
+
+    /// `UNLINK`, like `delete` but large values are dropped on the lazy free thread
+    /// once detached from their slot.
+    pub fn unlink<I, K>(&mut self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        keys.into_iter()
+            .filter_map(|key| {
+                let key = key.into();
+                let value = self.write(&key).remove(&key)?;
+                if value.free_effort() > LAZYFREE_THRESHOLD {
+                    free_later(value);
+                }
+                self.notify(EventClass::Generic, "del", &key);
+                Some(())
             })
             .count()
     }
@@ -139,8 +350,8 @@ impl Database {
         let key = key.into();
         let mut map = self.write(&key);
 
-        let mut value = map
-            .entry(key)
+        let value = map
+            .entry(key.clone())
             .and_modify(|value| {
                 if let Err(_) = value.with_list() {
                     value.set_list(List::new());
@@ -148,10 +359,15 @@ impl Database {
             })
             .or_insert_with(|| Value::new_list(List::new()));
 
-        value
+        let len = value
             .with_list_mut()
             .unwrap()
-            .lpush(list.into_iter().map(|item| item.into()))
+            .lpush(list.into_iter().map(|item| item.into()));
+        value.modified();
+        drop(map);
+
+        self.notify(EventClass::List, "lpush", &key);
+        len
     }
 
     pub fn rpush<K, V, I>(&mut self, key: K, list: I) -> usize
@@ -163,8 +379,8 @@ impl Database {
         let key = key.into();
         let mut map = self.write(&key);
 
-        let mut value = map
-            .entry(key)
+        let value = map
+            .entry(key.clone())
             .and_modify(|value| {
                 if let Err(_) = value.with_list() {
                     value.set_list(List::new());
@@ -172,10 +388,15 @@ impl Database {
             })
             .or_insert_with(|| Value::new_list(List::new()));
 
-        value
+        let len = value
             .with_list_mut()
             .unwrap()
-            .rpush(list.into_iter().map(|item| item.into()))
+            .rpush(list.into_iter().map(|item| item.into()));
+        value.modified();
+        drop(map);
+
+        self.notify(EventClass::List, "rpush", &key);
+        len
     }
 
     pub fn lpop<K>(&mut self, key: K) -> Option<Result<Option<Arc<String>>, TypeError>>
@@ -185,8 +406,19 @@ impl Database {
         let key = key.into();
         let mut map = self.write(&key);
 
-        map.get_mut(&key)
-            .map(|item| item.with_list_mut().map(|list| list.lpop()))
+        let popped = map.get_mut(&key).map(|value| {
+            let popped = value.with_list_mut().map(|list| list.lpop());
+            if let Ok(Some(_)) = popped {
+                value.modified();
+            }
+            popped
+        });
+        drop(map);
+
+        if let Some(Ok(Some(_))) = popped {
+            self.notify(EventClass::List, "lpop", &key);
+        }
+        popped
     }
 
     pub fn rpop<K>(&mut self, key: K) -> Option<Result<Option<Arc<String>>, TypeError>>
@@ -196,8 +428,19 @@ impl Database {
         let key = key.into();
         let mut map = self.write(&key);
 
-        map.get_mut(&key)
-            .map(|item| item.with_list_mut().map(|list| list.rpop()))
+        let popped = map.get_mut(&key).map(|value| {
+            let popped = value.with_list_mut().map(|list| list.rpop());
+            if let Ok(Some(_)) = popped {
+                value.modified();
+            }
+            popped
+        });
+        drop(map);
+
+        if let Some(Ok(Some(_))) = popped {
+            self.notify(EventClass::List, "rpop", &key);
+        }
+        popped
     }
 
     pub fn lrange<K>(&self, key: K, start: i64, stop: i64) -> Vec<Arc<String>>
@@ -241,9 +484,10 @@ impl Database {
             let key = key.into();
             let value = Strings::set(value.into());
             let value = Value::new_string(value);
-            let point = Self::find_point(key.as_bytes());
-            let mut map = (&self.slots[point]).write();
-            map.insert(key, value);
+            let mut map = self.write(&key);
+            map.insert(key.clone(), value);
+            drop(map);
+            self.notify(EventClass::String, "set", &key);
         });
     }
 
@@ -257,7 +501,7 @@ impl Database {
         let mut map = self.write(&key);
 
         let value = map
-            .entry(key)
+            .entry(key.clone())
             .and_modify(|value| {
                 if let Err(_) = value.with_set() {
                     value.set_sets(Set::new());
@@ -265,10 +509,19 @@ impl Database {
             })
             .or_insert_with(|| Value::new_set(Set::new()));
 
-        value
+        let added = value
             .with_set_mut()
             .unwrap()
-            .sadd(members.into_iter().map(|member| member.into()))
+            .sadd(members.into_iter().map(|member| member.into()));
+        if added > 0 {
+            value.modified();
+        }
+        drop(map);
+
+        if added > 0 {
+            self.notify(EventClass::Set, "sadd", &key);
+        }
+        added
     }
 
     pub fn smembers<K>(&self, key: K) -> Vec<Arc<String>>
@@ -0,0 +1,95 @@
+use crate::key::Key;
+use crate::value::Value;
+use crate::Database;
+use collections::HeapSize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::convert::Into;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static STARTUP: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator keeping count of the bytes in use, install it with
+/// `#[global_allocator]` for `used_memory` to report anything.
+pub struct Allocator;
+
+impl Allocator {
+    fn grow(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::grow(new_size);
+            Self::shrink(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Bytes allocated right now.
+pub fn used_memory() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// The most bytes ever allocated at once.
+pub fn peak_memory() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Bytes allocated when `mark_startup` was called.
+pub fn startup_memory() -> usize {
+    STARTUP.load(Ordering::Relaxed)
+}
+
+/// Records the memory in use once the server is set up, before any data is loaded.
+pub fn mark_startup() {
+    STARTUP.store(used_memory(), Ordering::Relaxed);
+}
+
+impl Database {
+    /// `MEMORY USAGE`, the bytes a key and its value take, `None` for a missing key.
+    pub fn memory_usage<K>(&self, key: K, samples: usize) -> Option<usize>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.peek(&key);
+
+        map.get_key_value(&key).map(|(key, value)| {
+            size_of::<(Key, Value<Arc<String>>)>() + key.heap_size() + value.memory_usage(samples)
+        })
+    }
+}
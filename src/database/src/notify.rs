@@ -0,0 +1,159 @@
+use crate::key::Key;
+use crate::Database;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+
+/// Kinds of keyspace events, each enabled by its own `notify-keyspace-events` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventClass {
+    Generic,
+    String,
+    List,
+    Set,
+    SortedSet,
+    Expired,
+    Evicted,
+    Stream,
+}
+
+impl EventClass {
+    fn bit(self) -> u16 {
+        match self {
+            Self::Generic => 1 << 2,
+            Self::String => 1 << 3,
+            Self::List => 1 << 4,
+            Self::Set => 1 << 5,
+            Self::SortedSet => 1 << 7,
+            Self::Expired => 1 << 8,
+            Self::Evicted => 1 << 9,
+            Self::Stream => 1 << 10,
+        }
+    }
+}
+
+const KEYSPACE: u16 = 1 << 0;
+const KEYEVENT: u16 = 1 << 1;
+/// `A`, every class: `g$lshzxet`.
+const ALL: u16 = 0b111_1111_1100;
+
+/// The `notify-keyspace-events` flags as Redis spells them, `K` and `E` for the
+/// `__keyspace@<db>__:` and `__keyevent@<db>__:` channels and one letter per class.
+/// Nothing is published unless a channel and a class are both given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyspaceEvents(u16);
+
+#[derive(Debug)]
+pub struct ParseEventsError;
+
+impl Display for ParseEventsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "invalid keyspace events flags")
+    }
+}
+
+impl std::error::Error for ParseEventsError {}
+
+impl FromStr for KeyspaceEvents {
+    type Err = ParseEventsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = 0;
+        for flag in s.chars() {
+            flags |= match flag {
+                'K' => KEYSPACE,
+                'E' => KEYEVENT,
+                'A' => ALL,
+                'g' => 1 << 2,
+                '$' => 1 << 3,
+                'l' => 1 << 4,
+                's' => 1 << 5,
+                // ky has no hashes, the flag is taken for compatibility with Redis
+                // configurations
+                'h' => 1 << 6,
+                'z' => 1 << 7,
+                'x' => 1 << 8,
+                'e' => 1 << 9,
+                't' => 1 << 10,
+                _ => return Err(ParseEventsError),
+            };
+        }
+        Ok(Self(flags))
+    }
+}
+
+impl KeyspaceEvents {
+    fn enabled(&self, class: EventClass) -> bool {
+        self.0 & (KEYSPACE | KEYEVENT) != 0 && self.0 & class.bit() != 0
+    }
+}
+
+/// Publishes a message, given the channel then the message.
+pub type Publish = Box<dyn Fn(String, String) + Send + Sync>;
+
+/// Where the databases of a server send their keyspace events.
+pub(crate) struct Notifier {
+    pub(crate) events: KeyspaceEvents,
+    pub(crate) publish: Publish,
+}
+
+impl Database {
+    /// Called once a write changed `key`: tells the clients caching it to drop it, then
+    /// publishes `event` on it when its class is enabled.
+    pub(crate) fn notify(&self, class: EventClass, event: &str, key: &Key) {
+        self.invalidate(key);
+        let notifier = match self.notifier.as_ref() {
+            Some(notifier) if notifier.events.enabled(class) => notifier,
+            _ => return,
+        };
+        let index = self.index.load(Ordering::Relaxed);
+        if notifier.events.0 & KEYSPACE != 0 {
+            let channel = format!("__keyspace@{}__:{}", index, key.as_str());
+            (notifier.publish)(channel, event.to_string());
+        }
+        if notifier.events.0 & KEYEVENT != 0 {
+            let channel = format!("__keyevent@{}__:{}", index, event);
+            (notifier.publish)(channel, key.to_string());
+        }
+    }
+}
+
+#[test]
+fn test_keyspace_events() {
+    let events = "KEA".parse::<KeyspaceEvents>().unwrap();
+    assert!(events.enabled(EventClass::Stream));
+    let events = "Kz".parse::<KeyspaceEvents>().unwrap();
+    assert!(events.enabled(EventClass::SortedSet));
+    assert!(!events.enabled(EventClass::Generic));
+    let events = "g$".parse::<KeyspaceEvents>().unwrap();
+    assert!(!events.enabled(EventClass::Generic));
+    assert!("Kq".parse::<KeyspaceEvents>().is_err());
+}
+
+#[test]
+fn test_expired() {
+    use crate::Databases;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    let published = Arc::new(Mutex::new(Vec::new()));
+    let sink = published.clone();
+    let events = "Ex".parse().unwrap();
+    let databases = Databases::with_events(
+        1,
+        events,
+        Box::new(move |channel, message| sink.lock().push((channel, message))),
+    );
+    let mut db = databases.get(0).unwrap();
+    db.set("a".to_string(), "1".to_string(), None, Some(1));
+    sleep(Duration::from_millis(5));
+    assert!(published.lock().is_empty());
+
+    assert!(db.get("a".to_string()).is_none());
+    assert_eq!(
+        *published.lock(),
+        [("__keyevent@0__:expired".to_string(), "a".to_string())]
+    );
+}
@@ -0,0 +1,114 @@
+use crate::glob::glob_match;
+use crate::key::Key;
+use crate::{Database, TypeError, SLOT_LEN};
+use collections::scan_table;
+use std::convert::Into;
+use std::sync::Arc;
+
+/// The next cursor with the items found.
+pub type Page<T> = (u64, Vec<T>);
+
+/// Filters of the `SCAN` family.
+pub struct ScanFilter {
+    pub pattern: Option<String>,
+    /// Only for `SCAN`, a type name as `TYPE` replies it.
+    pub key_type: Option<String>,
+}
+
+impl ScanFilter {
+    fn matches(&self, name: &str) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern.as_bytes(), name.as_bytes()))
+    }
+}
+
+impl Database {
+    /// `SCAN` walks the slots in order, the cursor keeps the slot in its low 5 bits and
+    /// the position inside the slot table above them. Each call holds a single slot lock.
+    pub fn scan(&self, cursor: u64, count: usize, filter: &ScanFilter) -> Page<String> {
+        let mut keys = Vec::new();
+        let mut point = (cursor % SLOT_LEN as u64) as usize;
+        let mut position = cursor / SLOT_LEN as u64;
+        let mut visited = 0;
+
+        while point < SLOT_LEN as usize && visited < count {
+            let slot = &self.slots[point];
+            let map = slot.read();
+            // bumped under the lock, so read once holding it
+            let rehashes = slot.rehashes();
+            position = scan_table(&map, rehashes, position, count - visited, |key, value| {
+                visited += 1;
+                let type_matches = filter
+                    .key_type
+                    .as_ref()
+                    .is_none_or(|name| name.eq_ignore_ascii_case(value.type_name()));
+                if type_matches && !value.is_expired() && filter.matches(key) {
+                    keys.push(key.to_string());
+                }
+            });
+            if position != 0 {
+                return (position * SLOT_LEN as u64 + point as u64, keys);
+            }
+            point += 1;
+        }
+
+        if point == SLOT_LEN as usize {
+            (0, keys)
+        } else {
+            (point as u64, keys)
+        }
+    }
+
+    pub fn sscan<K>(
+        &self,
+        key: K,
+        cursor: u64,
+        count: usize,
+        filter: &ScanFilter,
+    ) -> Result<Page<Arc<String>>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let mut members = Vec::new();
+        let cursor = match map.get(&key) {
+            Some(value) => value.with_set()?.scan(cursor, count, |member| {
+                if filter.matches(member) {
+                    members.push(member.clone());
+                }
+            }),
+            None => 0,
+        };
+        Ok((cursor, members))
+    }
+
+    pub fn zscan<K>(
+        &self,
+        key: K,
+        cursor: u64,
+        count: usize,
+        filter: &ScanFilter,
+    ) -> Result<Page<(Arc<String>, f64)>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let mut members = Vec::new();
+        let cursor = match map.get(&key) {
+            Some(value) => value
+                .with_sorted_set()?
+                .scan(cursor, count, |member, score| {
+                    if filter.matches(member) {
+                        members.push((member.clone(), score));
+                    }
+                }),
+            None => 0,
+        };
+        Ok((cursor, members))
+    }
+}
@@ -1,25 +1,142 @@
 use crate::key::Key;
 use crate::value::Value;
 use hashbrown::HashMap;
-use parking_lot::RwLock;
-use std::ops::Deref;
+use parking_lot::{ArcRwLockWriteGuard, RawRwLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
-pub(crate) struct Slot {
-    inner: RwLock<HashMap<Key, Value<Arc<String>>>>,
+pub(crate) type Map = HashMap<Key, Value<Arc<String>>>;
+
+thread_local! {
+    /// Addresses of the slots a transaction running on this thread holds.
+    static HELD: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
 }
 
-impl Deref for Slot {
-    type Target = RwLock<HashMap<Key, Value<Arc<String>>>>;
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
+pub(crate) struct Slot {
+    inner: RwLock<Map>,
+    /// Times the keys may have moved inside the table, for `SCAN`.
+    rehashes: AtomicU64,
+    /// Taken shared along with `inner` and exclusively by a transaction, whose thread
+    /// then reaches the slot without it.
+    gate: Arc<RwLock<()>>,
 }
 
 impl Slot {
     pub(crate) fn new() -> Self {
         Self {
             inner: RwLock::new(HashMap::default()),
+            rehashes: AtomicU64::new(0),
+            gate: Arc::new(RwLock::new(())),
+        }
+    }
+
+    fn address(&self) -> usize {
+        self as *const Slot as usize
+    }
+
+    /// Whether this thread holds the slot exclusively.
+    pub(crate) fn held(&self) -> bool {
+        let address = self.address();
+        HELD.with(|held| held.borrow().contains(&address))
+    }
+
+    fn enter(&self) -> Option<RwLockReadGuard<'_, ()>> {
+        if self.held() {
+            None
+        } else {
+            Some(self.gate.read())
+        }
+    }
+
+    pub(crate) fn read(&self) -> SlotReadGuard<'_> {
+        let gate = self.enter();
+        SlotReadGuard {
+            map: self.inner.read(),
+            _gate: gate,
+        }
+    }
+
+    pub(crate) fn write(&self) -> SlotWriteGuard<'_> {
+        let gate = self.enter();
+        let map = self.inner.write();
+        SlotWriteGuard {
+            capacity: map.capacity(),
+            map,
+            rehashes: &self.rehashes,
+            _gate: gate,
+        }
+    }
+
+    pub(crate) fn rehashes(&self) -> u64 {
+        self.rehashes.load(Ordering::Relaxed)
+    }
+
+    /// Keeps other threads out of the slot until the guard is dropped, while this one
+    /// still goes through. The guard must be dropped on this thread.
+    pub(crate) fn lock_exclusive(&self) -> ExclusiveGuard {
+        let gate = self.gate.write_arc();
+        HELD.with(|held| held.borrow_mut().push(self.address()));
+        ExclusiveGuard {
+            _gate: gate,
+            address: self.address(),
+        }
+    }
+}
+
+/// A slot held by a transaction, see `Slot::lock_exclusive`.
+pub(crate) struct ExclusiveGuard {
+    _gate: ArcRwLockWriteGuard<RawRwLock, ()>,
+    address: usize,
+}
+
+impl Drop for ExclusiveGuard {
+    fn drop(&mut self) {
+        // before the gate opens once the fields drop
+        HELD.with(|held| held.borrow_mut().retain(|held| *held != self.address));
+    }
+}
+
+pub(crate) struct SlotReadGuard<'a> {
+    map: RwLockReadGuard<'a, Map>,
+    _gate: Option<RwLockReadGuard<'a, ()>>,
+}
+
+impl Deref for SlotReadGuard<'_> {
+    type Target = Map;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+/// Write access to a slot, noticing when the table was resized or rehashed in place.
+pub(crate) struct SlotWriteGuard<'a> {
+    map: RwLockWriteGuard<'a, Map>,
+    capacity: usize,
+    rehashes: &'a AtomicU64,
+    _gate: Option<RwLockReadGuard<'a, ()>>,
+}
+
+impl Deref for SlotWriteGuard<'_> {
+    type Target = Map;
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl DerefMut for SlotWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+impl Drop for SlotWriteGuard<'_> {
+    fn drop(&mut self) {
+        // removals never grow the capacity and an insert over a tombstone grows it by
+        // one, a rehash gives back at least half the table
+        if self.map.capacity() > self.capacity + 1 {
+            self.rehashes.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
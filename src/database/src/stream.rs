@@ -0,0 +1,676 @@
+use crate::key::Key;
+use crate::notify::EventClass;
+use crate::value::Value;
+use crate::{Database, Error, TypeError};
+use collections::{
+    AddId, AutoClaim, ClaimOptions, GroupEntry, Stream, StreamEntry, StreamError, StreamId, Trim,
+};
+use hashbrown::HashMap;
+use std::convert::Into;
+use std::iter::IntoIterator;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type Map = HashMap<Key, Value<Arc<String>>>;
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Database {
+    /// Appends an entry, `None` when the stream does not exist and `nomkstream` is set.
+    pub fn xadd<K, V, I>(
+        &mut self,
+        key: K,
+        id: AddId,
+        fields: I,
+        nomkstream: bool,
+        trim: Option<Trim>,
+    ) -> Result<Option<StreamId>, Error>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = V>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        if nomkstream && !map.contains_key(&key) {
+            return Ok(None);
+        }
+
+        let value = map
+            .entry(key.clone())
+            .or_insert_with(|| Value::new_stream(Stream::new()));
+        let stream = value.with_stream_mut()?;
+        let result = stream.add(
+            id,
+            fields.into_iter().map(|field| field.into()).collect(),
+            now_ms(),
+        );
+        match result {
+            Ok(id) => {
+                let trimmed = trim.map_or(0, |trim| stream.trim(&trim));
+                value.modified();
+                drop(map);
+
+                self.notify(EventClass::Stream, "xadd", &key);
+                if trimmed > 0 {
+                    self.notify(EventClass::Stream, "xtrim", &key);
+                }
+                self.blocking.ready_all(&key);
+                Ok(Some(id))
+            }
+            Err(e) => {
+                if stream.is_empty() && stream.last_id() == StreamId::MIN {
+                    map.remove(&key);
+                }
+                Err(Error::from(e))
+            }
+        }
+    }
+
+    pub fn xlen<K>(&self, key: K) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_stream().map(|stream| stream.len()))
+            .unwrap_or(Ok(0))
+    }
+
+    /// `XRANGE`, or `XREVRANGE` when `rev` is set.
+    pub fn xrange<K>(
+        &self,
+        key: K,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Result<Vec<StreamEntry<Arc<String>>>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| {
+                value
+                    .with_stream()
+                    .map(|stream| stream.range(start, end, count, rev))
+            })
+            .unwrap_or(Ok(Vec::new()))
+    }
+
+    /// Entries after `id`, for `XREAD`.
+    pub fn xread<K>(
+        &self,
+        key: K,
+        id: StreamId,
+        count: Option<usize>,
+    ) -> Result<Vec<StreamEntry<Arc<String>>>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_stream().map(|stream| stream.after(id, count)))
+            .unwrap_or(Ok(Vec::new()))
+    }
+
+    /// The ID `$` stands for in `XREAD`.
+    pub fn xlast_id<K>(&self, key: K) -> Result<StreamId, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_stream().map(|stream| stream.last_id()))
+            .unwrap_or(Ok(StreamId::MIN))
+    }
+
+    pub fn xtrim<K>(&mut self, key: K, trim: &Trim) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let trimmed = match map.get_mut(&key) {
+            Some(value) => {
+                let trimmed = value.with_stream_mut()?.trim(trim);
+                if trimmed > 0 {
+                    value.modified();
+                }
+                trimmed
+            }
+            None => 0,
+        };
+        drop(map);
+
+        if trimmed > 0 {
+            self.notify(EventClass::Stream, "xtrim", &key);
+        }
+        Ok(trimmed)
+    }
+
+    pub fn xdel<K>(&mut self, key: K, ids: &[StreamId]) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let deleted = match map.get_mut(&key) {
+            Some(value) => {
+                let deleted = value.with_stream_mut()?.delete(ids.iter());
+                if deleted > 0 {
+                    value.modified();
+                }
+                deleted
+            }
+            None => 0,
+        };
+        drop(map);
+
+        if deleted > 0 {
+            self.notify(EventClass::Stream, "xdel", &key);
+        }
+        Ok(deleted)
+    }
+}
+
+/// `XPENDING` without a range.
+pub struct PendingSummary {
+    pub count: usize,
+    /// Smallest and greatest pending IDs.
+    pub range: Option<(StreamId, StreamId)>,
+    pub consumers: Vec<(Arc<String>, usize)>,
+}
+
+/// An entry of the extended form of `XPENDING`.
+pub struct PendingInfo {
+    pub id: StreamId,
+    pub consumer: Arc<String>,
+    pub idle_ms: u64,
+    pub delivery_count: u64,
+}
+
+pub struct StreamInfo {
+    pub length: usize,
+    pub last_generated_id: StreamId,
+    pub max_deleted_id: StreamId,
+    pub entries_added: u64,
+    pub recorded_first_id: StreamId,
+    pub groups: usize,
+    pub first_entry: Option<StreamEntry<Arc<String>>>,
+    pub last_entry: Option<StreamEntry<Arc<String>>>,
+}
+
+pub struct GroupInfo {
+    pub name: Arc<String>,
+    pub consumers: usize,
+    pub pending: usize,
+    pub last_delivered_id: StreamId,
+    pub entries_read: Option<u64>,
+    pub lag: Option<u64>,
+}
+
+pub struct ConsumerInfo {
+    pub name: Arc<String>,
+    pub pending: usize,
+    pub idle_ms: u64,
+    /// `None` when the consumer never got an entry.
+    pub inactive_ms: Option<u64>,
+}
+
+fn no_group(key: &Key, group: &Arc<String>) -> Error {
+    Error::from(StreamError::NoGroup {
+        key: key.to_string(),
+        group: group.to_string(),
+    })
+}
+
+/// Gives the stream under `key` a new version once a write changed it.
+fn modified(map: &mut Map, key: &Key) {
+    if let Some(value) = map.get_mut(key) {
+        value.modified();
+    }
+}
+
+fn stream_mut<'a>(map: &'a mut Map, key: &Key) -> Result<&'a mut Stream<Arc<String>>, Error> {
+    match map.get_mut(key) {
+        Some(value) => Ok(value.with_stream_mut()?),
+        None => Err(Error::from(StreamError::NoStream)),
+    }
+}
+
+impl Database {
+    /// `XGROUP CREATE`, `id` of `None` standing for `$`.
+    pub fn xgroup_create<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        id: Option<StreamId>,
+        mkstream: bool,
+        entries_read: Option<u64>,
+    ) -> Result<(), Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        if mkstream && !map.contains_key(&key) {
+            map.insert(key.clone(), Value::new_stream(Stream::new()));
+        }
+        let stream = stream_mut(&mut map, &key)?;
+        if !stream.create_group(group.into(), id, entries_read) {
+            return Err(Error::from(StreamError::BusyGroup));
+        }
+        modified(&mut map, &key);
+        drop(map);
+
+        self.notify(EventClass::Stream, "xgroup-create", &key);
+        Ok(())
+    }
+
+    /// `XGROUP SETID`, `id` of `None` standing for `$`.
+    pub fn xgroup_setid<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        id: Option<StreamId>,
+        entries_read: Option<u64>,
+    ) -> Result<(), Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        if !stream_mut(&mut map, &key)?.set_group_id(&group, id, entries_read) {
+            return Err(no_group(&key, &group));
+        }
+        modified(&mut map, &key);
+        drop(map);
+
+        self.notify(EventClass::Stream, "xgroup-setid", &key);
+        Ok(())
+    }
+
+    pub fn xgroup_destroy<K, V>(&mut self, key: K, group: V) -> Result<bool, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let destroyed = stream_mut(&mut map, &key)?.destroy_group(&group.into());
+        if destroyed {
+            modified(&mut map, &key);
+        }
+        drop(map);
+
+        if destroyed {
+            self.notify(EventClass::Stream, "xgroup-destroy", &key);
+        }
+        Ok(destroyed)
+    }
+
+    pub fn xgroup_createconsumer<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        consumer: V,
+    ) -> Result<bool, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        let created = stream_mut(&mut map, &key)?
+            .create_consumer(&group, consumer.into(), now_ms())
+            .ok_or_else(|| no_group(&key, &group))?;
+        if created {
+            modified(&mut map, &key);
+        }
+        drop(map);
+
+        if created {
+            self.notify(EventClass::Stream, "xgroup-createconsumer", &key);
+        }
+        Ok(created)
+    }
+
+    /// Returns how many entries the consumer had pending.
+    pub fn xgroup_delconsumer<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        consumer: V,
+    ) -> Result<usize, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        let pending = stream_mut(&mut map, &key)?
+            .delete_consumer(&group, &consumer.into())
+            .ok_or_else(|| no_group(&key, &group))?;
+        modified(&mut map, &key);
+        drop(map);
+
+        self.notify(EventClass::Stream, "xgroup-delconsumer", &key);
+        Ok(pending)
+    }
+
+    /// `XREADGROUP` on one stream, `id` of `None` standing for `>`.
+    pub fn xreadgroup<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        consumer: V,
+        id: Option<StreamId>,
+        count: Option<usize>,
+        noack: bool,
+    ) -> Result<Vec<GroupEntry<Arc<String>>>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        let stream = match map.get_mut(&key) {
+            Some(value) => value.with_stream_mut()?,
+            None => return Err(no_group(&key, &group)),
+        };
+        let entries = stream
+            .read_group(&group, consumer.into(), id, count, noack, now_ms())
+            .ok_or_else(|| no_group(&key, &group))?;
+        if !entries.is_empty() {
+            modified(&mut map, &key);
+            drop(map);
+            self.invalidate(&key);
+        }
+        Ok(entries)
+    }
+
+    pub fn xack<K, V>(&mut self, key: K, group: V, ids: &[StreamId]) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let acked = match map.get_mut(&key) {
+            Some(value) => {
+                let acked = value.with_stream_mut()?.ack(&group.into(), ids.iter());
+                if acked > 0 {
+                    value.modified();
+                }
+                acked
+            }
+            None => 0,
+        };
+        drop(map);
+        if acked > 0 {
+            self.invalidate(&key);
+        }
+        Ok(acked)
+    }
+
+    pub fn xpending_summary<K, V>(&self, key: K, group: V) -> Result<PendingSummary, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let map = self.read(&key);
+
+        let consumer_group = map
+            .get(&key)
+            .map(|value| value.with_stream())
+            .transpose()?
+            .and_then(|stream| stream.group(&group))
+            .ok_or_else(|| no_group(&key, &group))?;
+
+        let mut pending = consumer_group.pending();
+        let range = pending.next().map(|(first, _)| {
+            let last = pending.last().map_or(*first, |(last, _)| *last);
+            (*first, last)
+        });
+        Ok(PendingSummary {
+            count: consumer_group.pending_len(),
+            range,
+            consumers: consumer_group
+                .consumers()
+                .filter(|(_, consumer)| consumer.pending_len() > 0)
+                .map(|(name, consumer)| (name.clone(), consumer.pending_len()))
+                .collect(),
+        })
+    }
+
+    /// The extended form of `XPENDING`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn xpending<K, V>(
+        &self,
+        key: K,
+        group: V,
+        min_idle_ms: Option<u64>,
+        start: StreamId,
+        end: StreamId,
+        count: usize,
+        consumer: Option<V>,
+    ) -> Result<Vec<PendingInfo>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let consumer = consumer.map(|consumer| consumer.into());
+        let map = self.read(&key);
+
+        let consumer_group = map
+            .get(&key)
+            .map(|value| value.with_stream())
+            .transpose()?
+            .and_then(|stream| stream.group(&group))
+            .ok_or_else(|| no_group(&key, &group))?;
+
+        let now = now_ms();
+        Ok(consumer_group
+            .pending()
+            .filter(|(id, _)| start <= **id && **id <= end)
+            .filter(|(_, pending)| consumer.as_ref().is_none_or(|c| *c == pending.consumer))
+            .map(|(id, pending)| PendingInfo {
+                id: *id,
+                consumer: pending.consumer.clone(),
+                idle_ms: now.saturating_sub(pending.delivered_ms),
+                delivery_count: pending.delivery_count,
+            })
+            .filter(|info| min_idle_ms.is_none_or(|min| info.idle_ms >= min))
+            .take(count)
+            .collect())
+    }
+
+    pub fn xclaim<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        consumer: V,
+        min_idle_ms: u64,
+        ids: &[StreamId],
+        options: &ClaimOptions,
+    ) -> Result<Vec<GroupEntry<Arc<String>>>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        let stream = match map.get_mut(&key) {
+            Some(value) => value.with_stream_mut()?,
+            None => return Err(no_group(&key, &group)),
+        };
+        let entries = stream
+            .claim(
+                &group,
+                consumer.into(),
+                min_idle_ms,
+                ids.iter(),
+                options,
+                now_ms(),
+            )
+            .ok_or_else(|| no_group(&key, &group))?;
+        if !entries.is_empty() {
+            modified(&mut map, &key);
+            drop(map);
+            self.invalidate(&key);
+        }
+        Ok(entries)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn xautoclaim<K, V>(
+        &mut self,
+        key: K,
+        group: V,
+        consumer: V,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        just_id: bool,
+    ) -> Result<AutoClaim<Arc<String>>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let mut map = self.write(&key);
+
+        let stream = match map.get_mut(&key) {
+            Some(value) => value.with_stream_mut()?,
+            None => return Err(no_group(&key, &group)),
+        };
+        let claimed = stream
+            .auto_claim(
+                &group,
+                consumer.into(),
+                min_idle_ms,
+                start,
+                count,
+                just_id,
+                now_ms(),
+            )
+            .ok_or_else(|| no_group(&key, &group))?;
+        if !claimed.1.is_empty() || !claimed.2.is_empty() {
+            modified(&mut map, &key);
+            drop(map);
+            self.invalidate(&key);
+        }
+        Ok(claimed)
+    }
+
+    pub fn xinfo_stream<K>(&self, key: K) -> Result<StreamInfo, Error>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let stream = match map.get(&key) {
+            Some(value) => value.with_stream()?,
+            None => return Err(Error::from(StreamError::NoKey)),
+        };
+        Ok(StreamInfo {
+            length: stream.len(),
+            last_generated_id: stream.last_id(),
+            max_deleted_id: stream.max_deleted_id(),
+            entries_added: stream.entries_added(),
+            recorded_first_id: stream.first_id(),
+            groups: stream.groups().count(),
+            first_entry: stream.first_entry(),
+            last_entry: stream.last_entry(),
+        })
+    }
+
+    pub fn xinfo_groups<K>(&self, key: K) -> Result<Vec<GroupInfo>, Error>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        let stream = match map.get(&key) {
+            Some(value) => value.with_stream()?,
+            None => return Err(Error::from(StreamError::NoKey)),
+        };
+        Ok(stream
+            .groups()
+            .map(|(name, group)| GroupInfo {
+                name: name.clone(),
+                consumers: group.consumers().count(),
+                pending: group.pending_len(),
+                last_delivered_id: group.last_delivered(),
+                entries_read: group.entries_read(),
+                lag: stream.lag(group),
+            })
+            .collect())
+    }
+
+    pub fn xinfo_consumers<K, V>(&self, key: K, group: V) -> Result<Vec<ConsumerInfo>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let group = group.into();
+        let map = self.read(&key);
+
+        let stream = match map.get(&key) {
+            Some(value) => value.with_stream()?,
+            None => return Err(Error::from(StreamError::NoKey)),
+        };
+        let consumer_group = stream.group(&group).ok_or_else(|| no_group(&key, &group))?;
+        let now = now_ms();
+        Ok(consumer_group
+            .consumers()
+            .map(|(name, consumer)| ConsumerInfo {
+                name: name.clone(),
+                pending: consumer.pending_len(),
+                idle_ms: now.saturating_sub(consumer.seen_ms),
+                inactive_ms: consumer.active_ms.map(|active| now.saturating_sub(active)),
+            })
+            .collect())
+    }
+}
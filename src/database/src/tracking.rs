@@ -0,0 +1,234 @@
+use crate::key::Key;
+use crate::{Database, Databases};
+use hashbrown::{HashMap, HashSet};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// What a client caching keys is told to drop.
+#[derive(Clone)]
+pub enum Invalidation {
+    Key(Key),
+    /// Every key, after a flush.
+    All,
+    /// The client its invalidations were redirected to, gone.
+    RedirectBroken(u64),
+}
+
+/// How a client asked to be told of the keys that change with `CLIENT TRACKING on`.
+#[derive(Debug, Clone, Default)]
+pub struct TrackingOptions {
+    /// Another client receiving the invalidations instead.
+    pub redirect: Option<u64>,
+    /// Told of every key starting with one of `prefixes`, all keys without any, rather
+    /// than of those it read.
+    pub bcast: bool,
+    pub prefixes: Vec<String>,
+    /// Only the reads following `CLIENT CACHING yes` are tracked.
+    pub optin: bool,
+    /// The reads following `CLIENT CACHING no` are not tracked.
+    pub optout: bool,
+    /// Not told of the keys it wrote itself.
+    pub noloop: bool,
+}
+
+/// The client a `Database` handle runs the commands of.
+#[derive(Clone, Copy)]
+pub(crate) struct Caller {
+    id: u64,
+    /// Whether the keys it reads are recorded.
+    track_reads: bool,
+}
+
+#[derive(Default)]
+struct Table {
+    /// Where the invalidations of each connection go.
+    mailboxes: HashMap<u64, UnboundedSender<Invalidation>>,
+    clients: HashMap<u64, TrackingOptions>,
+    /// Clients that read each key since it last changed.
+    keys: HashMap<Key, HashSet<u64>>,
+}
+
+impl Table {
+    fn send(&self, id: u64, invalidation: Invalidation) {
+        let redirect = self.clients.get(&id).and_then(|options| options.redirect);
+        match self.mailboxes.get(&redirect.unwrap_or(id)) {
+            Some(mailbox) => {
+                let _ = mailbox.send(invalidation);
+            }
+            None => {
+                if let (Some(mailbox), Some(redirect)) = (self.mailboxes.get(&id), redirect) {
+                    let _ = mailbox.send(Invalidation::RedirectBroken(redirect));
+                }
+            }
+        }
+    }
+}
+
+/// Which clients cache which keys, shared by the databases of a server. Keys are
+/// tracked by name whatever their database, as Redis does.
+#[derive(Default)]
+pub(crate) struct Tracking {
+    /// Clients with tracking on, so that writes skip the table while there are none.
+    clients: AtomicUsize,
+    inner: Mutex<Table>,
+}
+
+impl Tracking {
+    /// Called with the slot of `key` locked, so that a write can't come in between the
+    /// read and its record.
+    pub(crate) fn record(&self, id: u64, key: &Key) {
+        let mut table = self.inner.lock();
+        if table.clients.get(&id).is_some_and(|options| !options.bcast) {
+            table.keys.entry(key.clone()).or_default().insert(id);
+        }
+    }
+
+    /// Tells the clients that read `key`, or that broadcast a prefix of it, to drop it.
+    /// Called once a write changed it, so that a client reading it again once told
+    /// gets the new value, and never for a write that left it as it was.
+    pub(crate) fn invalidate(&self, key: &Key, writer: Option<u64>) {
+        if self.clients.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let mut table = self.inner.lock();
+        let mut targets = table.keys.remove(key).unwrap_or_default();
+        targets.extend(
+            table
+                .clients
+                .iter()
+                .filter(|(_, options)| {
+                    options.bcast
+                        && (options.prefixes.is_empty()
+                            || options
+                                .prefixes
+                                .iter()
+                                .any(|prefix| key.starts_with(prefix.as_str())))
+                })
+                .map(|(id, _)| *id),
+        );
+        for id in targets {
+            let noloop = table.clients.get(&id).is_some_and(|options| options.noloop);
+            if !(noloop && writer == Some(id)) {
+                table.send(id, Invalidation::Key(key.clone()));
+            }
+        }
+    }
+
+    /// Tells every tracking client to drop all it cached, after a flush.
+    pub(crate) fn invalidate_all(&self) {
+        if self.clients.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let mut table = self.inner.lock();
+        table.keys.clear();
+        for id in table.clients.keys() {
+            table.send(*id, Invalidation::All);
+        }
+    }
+
+    fn untrack(&self, table: &mut Table, id: u64) {
+        if table.clients.remove(&id).is_some() {
+            table.keys.retain(|_, clients| {
+                clients.remove(&id);
+                !clients.is_empty()
+            });
+            self.clients.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+/// A connection's place in the tracking table, left when dropped.
+pub struct ClientTracking {
+    id: u64,
+    tracking: Arc<Tracking>,
+    options: Option<TrackingOptions>,
+    receiver: UnboundedReceiver<Invalidation>,
+}
+
+impl Databases {
+    /// Registers the connection `id`, which then receives the invalidations of the
+    /// clients it is the redirect of, and its own once tracking.
+    pub fn client_tracking(&self, id: u64) -> ClientTracking {
+        let (sender, receiver) = unbounded_channel();
+        self.tracking.inner.lock().mailboxes.insert(id, sender);
+        ClientTracking {
+            id,
+            tracking: self.tracking.clone(),
+            options: None,
+            receiver,
+        }
+    }
+}
+
+impl ClientTracking {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// `CLIENT TRACKING on`, `false` when the redirect isn't a connected client.
+    pub fn track(&mut self, options: TrackingOptions) -> bool {
+        let mut table = self.tracking.inner.lock();
+        if options
+            .redirect
+            .is_some_and(|redirect| !table.mailboxes.contains_key(&redirect))
+        {
+            return false;
+        }
+        // switching modes forgets the keys read so far
+        self.tracking.untrack(&mut table, self.id);
+        table.clients.insert(self.id, options.clone());
+        self.tracking.clients.fetch_add(1, Ordering::Release);
+        self.options = Some(options);
+        true
+    }
+
+    /// `CLIENT TRACKING off`.
+    pub fn untrack(&mut self) {
+        self.tracking
+            .untrack(&mut self.tracking.inner.lock(), self.id);
+        self.options = None;
+    }
+
+    pub fn options(&self) -> Option<&TrackingOptions> {
+        self.options.as_ref()
+    }
+
+    pub fn receiver(&mut self) -> &mut UnboundedReceiver<Invalidation> {
+        &mut self.receiver
+    }
+
+    /// The handle the commands of this client go through while tracking, recording the
+    /// keys they read when `track_reads` is set.
+    pub fn database(&self, db: Database, track_reads: bool) -> Database {
+        Database {
+            caller: Some(Caller {
+                id: self.id,
+                track_reads,
+            }),
+            ..db
+        }
+    }
+}
+
+impl Drop for ClientTracking {
+    fn drop(&mut self) {
+        let mut table = self.tracking.inner.lock();
+        self.tracking.untrack(&mut table, self.id);
+        table.mailboxes.remove(&self.id);
+    }
+}
+
+impl Database {
+    pub(crate) fn record_read(&self, key: &Key) {
+        if let Some(caller) = self.caller.filter(|caller| caller.track_reads) {
+            self.tracking.record(caller.id, key);
+        }
+    }
+
+    pub(crate) fn invalidate(&self, key: &Key) {
+        self.tracking
+            .invalidate(key, self.caller.map(|caller| caller.id));
+    }
+}
@@ -1,32 +1,115 @@
+use crate::access::Access;
+use crate::stream::now_ms;
 use crate::TypeError;
-use collections::{List, Set, Strings};
+use collections::{HeapSize, List, Set, SortedSet, Stream, Strings};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+#[derive(Clone)]
 pub enum Item<V> {
     List(List<V>),
     String(Strings<V>),
     Sets(Set<V>),
+    SortedSet(SortedSet<V>),
+    Stream(Stream<V>),
+}
+
+/// Versions come from one counter, so a key deleted and set again gets a new one.
+static VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn next_version() -> u64 {
+    VERSION.fetch_add(1, Ordering::Relaxed)
 }
 
 pub struct Value<V> {
     item: Item<V>,
+    access: Access,
+    /// Unix time in milliseconds the key lives until, the volatile eviction policies
+    /// also rank keys by it.
+    expire_at: Option<u64>,
+    /// Changes with every write, for `WATCH`.
+    version: u64,
+}
+
+/// A copy is a new value to `WATCH`, with a version of its own.
+impl<V: Clone> Clone for Value<V> {
+    fn clone(&self) -> Self {
+        Self {
+            item: self.item.clone(),
+            access: self.access.clone(),
+            expire_at: self.expire_at,
+            version: next_version(),
+        }
+    }
 }
 
 impl<V> Value<V> {
     pub fn new_string(value: Strings<V>) -> Self {
         Self {
             item: Item::String(value),
+            access: Access::new(),
+            version: next_version(),
+            expire_at: None,
         }
     }
 
     pub fn new_list(value: List<V>) -> Self {
         Self {
             item: Item::List(value),
+            access: Access::new(),
+            version: next_version(),
+            expire_at: None,
         }
     }
 
     pub fn new_set(value: Set<V>) -> Self {
         Self {
             item: Item::Sets(value),
+            access: Access::new(),
+            version: next_version(),
+            expire_at: None,
+        }
+    }
+
+    pub fn new_sorted_set(value: SortedSet<V>) -> Self {
+        Self {
+            item: Item::SortedSet(value),
+            access: Access::new(),
+            version: next_version(),
+            expire_at: None,
+        }
+    }
+
+    pub fn new_stream(value: Stream<V>) -> Self {
+        Self {
+            item: Item::Stream(value),
+            access: Access::new(),
+            version: next_version(),
+            expire_at: None,
+        }
+    }
+
+    /// The name `TYPE` replies.
+    pub fn type_name(&self) -> &'static str {
+        match self.item {
+            Item::String(_) => "string",
+            Item::List(_) => "list",
+            Item::Sets(_) => "set",
+            Item::SortedSet(_) => "zset",
+            Item::Stream(_) => "stream",
+        }
+    }
+
+    /// The representation `OBJECT ENCODING` replies. Each type has a single one, named
+    /// after the closest Redis encoding.
+    pub fn encoding(&self) -> &'static str {
+        match self.item {
+            Item::String(_) => "raw",
+            Item::List(_) => "linkedlist",
+            Item::Sets(_) => "hashtable",
+            Item::SortedSet(_) => "skiplist",
+            Item::Stream(_) => "stream",
         }
     }
 
@@ -78,15 +161,140 @@ impl<V> Value<V> {
         }
     }
 
+    pub fn with_sorted_set(&self) -> Result<&SortedSet<V>, TypeError> {
+        if let Item::SortedSet(ref set) = self.item {
+            Ok(set)
+        } else {
+            Err(TypeError)
+        }
+    }
+
+    pub fn with_sorted_set_mut(&mut self) -> Result<&mut SortedSet<V>, TypeError> {
+        if let Item::SortedSet(ref mut set) = self.item {
+            Ok(set)
+        } else {
+            Err(TypeError)
+        }
+    }
+
+    pub fn with_stream(&self) -> Result<&Stream<V>, TypeError> {
+        if let Item::Stream(ref stream) = self.item {
+            Ok(stream)
+        } else {
+            Err(TypeError)
+        }
+    }
+
+    pub fn with_stream_mut(&mut self) -> Result<&mut Stream<V>, TypeError> {
+        if let Item::Stream(ref mut stream) = self.item {
+            Ok(stream)
+        } else {
+            Err(TypeError)
+        }
+    }
+
+    /// Records an access for the eviction policies, done once per command by the
+    /// database rather than by the getters above.
+    pub(crate) fn touch(&self) {
+        self.access.touch();
+    }
+
+    /// Seconds since the key was last accessed.
+    pub fn idle_time(&self) -> u32 {
+        self.access.idle()
+    }
+
+    /// The logarithmic access counter `OBJECT FREQ` replies.
+    pub fn frequency(&self) -> u8 {
+        self.access.frequency()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Gives the value a new version for `WATCH`. The `with_*_mut` getters leave this to
+    /// the caller, to call once the write did change something.
+    pub fn modified(&mut self) {
+        self.version = next_version();
+    }
+
+    pub fn expire_at(&self) -> Option<u64> {
+        self.expire_at
+    }
+
+    /// Whether the time to live is over, the key is then as good as missing.
+    pub fn is_expired(&self) -> bool {
+        self.expire_at
+            .is_some_and(|expire_at| expire_at <= now_ms())
+    }
+
+    pub fn set_expire_at(&mut self, expire_at: Option<u64>) {
+        self.expire_at = expire_at;
+    }
+
     pub fn set_string(&mut self, value: Strings<V>) {
+        self.version = next_version();
         self.item = Item::String(value);
     }
 
     pub fn set_list(&mut self, value: List<V>) {
+        self.version = next_version();
         self.item = Item::List(value);
     }
 
     pub fn set_sets(&mut self, value: Set<V>) {
+        self.version = next_version();
         self.item = Item::Sets(value)
     }
+
+    pub fn set_sorted_set(&mut self, value: SortedSet<V>) {
+        self.version = next_version();
+        self.item = Item::SortedSet(value)
+    }
+
+    pub fn set_stream(&mut self, value: Stream<V>) {
+        self.version = next_version();
+        self.item = Item::Stream(value)
+    }
+}
+
+impl<V> Value<V>
+where
+    V: Hash + Eq + Ord + Clone,
+{
+    /// Heap bytes of the value, collection elements estimated from `samples` of them.
+    pub fn memory_usage(&self, samples: usize) -> usize
+    where
+        V: HeapSize,
+    {
+        match &self.item {
+            Item::String(string) => string.memory_usage(),
+            Item::List(list) => list.memory_usage(samples),
+            Item::Sets(set) => set.memory_usage(samples),
+            Item::SortedSet(set) => set.memory_usage(samples),
+            Item::Stream(stream) => stream.memory_usage(samples),
+        }
+    }
+
+    /// Roughly how many allocations dropping the value gives back.
+    pub fn free_effort(&self) -> usize {
+        match &self.item {
+            Item::String(_) => 1,
+            Item::List(list) => list.llen(),
+            Item::Sets(set) => set.scard(),
+            Item::SortedSet(set) => set.zcard(),
+            Item::Stream(stream) => stream.len(),
+        }
+    }
+}
+
+impl Value<Arc<String>> {
+    /// Owners of the value, a string counts the replies still holding it.
+    pub fn refcount(&self) -> usize {
+        match &self.item {
+            Item::String(string) => Arc::strong_count(string.get()),
+            _ => 1,
+        }
+    }
 }
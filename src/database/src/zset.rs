@@ -0,0 +1,546 @@
+use crate::key::Key;
+use crate::notify::EventClass;
+use crate::value::Value;
+use crate::{Database, Error, TypeError};
+use collections::{AddFlags, AddResult, LexRange, RangeSpec, ScoreRange, SortedSet};
+use hashbrown::HashMap;
+use std::collections::BTreeMap;
+use std::convert::Into;
+use std::iter::IntoIterator;
+use std::ops::Deref;
+use std::sync::Arc;
+
+type Map = HashMap<Key, Value<Arc<String>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOperation {
+    Union,
+    Inter,
+    Diff,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(self, current: f64, score: f64) -> f64 {
+        match self {
+            // inf + -inf is NaN, redis settles it as 0
+            Self::Sum => {
+                let sum = current + score;
+                if sum.is_nan() {
+                    0f64
+                } else {
+                    sum
+                }
+            }
+            Self::Min => current.min(score),
+            Self::Max => current.max(score),
+        }
+    }
+}
+
+fn sorted_set_mut(map: &mut Map, key: Key) -> Result<&mut SortedSet<Arc<String>>, TypeError> {
+    map.entry(key)
+        .or_insert_with(|| Value::new_sorted_set(SortedSet::new()))
+        .with_sorted_set_mut()
+}
+
+/// `true` when the set was emptied and so removed.
+fn remove_if_empty(map: &mut Map, key: &Key) -> bool {
+    let is_empty = map
+        .get(key)
+        .and_then(|value| value.with_sorted_set().ok())
+        .is_some_and(|set| set.zcard() == 0);
+    if is_empty {
+        map.remove(key);
+    }
+    is_empty
+}
+
+impl Database {
+    /// Events for members taken out of a sorted set, then for the key when emptied.
+    fn notify_removed(&self, event: &str, key: &Key, removed: usize, emptied: bool) {
+        if removed > 0 {
+            self.notify(EventClass::SortedSet, event, key);
+        }
+        if emptied {
+            self.notify(EventClass::Generic, "del", key);
+        }
+    }
+}
+
+impl Database {
+    /// Returns how many members were added and how many existing ones changed score.
+    pub fn zadd<K, V, I>(
+        &mut self,
+        key: K,
+        members: I,
+        flags: AddFlags,
+    ) -> Result<(usize, usize), Error>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = (f64, V)>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        if flags.xx && !map.contains_key(&key) {
+            return Ok((0, 0));
+        }
+
+        let set = sorted_set_mut(&mut map, key.clone())?;
+        let mut added = 0;
+        let mut changed = 0;
+        let mut result = Ok(());
+        for (score, member) in members {
+            match set.add(member.into(), score, flags) {
+                AddResult::Added(_) => added += 1,
+                AddResult::Updated(_) => changed += 1,
+                AddResult::Unchanged(_) | AddResult::Ignored => {}
+                AddResult::NaN => {
+                    result = Err(Error::NaN);
+                    break;
+                }
+            }
+        }
+        if added + changed > 0 {
+            map.get_mut(&key).unwrap().modified();
+        }
+        remove_if_empty(&mut map, &key);
+        drop(map);
+
+        if added + changed > 0 {
+            self.notify(EventClass::SortedSet, "zadd", &key);
+        }
+        if added > 0 {
+            self.blocking.ready(&key);
+        }
+        result.map(|_| (added, changed))
+    }
+
+    /// `ZINCRBY`, also used by `ZADD ... INCR`. `None` when the flags prevented the update.
+    pub fn zincrby<K, V>(
+        &mut self,
+        key: K,
+        member: V,
+        increment: f64,
+        flags: AddFlags,
+    ) -> Result<Option<f64>, Error>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        if flags.xx && !map.contains_key(&key) {
+            return Ok(None);
+        }
+
+        let set = sorted_set_mut(&mut map, key.clone())?;
+        let incremented = set.incr(member.into(), increment, flags);
+        if let AddResult::Added(_) | AddResult::Updated(_) = incremented {
+            map.get_mut(&key).unwrap().modified();
+        }
+        let result = match incremented {
+            AddResult::Added(score) | AddResult::Updated(score) | AddResult::Unchanged(score) => {
+                Ok(Some(score))
+            }
+            AddResult::Ignored => Ok(None),
+            AddResult::NaN => Err(Error::NaN),
+        };
+        remove_if_empty(&mut map, &key);
+        drop(map);
+
+        if let Ok(Some(_)) = result {
+            self.notify(EventClass::SortedSet, "zincr", &key);
+            self.blocking.ready(&key);
+        }
+        result
+    }
+
+    pub fn zrem<K, V, I>(&mut self, key: K, members: I) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+        I: IntoIterator<Item = V>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let removed = match map.get_mut(&key) {
+            Some(value) => {
+                let members = members
+                    .into_iter()
+                    .map(|member| member.into())
+                    .collect::<Vec<Arc<String>>>();
+                let removed = value.with_sorted_set_mut()?.zrem(members.iter());
+                if removed > 0 {
+                    value.modified();
+                }
+                removed
+            }
+            None => 0,
+        };
+        let emptied = remove_if_empty(&mut map, &key);
+        drop(map);
+
+        self.notify_removed("zrem", &key, removed, emptied);
+        Ok(removed)
+    }
+
+    pub fn zscore<K, V>(&self, key: K, member: V) -> Result<Option<f64>, TypeError>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        match map.get(&key) {
+            Some(value) => Ok(value.with_sorted_set()?.score(&member.into())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn zcard<K>(&self, key: K) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_sorted_set().map(|set| set.zcard()))
+            .unwrap_or(Ok(0))
+    }
+
+    pub fn zrank<K, V>(&self, key: K, member: V, rev: bool) -> Result<Option<usize>, TypeError>
+    where
+        K: Into<Key>,
+        V: Into<Arc<String>>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        match map.get(&key) {
+            Some(value) => Ok(value.with_sorted_set()?.rank(&member.into(), rev)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn zrange<K>(
+        &self,
+        key: K,
+        spec: &RangeSpec<Arc<String>>,
+        rev: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Result<Vec<(Arc<String>, f64)>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| {
+                value
+                    .with_sorted_set()
+                    .map(|set| set.range(spec, rev, offset, count))
+            })
+            .unwrap_or(Ok(Vec::new()))
+    }
+
+    pub fn zcount<K>(&self, key: K, range: &ScoreRange) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_sorted_set().map(|set| set.count(range)))
+            .unwrap_or(Ok(0))
+    }
+
+    pub fn zlexcount<K>(&self, key: K, range: &LexRange<Arc<String>>) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let map = self.read(&key);
+
+        map.get(&key)
+            .map(|value| value.with_sorted_set().map(|set| set.lex_count(range)))
+            .unwrap_or(Ok(0))
+    }
+
+    /// `ZPOPMIN`, or `ZPOPMAX` when `max` is set.
+    pub fn zpop<K>(
+        &mut self,
+        key: K,
+        count: usize,
+        max: bool,
+    ) -> Result<Vec<(Arc<String>, f64)>, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let list = match map.get_mut(&key) {
+            Some(value) => {
+                let list = value.with_sorted_set_mut()?.pop(count, max);
+                if !list.is_empty() {
+                    value.modified();
+                }
+                list
+            }
+            None => Vec::new(),
+        };
+        let emptied = remove_if_empty(&mut map, &key);
+        let remains = map.contains_key(&key);
+        drop(map);
+
+        let event = if max { "zpopmax" } else { "zpopmin" };
+        self.notify_removed(event, &key, list.len(), emptied);
+        // there is more for the next client parked on this key
+        if !list.is_empty() && remains {
+            self.blocking.ready(&key);
+        }
+        Ok(list)
+    }
+
+    /// `ZREMRANGEBYRANK`, `ZREMRANGEBYSCORE` and `ZREMRANGEBYLEX`.
+    pub fn zremrange<K>(
+        &mut self,
+        key: K,
+        spec: &RangeSpec<Arc<String>>,
+    ) -> Result<usize, TypeError>
+    where
+        K: Into<Key>,
+    {
+        let key = key.into();
+        let mut map = self.write(&key);
+
+        let removed = match map.get_mut(&key) {
+            Some(value) => {
+                let removed = value.with_sorted_set_mut()?.remove_range(spec);
+                if removed > 0 {
+                    value.modified();
+                }
+                removed
+            }
+            None => 0,
+        };
+        let emptied = remove_if_empty(&mut map, &key);
+        drop(map);
+
+        let event = match spec {
+            RangeSpec::Rank(_, _) => "zremrangebyrank",
+            RangeSpec::Score(_) => "zremrangebyscore",
+            RangeSpec::Lex(_) => "zremrangebylex",
+        };
+        self.notify_removed(event, &key, removed, emptied);
+        Ok(removed)
+    }
+}
+
+/// Members of a sorted set, or of a plain set with every score being 1.
+fn zsource(map: &Map, key: &Key) -> Result<HashMap<Arc<String>, f64>, TypeError> {
+    match map.get(key) {
+        Some(value) => match value.with_sorted_set() {
+            Ok(set) => Ok(set
+                .iter()
+                .map(|(member, score)| (member.clone(), score))
+                .collect()),
+            Err(_) => Ok(value
+                .with_set()?
+                .smembers()
+                .map(|member| (member.clone(), 1f64))
+                .collect()),
+        },
+        None => Ok(HashMap::new()),
+    }
+}
+
+impl Database {
+    /// Combines the sets of `keys`, their slots being locked in `guards`.
+    fn zcombine_set<G>(
+        &self,
+        guards: &BTreeMap<usize, G>,
+        operation: SetOperation,
+        keys: &[Key],
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    ) -> Result<SortedSet<Arc<String>>, TypeError>
+    where
+        G: Deref<Target = Map>,
+    {
+        let sources = keys
+            .iter()
+            .map(|key| zsource(&guards[&Self::find_point(key.as_bytes())], key))
+            .collect::<Result<Vec<_>, TypeError>>()?;
+        let weight = |index: usize, score: f64| {
+            let weight = weights
+                .as_ref()
+                .and_then(|weights| weights.get(index))
+                .copied()
+                .unwrap_or(1f64);
+            // 0 * inf is NaN as well
+            let score = score * weight;
+            if score.is_nan() {
+                0f64
+            } else {
+                score
+            }
+        };
+
+        let mut result: HashMap<Arc<String>, f64> = HashMap::new();
+        let mut sources = sources.into_iter();
+        let first = sources.next().unwrap_or_default();
+        match operation {
+            SetOperation::Union => {
+                for (index, source) in std::iter::once(first).chain(sources).enumerate() {
+                    for (member, score) in source {
+                        let score = weight(index, score);
+                        result
+                            .entry(member)
+                            .and_modify(|current| *current = aggregate.apply(*current, score))
+                            .or_insert(score);
+                    }
+                }
+            }
+            SetOperation::Inter => {
+                let others = sources.collect::<Vec<_>>();
+                'member: for (member, score) in first {
+                    let mut score = weight(0, score);
+                    for (index, source) in others.iter().enumerate() {
+                        match source.get(&member) {
+                            Some(other) => {
+                                score = aggregate.apply(score, weight(index + 1, *other))
+                            }
+                            None => continue 'member,
+                        }
+                    }
+                    result.insert(member, score);
+                }
+            }
+            SetOperation::Diff => {
+                let others = sources.collect::<Vec<_>>();
+                for (member, score) in first {
+                    if others.iter().all(|source| !source.contains_key(&member)) {
+                        result.insert(member, score);
+                    }
+                }
+            }
+        }
+
+        let mut set = SortedSet::new();
+        for (member, score) in result {
+            set.add(member, score, AddFlags::default());
+        }
+        Ok(set)
+    }
+
+    /// `ZUNION`, `ZINTER` and `ZDIFF`, ordered by score.
+    pub fn zcombine<I, K>(
+        &self,
+        operation: SetOperation,
+        keys: I,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    ) -> Result<Vec<(Arc<String>, f64)>, TypeError>
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        let keys = keys.into_iter().map(Into::into).collect::<Vec<Key>>();
+        let guards = self.lock_slots(&keys, |slot| slot.read());
+        self.zcombine_set(&guards, operation, &keys, weights, aggregate)
+            .map(|set| set.range(&RangeSpec::Rank(0, -1), false, 0, None))
+    }
+
+    /// `ZUNIONSTORE`, `ZINTERSTORE` and `ZDIFFSTORE`, replacing `destination` with the result.
+    pub fn zcombine_store<D, I, K>(
+        &mut self,
+        destination: D,
+        operation: SetOperation,
+        keys: I,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    ) -> Result<usize, TypeError>
+    where
+        D: Into<Key>,
+        I: IntoIterator<Item = K>,
+        K: Into<Key>,
+    {
+        let destination = destination.into();
+        let keys = keys.into_iter().map(Into::into).collect::<Vec<Key>>();
+        let mut all = keys.clone();
+        all.push(destination.clone());
+        // the sources stay locked until the result replaced the destination
+        let mut guards = self.lock_slots(&all, |slot| slot.write());
+        let set = self.zcombine_set(&guards, operation, &keys, weights, aggregate)?;
+        let len = set.zcard();
+
+        let map = guards
+            .get_mut(&Self::find_point(destination.as_bytes()))
+            .unwrap();
+        if len == 0 {
+            if map.remove(&destination).is_some() {
+                drop(guards);
+                self.notify(EventClass::Generic, "del", &destination);
+            }
+        } else {
+            map.insert(destination.clone(), Value::new_sorted_set(set));
+            drop(guards);
+            let event = match operation {
+                SetOperation::Union => "zunionstore",
+                SetOperation::Inter => "zinterstore",
+                SetOperation::Diff => "zdiffstore",
+            };
+            self.notify(EventClass::SortedSet, event, &destination);
+            self.blocking.ready(&destination);
+        }
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_zcombine_store() {
+    let s = String::from;
+    let mut db = Database::default();
+    let _ = db.zadd(
+        s("a"),
+        [(1f64, s("x")), (2f64, s("y"))],
+        AddFlags::default(),
+    );
+    let _ = db.zadd(s("b"), [(3f64, s("y"))], AddFlags::default());
+    // the destination being one of the sources shares its slot
+    let len = db.zcombine_store(
+        s("a"),
+        SetOperation::Union,
+        [s("a"), s("b")],
+        None,
+        Aggregate::Sum,
+    );
+    assert_eq!(len.ok(), Some(2));
+    assert_eq!(db.zscore(s("a"), s("y")).ok(), Some(Some(5f64)));
+    let len = db.zcombine_store(
+        s("c"),
+        SetOperation::Inter,
+        [s("a"), s("d")],
+        None,
+        Aggregate::Sum,
+    );
+    assert_eq!(len.ok(), Some(0));
+    assert_eq!(db.zscore(s("c"), s("x")).ok(), Some(None));
+}
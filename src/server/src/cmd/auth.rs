@@ -0,0 +1,118 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use std::convert::Infallible;
+
+/// The only user there is, the one `requirepass` is the password of.
+const DEFAULT_USER: &str = "default";
+
+pub(crate) struct Auth {
+    /// `None` for the legacy form, which authenticates the default user.
+    username: Option<String>,
+    password: String,
+}
+
+impl Builder for Auth {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let (username, password) = match adpater.get_total() {
+            1 => (None, adpater.get_field::<String, Infallible>()?),
+            2 => (
+                Some(adpater.get_field::<String, Infallible>()?),
+                adpater.get_field::<String, Infallible>()?,
+            ),
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "wrong number of arguments for 'auth' command",
+                )))
+            }
+        };
+        Ok(Self { username, password })
+    }
+}
+
+impl Auth {
+    pub(crate) fn new(username: String, password: String) -> Self {
+        Self {
+            username: Some(username),
+            password,
+        }
+    }
+
+    pub fn apply(self, requirepass: Option<&str>, authenticated: &mut bool) -> Reply {
+        match self.check(requirepass) {
+            Ok(()) => {
+                *authenticated = true;
+                Reply::Simple(String::from("OK"))
+            }
+            Err(reply) => reply,
+        }
+    }
+
+    /// Whether the credentials are those of the default user, which takes any password
+    /// when the server has none.
+    pub(crate) fn check(&self, requirepass: Option<&str>) -> Result<(), Reply> {
+        if self
+            .username
+            .as_deref()
+            .is_some_and(|username| username != DEFAULT_USER)
+        {
+            return Err(wrong_pass());
+        }
+        match requirepass {
+            Some(requirepass) if equal(requirepass.as_bytes(), self.password.as_bytes()) => {
+                Ok(())
+            }
+            Some(_) => Err(wrong_pass()),
+            None if self.username.is_some() => Ok(()),
+            None => Err(Reply::Error(String::from(
+                "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?",
+            ))),
+        }
+    }
+}
+
+fn wrong_pass() -> Reply {
+    Reply::Error(String::from(
+        "WRONGPASS invalid username-password pair or user is disabled.",
+    ))
+}
+
+/// Compares every byte whatever the first difference, so that the time taken tells
+/// nothing of the password.
+fn equal(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[test]
+fn test_auth() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    let config = Config {
+        requirepass: Some(String::from("secret")),
+        ..Config::default()
+    };
+    serve(config, |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(
+            client.call(&["SET", "k", "v"]).await,
+            "-NOAUTH Authentication required.\r\n"
+        );
+        assert_eq!(
+            client.call(&["AUTH", "wrong"]).await,
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n"
+        );
+        assert_eq!(client.call(&["AUTH", "secret"]).await, "+OK\r\n");
+        assert_eq!(client.call(&["SET", "k", "v"]).await, "+OK\r\n");
+        assert_eq!(client.call(&["GET", "k"]).await, "$1\r\nv\r\n");
+
+        let mut other = Client::connect(addr).await;
+        assert_eq!(
+            other.call(&["GET", "k"]).await,
+            "-NOAUTH Authentication required.\r\n"
+        );
+        assert_eq!(other.call(&["AUTH", "default", "secret"]).await, "+OK\r\n");
+        assert_eq!(other.call(&["GET", "k"]).await, "$1\r\nv\r\n");
+    });
+}
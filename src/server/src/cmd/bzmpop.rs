@@ -0,0 +1,79 @@
+use crate::cmd::bzpopmin::{blocking_pop, parse_timeout};
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::time::Duration;
+
+pub(crate) struct BZMPop {
+    timeout: Option<Duration>,
+    keys: Vec<String>,
+    max: bool,
+    count: usize,
+}
+
+impl Builder for BZMPop {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let timeout = parse_timeout(&adpater.get_field::<String, Infallible>()?)?;
+        let numkeys = adpater.get_field::<usize, ParseIntError>()?;
+        if numkeys == 0 || numkeys >= adpater.get_total() {
+            return Err(Error::Protocol(String::from(
+                "numkeys should be greater than 0",
+            )));
+        }
+        let keys = (0..numkeys)
+            .map(|_| adpater.get_field::<String, Infallible>())
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let max = match adpater
+            .get_field::<String, Infallible>()?
+            .to_uppercase()
+            .as_str()
+        {
+            "MIN" => false,
+            "MAX" => true,
+            _ => return Err(Error::Protocol(String::from("syntax error"))),
+        };
+
+        let mut count = 1;
+        if adpater.get_total() > 0 {
+            let field = adpater.get_field::<String, Infallible>()?;
+            if field.to_uppercase() != "COUNT" || adpater.get_total() != 1 {
+                return Err(Error::Protocol(String::from("syntax error")));
+            }
+            count = adpater.get_field::<usize, ParseIntError>()?;
+            if count == 0 {
+                return Err(Error::Protocol(String::from(
+                    "count should be greater than 0",
+                )));
+            }
+        }
+
+        Ok(Self {
+            timeout,
+            keys,
+            max,
+            count,
+        })
+    }
+}
+
+impl BZMPop {
+    pub async fn apply(self, db: Database) -> Reply {
+        let popped = blocking_pop(db, self.keys, self.timeout, self.count, self.max).await;
+        Reply::from(popped.map(|popped| {
+            popped.map(|(key, list)| {
+                let list = list
+                    .into_iter()
+                    .map(|(member, score)| {
+                        Reply::Array(vec![Reply::from(member), Reply::from(score)])
+                    })
+                    .collect::<Vec<Reply>>();
+                Reply::Array(vec![Reply::from(key), Reply::Array(list)])
+            })
+        }))
+    }
+}
@@ -0,0 +1,45 @@
+use crate::cmd::bzpopmin::{blocking_pop, parse_timeout};
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+use std::time::Duration;
+
+pub(crate) struct BZPopMax {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+impl Builder for BZPopMax {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() < 2 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'bzpopmax' command",
+            )));
+        }
+        Ok(Self {
+            keys: (0..adpater.get_total() - 1)
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+            timeout: parse_timeout(&adpater.get_field::<String, Infallible>()?)?,
+        })
+    }
+}
+
+impl BZPopMax {
+    pub async fn apply(self, db: Database) -> Reply {
+        let popped = blocking_pop(db, self.keys, self.timeout, 1, true).await;
+        Reply::from(popped.map(|popped| {
+            popped.map(|(key, mut list)| {
+                let (member, score) = list.remove(0);
+                Reply::Array(vec![
+                    Reply::from(key),
+                    Reply::from(member),
+                    Reply::from(score),
+                ])
+            })
+        }))
+    }
+}
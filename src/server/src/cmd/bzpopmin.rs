@@ -0,0 +1,143 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::cmd::zadd::parse_score;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, TypeError};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+pub(crate) fn parse_timeout(field: &str) -> Result<Option<Duration>, Error> {
+    let timeout = parse_score(field)
+        .map_err(|_| Error::Protocol(String::from("timeout is not a float or out of range")))?;
+    if timeout < 0f64 {
+        return Err(Error::Protocol(String::from("timeout is negative")));
+    }
+    if timeout == 0f64 {
+        return Ok(None);
+    }
+    // `inf` and the like parse as scores but are no duration
+    Duration::try_from_secs_f64(timeout)
+        .map(Some)
+        .map_err(|_| Error::Protocol(String::from("timeout is out of range")))
+}
+
+/// Pops from the first non-empty key, parking the connection until a key is
+/// served or `timeout` elapses. A `None` timeout blocks forever.
+pub(crate) async fn blocking_pop(
+    db: Database,
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+    count: usize,
+    max: bool,
+) -> Result<Option<(String, Vec<(Arc<String>, f64)>)>, TypeError> {
+    let mut db = db;
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    // parked before looking, so a member added in between still wakes us, and once
+    // for all so that the client keeps its turn when woken for nothing
+    let blocked = db.block(keys.iter().cloned());
+    loop {
+        for key in keys.iter() {
+            let list = db.zpop(key.clone(), count, max)?;
+            if !list.is_empty() {
+                return Ok(Some((key.clone(), list)));
+            }
+        }
+
+        match deadline {
+            Some(deadline) => {
+                if timeout_at(deadline, blocked.wait()).await.is_err() {
+                    return Ok(None);
+                }
+            }
+            None => blocked.wait().await,
+        }
+    }
+}
+
+pub(crate) struct BZPopMin {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+impl Builder for BZPopMin {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() < 2 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'bzpopmin' command",
+            )));
+        }
+        Ok(Self {
+            keys: (0..adpater.get_total() - 1)
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+            timeout: parse_timeout(&adpater.get_field::<String, Infallible>()?)?,
+        })
+    }
+}
+
+impl BZPopMin {
+    pub async fn apply(self, db: Database) -> Reply {
+        let popped = blocking_pop(db, self.keys, self.timeout, 1, false).await;
+        Reply::from(popped.map(|popped| {
+            popped.map(|(key, mut list)| {
+                let (member, score) = list.remove(0);
+                Reply::Array(vec![
+                    Reply::from(key),
+                    Reply::from(member),
+                    Reply::from(score),
+                ])
+            })
+        }))
+    }
+}
+
+#[test]
+fn test_parse_timeout() {
+    assert_eq!(parse_timeout("0").ok(), Some(None));
+    assert_eq!(
+        parse_timeout("1.5").ok(),
+        Some(Some(Duration::from_millis(1500)))
+    );
+    assert!(parse_timeout("-1").is_err());
+    assert!(parse_timeout("inf").is_err());
+    assert!(parse_timeout("+inf").is_err());
+    assert!(parse_timeout("1e300").is_err());
+}
+
+#[test]
+fn test_blocking_order() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut first = Client::connect(addr).await;
+        let mut second = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        first.send(&["BZPOPMIN", "z", "0"]).await;
+        assert_eq!(first.try_read().await, None);
+        second.send(&["BZPOPMIN", "z", "0"]).await;
+        assert_eq!(second.try_read().await, None);
+
+        // woken for a member taken before it looks, the first client keeps its turn
+        other.send(&["ZADD", "z", "1", "a"]).await;
+        other.send(&["ZPOPMIN", "z"]).await;
+        assert_eq!(other.read().await, ":1\r\n");
+        other.read().await;
+        assert_eq!(first.try_read().await, None);
+
+        assert_eq!(other.call(&["ZADD", "z", "2", "b"]).await, ":1\r\n");
+        assert_eq!(
+            first.try_read().await.as_deref(),
+            Some("*3\r\n$1\r\nz\r\n$1\r\nb\r\n$1\r\n2\r\n")
+        );
+        assert_eq!(second.try_read().await, None);
+        assert_eq!(other.call(&["ZADD", "z", "3", "c"]).await, ":1\r\n");
+        assert_eq!(
+            second.try_read().await.as_deref(),
+            Some("*3\r\n$1\r\nz\r\n$1\r\nc\r\n$1\r\n3\r\n")
+        );
+    });
+}
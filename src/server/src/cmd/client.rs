@@ -0,0 +1,251 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{ClientTracking, TrackingOptions};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+enum Subcommand {
+    Id,
+    /// `None` for `off`.
+    Tracking(Option<TrackingOptions>),
+    Caching(bool),
+    GetRedir,
+}
+
+pub(crate) struct Client {
+    subcommand: Subcommand,
+}
+
+impl Builder for Client {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "ID" => Subcommand::Id,
+            "GETREDIR" => Subcommand::GetRedir,
+            "CACHING" => {
+                let mode = adpater.get_field::<String, Infallible>()?.to_uppercase();
+                match mode.as_str() {
+                    "YES" => Subcommand::Caching(true),
+                    "NO" => Subcommand::Caching(false),
+                    _ => return Err(Error::Protocol(String::from("syntax error"))),
+                }
+            }
+            "TRACKING" => {
+                let switch = adpater.get_field::<String, Infallible>()?.to_uppercase();
+                let mut options = TrackingOptions::default();
+                while adpater.get_total() > 0 {
+                    let option = adpater.get_field::<String, Infallible>()?.to_uppercase();
+                    match option.as_str() {
+                        "REDIRECT" => {
+                            options.redirect = Some(adpater.get_field::<u64, ParseIntError>()?)
+                        }
+                        "PREFIX" => options
+                            .prefixes
+                            .push(adpater.get_field::<String, Infallible>()?),
+                        "BCAST" => options.bcast = true,
+                        "OPTIN" => options.optin = true,
+                        "OPTOUT" => options.optout = true,
+                        "NOLOOP" => options.noloop = true,
+                        _ => return Err(Error::Protocol(String::from("syntax error"))),
+                    }
+                }
+                match switch.as_str() {
+                    "ON" => Subcommand::Tracking(Some(options)),
+                    "OFF" => Subcommand::Tracking(None),
+                    _ => return Err(Error::Protocol(String::from("syntax error"))),
+                }
+            }
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'client' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand })
+    }
+}
+
+impl Client {
+    /// `caching` is what `CLIENT CACHING` asked of the next command.
+    pub fn apply(self, tracking: &mut ClientTracking, caching: &mut Option<bool>) -> Reply {
+        match self.subcommand {
+            Subcommand::Id => Reply::from(tracking.id()),
+            Subcommand::GetRedir => match tracking.options() {
+                Some(options) => Reply::from(options.redirect.map_or(0, |id| id as i64)),
+                None => Reply::from(-1),
+            },
+            Subcommand::Caching(yes) => {
+                let (optin, optout) = tracking
+                    .options()
+                    .map_or((false, false), |options| (options.optin, options.optout));
+                match (yes, optin, optout) {
+                    (true, true, _) | (false, _, true) => {
+                        *caching = Some(yes);
+                        Reply::Simple(String::from("OK"))
+                    }
+                    (_, false, false) => Reply::Error(String::from(
+                        "ERR CLIENT CACHING can be called only when the client is in tracking mode with OPTIN or OPTOUT mode enabled",
+                    )),
+                    (true, _, _) => Reply::Error(String::from(
+                        "ERR CLIENT CACHING YES is only valid when tracking is enabled in OPTIN mode.",
+                    )),
+                    (false, _, _) => Reply::Error(String::from(
+                        "ERR CLIENT CACHING NO is only valid when tracking is enabled in OPTOUT mode.",
+                    )),
+                }
+            }
+            Subcommand::Tracking(None) => {
+                tracking.untrack();
+                Reply::Simple(String::from("OK"))
+            }
+            Subcommand::Tracking(Some(options)) => {
+                if options.optin && options.optout {
+                    return Reply::Error(String::from("ERR You can't use both OPTIN and OPTOUT"));
+                }
+                if options.bcast && (options.optin || options.optout) {
+                    return Reply::Error(String::from(
+                        "ERR OPTIN and OPTOUT are not compatible with BCAST",
+                    ));
+                }
+                if !options.bcast && !options.prefixes.is_empty() {
+                    return Reply::Error(String::from(
+                        "ERR PREFIX option requires BCAST mode to be enabled",
+                    ));
+                }
+                if tracking.track(options) {
+                    Reply::Simple(String::from("OK"))
+                } else {
+                    Reply::Error(String::from(
+                        "ERR The client ID you want redirect to does not exist",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tracking() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    let invalidate = |key: &str| {
+        format!(
+            "*3\r\n$7\r\nmessage\r\n$20\r\n__redis__:invalidate\r\n*1\r\n${}\r\n{}\r\n",
+            key.len(),
+            key
+        )
+    };
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut redirect = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        let id = redirect.call(&["CLIENT", "ID"]).await;
+        let id = id[1..].trim_end();
+        redirect.call(&["SUBSCRIBE", "__redis__:invalidate"]).await;
+        let track = ["CLIENT", "TRACKING", "ON", "REDIRECT", id];
+        assert_eq!(client.call(&track).await, "+OK\r\n");
+        client.call(&["GET", "a"]).await;
+        other.call(&["SET", "a", "1"]).await;
+        assert_eq!(redirect.read().await, invalidate("a"));
+        // only once per read
+        other.call(&["SET", "a", "2"]).await;
+        assert_eq!(redirect.try_read().await, None);
+        // nor for keys it never read
+        other.call(&["SET", "b", "1"]).await;
+        assert_eq!(redirect.try_read().await, None);
+
+        client.call(&["CLIENT", "TRACKING", "OFF"]).await;
+        assert_eq!(
+            client
+                .call(&[&track[..], &["BCAST", "PREFIX", "user:"]].concat())
+                .await,
+            "+OK\r\n"
+        );
+        other.call(&["SET", "user:1", "1"]).await;
+        assert_eq!(redirect.read().await, invalidate("user:1"));
+        other.call(&["SET", "item:1", "1"]).await;
+        assert_eq!(redirect.try_read().await, None);
+
+        client.call(&["CLIENT", "TRACKING", "OFF"]).await;
+        client.call(&[&track[..], &["OPTIN"]].concat()).await;
+        client.call(&["GET", "a"]).await;
+        client.call(&["CLIENT", "CACHING", "YES"]).await;
+        client.call(&["GET", "b"]).await;
+        other.call(&["SET", "a", "3"]).await;
+        other.call(&["SET", "b", "2"]).await;
+        assert_eq!(redirect.read().await, invalidate("b"));
+        assert_eq!(redirect.try_read().await, None);
+
+        client.call(&["CLIENT", "TRACKING", "OFF"]).await;
+        client.call(&[&track[..], &["NOLOOP"]].concat()).await;
+        client.call(&["GET", "a"]).await;
+        client.call(&["SET", "a", "4"]).await;
+        assert_eq!(redirect.try_read().await, None);
+
+        assert_eq!(
+            client
+                .call(&["CLIENT", "TRACKING", "ON", "REDIRECT", "0"])
+                .await,
+            "-ERR The client ID you want redirect to does not exist\r\n"
+        );
+    });
+}
+
+#[test]
+fn test_tracking_unchanged() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut redirect = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        let id = redirect.call(&["CLIENT", "ID"]).await;
+        let id = id[1..].trim_end();
+        redirect.call(&["SUBSCRIBE", "__redis__:invalidate"]).await;
+        client
+            .call(&["CLIENT", "TRACKING", "ON", "REDIRECT", id])
+            .await;
+        other.call(&["ZADD", "z", "1", "a"]).await;
+        client.call(&["ZSCORE", "z", "a"]).await;
+
+        // writes that leave the key as it was invalidate nothing
+        assert_eq!(other.call(&["ZADD", "z", "1", "a"]).await, ":0\r\n");
+        assert_eq!(other.call(&["ZREM", "z", "b"]).await, ":0\r\n");
+        assert_eq!(redirect.try_read().await, None);
+
+        other.call(&["ZREM", "z", "a"]).await;
+        assert_eq!(
+            redirect.read().await,
+            "*3\r\n$7\r\nmessage\r\n$20\r\n__redis__:invalidate\r\n*1\r\n$1\r\nz\r\n"
+        );
+    });
+}
+
+#[test]
+fn test_tracking_push() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        client.call(&["HELLO", "3"]).await;
+        assert_eq!(client.call(&["CLIENT", "TRACKING", "ON"]).await, "+OK\r\n");
+        client.call(&["GET", "a"]).await;
+        other.call(&["SET", "a", "1"]).await;
+        assert_eq!(
+            client.read().await,
+            ">2\r\n$10\r\ninvalidate\r\n*1\r\n$1\r\na\r\n"
+        );
+        client.call(&["GET", "a"]).await;
+        other.call(&["FLUSHALL"]).await;
+        assert_eq!(client.read().await, ">2\r\n$10\r\ninvalidate\r\n_\r\n");
+    });
+}
@@ -0,0 +1,54 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, Databases};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+/// `COPY`
+pub(crate) struct CopyKey {
+    key: String,
+    destination: String,
+    db: Option<usize>,
+    replace: bool,
+}
+
+impl Builder for CopyKey {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let destination = adpater.get_field::<String, Infallible>()?;
+        let mut db = None;
+        let mut replace = false;
+        while adpater.get_total() > 0 {
+            let option = adpater.get_field::<String, Infallible>()?.to_uppercase();
+            match option.as_str() {
+                "DB" => db = Some(adpater.get_field::<usize, ParseIntError>()?),
+                "REPLACE" => replace = true,
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+        Ok(Self {
+            key,
+            destination,
+            db,
+            replace,
+        })
+    }
+}
+
+impl CopyKey {
+    pub fn apply(self, db: Database, databases: &Databases) -> Reply {
+        let target = match self.db {
+            Some(index) => match databases.get(index) {
+                Some(target) => target,
+                None => return Reply::Error(String::from("ERR DB index is out of range")),
+            },
+            None => db.clone(),
+        };
+        Reply::from(
+            db.copy(self.key, &target, self.destination, self.replace)
+                .map(|copied| copied as u8),
+        )
+    }
+}
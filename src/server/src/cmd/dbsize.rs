@@ -0,0 +1,19 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+
+pub(crate) struct DbSize {}
+
+impl Builder for DbSize {
+    fn build<'a>(_: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {})
+    }
+}
+
+impl Apply for DbSize {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.dbsize())
+    }
+}
@@ -1,5 +1,5 @@
 use crate::cmd::field_builder::FieldBuilder;
-use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::traits::Builder;
 use crate::reply::Reply;
 use crate::service::Error;
 use database::{Database, Key};
@@ -20,9 +20,14 @@ impl Builder for Delete {
     }
 }
 
-impl Apply for Delete {
-    fn apply(self, db: Database) -> Reply {
+impl Delete {
+    /// `lazy` frees the values in the background like `UNLINK`.
+    pub fn apply(self, db: Database, lazy: bool) -> Reply {
         let mut db = db;
-        Reply::from(db.delete(self.keys))
+        if lazy {
+            Reply::from(db.unlink(self.keys))
+        } else {
+            Reply::from(db.delete(self.keys))
+        }
     }
 }
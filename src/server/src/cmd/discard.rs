@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use crate::transaction::{Transaction, Watched};
+
+pub(crate) struct Discard {}
+
+impl Builder for Discard {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'discard' command",
+            )));
+        }
+        Ok(Self {})
+    }
+}
+
+impl Discard {
+    pub fn apply(self, transaction: &mut Option<Transaction>, watched: &mut Vec<Watched>) -> Reply {
+        match transaction.take() {
+            Some(_) => {
+                watched.clear();
+                Reply::Simple(String::from("OK"))
+            }
+            None => Reply::Error(String::from("ERR DISCARD without MULTI")),
+        }
+    }
+}
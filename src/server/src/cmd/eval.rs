@@ -0,0 +1,53 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::scripting::{Call, Scripts};
+use crate::service::Error;
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::sync::Arc;
+
+/// `numkeys [key ...] [arg ...]`, shared with `EVALSHA`.
+pub(crate) fn script_args(
+    adpater: &mut FieldBuilder<'_>,
+) -> Result<(Vec<String>, Vec<String>), Error> {
+    let numkeys = adpater.get_field::<usize, ParseIntError>()?;
+    if numkeys > adpater.get_total() {
+        return Err(Error::Protocol(String::from(
+            "Number of keys can't be greater than number of args",
+        )));
+    }
+    let keys = (0..numkeys)
+        .map(|_| adpater.get_field::<String, Infallible>())
+        .collect::<Result<Vec<String>, Error>>()?;
+    let args = (0..adpater.get_total())
+        .map(|_| adpater.get_field::<String, Infallible>())
+        .collect::<Result<Vec<String>, Error>>()?;
+    Ok((keys, args))
+}
+
+pub(crate) struct Eval {
+    script: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+impl Builder for Eval {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let script = adpater.get_field::<String, Infallible>()?;
+        let (keys, args) = script_args(adpater)?;
+        Ok(Self { script, keys, args })
+    }
+}
+
+impl Eval {
+    /// Caches the script as `SCRIPT LOAD` does.
+    pub fn apply(self, scripts: &Scripts) -> Call {
+        let script = Arc::new(self.script);
+        Call {
+            sha: scripts.load(script.to_string()),
+            script,
+            keys: self.keys,
+            args: self.args,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::cmd::eval::script_args;
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::scripting::{Call, Scripts};
+use crate::service::Error;
+use std::convert::Infallible;
+
+pub(crate) struct EvalSha {
+    sha: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+impl Builder for EvalSha {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let sha = adpater.get_field::<String, Infallible>()?.to_lowercase();
+        let (keys, args) = script_args(adpater)?;
+        Ok(Self { sha, keys, args })
+    }
+}
+
+impl EvalSha {
+    pub fn apply(self, scripts: &Scripts) -> Result<Call, Reply> {
+        match scripts.get(&self.sha) {
+            Some(script) => Ok(Call {
+                sha: self.sha,
+                script,
+                keys: self.keys,
+                args: self.args,
+            }),
+            None => Err(Reply::Error(String::from(
+                "NOSCRIPT No matching script. Please use EVAL.",
+            ))),
+        }
+    }
+}
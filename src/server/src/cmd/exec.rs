@@ -0,0 +1,68 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use crate::transaction::{Transaction, Watched};
+use std::mem;
+
+pub(crate) struct Exec {}
+
+impl Builder for Exec {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'exec' command",
+            )));
+        }
+        Ok(Self {})
+    }
+}
+
+impl Exec {
+    /// Ends the transaction and unwatches every key, handing back the commands to run
+    /// with the keys to check first, or the error to reply.
+    pub fn apply(
+        self,
+        transaction: &mut Option<Transaction>,
+        watched: &mut Vec<Watched>,
+    ) -> Result<(Transaction, Vec<Watched>), Reply> {
+        let watched = mem::take(watched);
+        match transaction.take() {
+            Some(transaction) if transaction.is_aborted() => Err(Reply::Error(String::from(
+                "EXECABORT Transaction discarded because of previous errors.",
+            ))),
+            Some(transaction) => Ok((transaction, watched)),
+            None => Err(Reply::Error(String::from("ERR EXEC without MULTI"))),
+        }
+    }
+}
+
+#[test]
+fn test_exec() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(client.call(&["EXEC"]).await, "-ERR EXEC without MULTI\r\n");
+        assert_eq!(client.call(&["MULTI"]).await, "+OK\r\n");
+        assert_eq!(
+            client.call(&["MSET", "a", "1", "b", "2"]).await,
+            "+QUEUED\r\n"
+        );
+        assert_eq!(client.call(&["MEMORY", "STATS"]).await, "+QUEUED\r\n");
+        assert_eq!(client.call(&["DBSIZE"]).await, "+QUEUED\r\n");
+        let reply = client.call(&["EXEC"]).await;
+        assert!(reply.starts_with("*3\r\n+OK\r\n"));
+        assert!(reply.ends_with(":2\r\n"));
+
+        client.call(&["MULTI"]).await;
+        client.call(&["SET", "c", "3"]).await;
+        assert!(client.call(&["NOSUCHCOMMAND"]).await.starts_with("-ERR"));
+        assert_eq!(
+            client.call(&["EXEC"]).await,
+            "-EXECABORT Transaction discarded because of previous errors.\r\n"
+        );
+        assert_eq!(client.call(&["EXISTS", "c"]).await, ":0\r\n");
+    });
+}
@@ -0,0 +1,26 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct Exists {
+    keys: Vec<String>,
+}
+
+impl Builder for Exists {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            keys: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for Exists {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.exists(self.keys))
+    }
+}
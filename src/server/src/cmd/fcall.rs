@@ -0,0 +1,80 @@
+use crate::cmd::eval::script_args;
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::functions::{Libraries, Library};
+use crate::reply::Reply;
+use crate::service::Error;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+/// `FCALL` and `FCALL_RO`.
+pub(crate) struct Fcall {
+    function: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+/// A function to run, `read_only` when it may not write.
+pub(crate) struct FunctionCall {
+    pub(crate) library: Arc<Library>,
+    pub(crate) function: String,
+    pub(crate) keys: Vec<String>,
+    pub(crate) args: Vec<String>,
+    pub(crate) read_only: bool,
+}
+
+impl Builder for Fcall {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let function = adpater.get_field::<String, Infallible>()?;
+        let (keys, args) = script_args(adpater)?;
+        Ok(Self {
+            function,
+            keys,
+            args,
+        })
+    }
+}
+
+impl Fcall {
+    /// `read_only` for `FCALL_RO`, which only runs the functions flagged `no-writes`.
+    pub fn apply(self, libraries: &Libraries, read_only: bool) -> Result<FunctionCall, Reply> {
+        let library = libraries
+            .find(&self.function)
+            .ok_or_else(|| Reply::Error(String::from("ERR Function not found")))?;
+        let no_writes = library
+            .function(&self.function)
+            .is_some_and(|function| function.is_read_only());
+        if read_only && !no_writes {
+            return Err(Reply::Error(String::from(
+                "ERR Can not execute a script with write flag using *_ro command.",
+            )));
+        }
+        Ok(FunctionCall {
+            library,
+            function: self.function,
+            keys: self.keys,
+            args: self.args,
+            read_only: no_writes,
+        })
+    }
+}
+
+#[test]
+fn test_read_only() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    let library = "#!lua name=lib\n\
+        redis.register_function{function_name='mset', flags={'no-writes'}, \
+        callback=function(keys, args) return redis.call('MSET', keys[1], args[1]) end}";
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(
+            client.call(&["FUNCTION", "LOAD", library]).await,
+            "$3\r\nlib\r\n"
+        );
+        let reply = client.call(&["FCALL_RO", "mset", "1", "a", "1"]).await;
+        assert!(reply.starts_with("-ERR Write commands are not allowed from read-only scripts."));
+        assert_eq!(client.call(&["EXISTS", "a"]).await, ":0\r\n");
+    });
+}
@@ -44,6 +44,17 @@ impl<'a> FieldBuilder<'a> {
         }
     }
 
+    /// Parses the next field without consuming it, for optional keywords.
+    pub(crate) fn peek_field<S, E>(&self) -> Result<S, Error>
+    where
+        S: FromStr<Err = E>,
+        E: ToString,
+    {
+        let (_, result) = parse_bulk(&self.content).map_err(|e| Error::Protocol(e.to_string()))?;
+        let result = result.ok_or(Error::Protocol(String::from("bulk parse error")))?;
+        <S as FromStr>::from_str(result).map_err(|e| Error::Protocol(e.to_string()))
+    }
+
     pub(crate) fn get_total(&self) -> usize {
         self.total
     }
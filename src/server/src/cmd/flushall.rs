@@ -0,0 +1,25 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::flushdb::parse_lazy;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Databases;
+
+pub(crate) struct FlushAll {
+    lazy: bool,
+}
+
+impl Builder for FlushAll {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            lazy: parse_lazy(adpater)?,
+        })
+    }
+}
+
+impl FlushAll {
+    pub fn apply(self, databases: &Databases) -> Reply {
+        databases.flush_all(self.lazy);
+        Reply::Simple(String::from("OK"))
+    }
+}
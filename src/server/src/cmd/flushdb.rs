@@ -0,0 +1,39 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+/// Parses the optional `ASYNC` or `SYNC` of the flush commands, `true` for `ASYNC`.
+pub(crate) fn parse_lazy<'a>(adpater: &mut FieldBuilder<'a>) -> Result<bool, Error> {
+    let lazy = match adpater.get_field_option::<String, Infallible>()? {
+        Some(mode) if mode.eq_ignore_ascii_case("ASYNC") => true,
+        Some(mode) if mode.eq_ignore_ascii_case("SYNC") => false,
+        Some(_) => return Err(Error::Protocol(String::from("syntax error"))),
+        None => false,
+    };
+    if adpater.get_total() > 0 {
+        return Err(Error::Protocol(String::from("syntax error")));
+    }
+    Ok(lazy)
+}
+
+pub(crate) struct FlushDb {
+    lazy: bool,
+}
+
+impl Builder for FlushDb {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            lazy: parse_lazy(adpater)?,
+        })
+    }
+}
+
+impl Apply for FlushDb {
+    fn apply(self, db: Database) -> Reply {
+        db.flush(self.lazy);
+        Reply::Simple(String::from("OK"))
+    }
+}
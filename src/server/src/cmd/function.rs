@@ -0,0 +1,136 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::functions::{Libraries, Library};
+use crate::reply::Reply;
+use crate::scripting::Engine;
+use crate::service::Error;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+enum Subcommand {
+    Load {
+        replace: bool,
+        code: String,
+    },
+    List {
+        pattern: Option<String>,
+        with_code: bool,
+    },
+    Delete(String),
+}
+
+pub(crate) struct Function {
+    subcommand: Subcommand,
+}
+
+impl Builder for Function {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "LOAD" => {
+                let mut replace = false;
+                if adpater.get_total() > 1 {
+                    let option = adpater.get_field::<String, Infallible>()?;
+                    if !option.eq_ignore_ascii_case("REPLACE") {
+                        return Err(Error::Protocol(String::from("syntax error")));
+                    }
+                    replace = true;
+                }
+                let code = adpater.get_field::<String, Infallible>()?;
+                Subcommand::Load { replace, code }
+            }
+            "LIST" => {
+                let mut pattern = None;
+                let mut with_code = false;
+                while adpater.get_total() > 0 {
+                    let option = adpater.get_field::<String, Infallible>()?;
+                    match option.to_uppercase().as_str() {
+                        "WITHCODE" => with_code = true,
+                        "LIBRARYNAME" if adpater.get_total() > 0 => {
+                            pattern = Some(adpater.get_field::<String, Infallible>()?)
+                        }
+                        _ => return Err(Error::Protocol(String::from("syntax error"))),
+                    }
+                }
+                Subcommand::List { pattern, with_code }
+            }
+            "DELETE" => Subcommand::Delete(adpater.get_field::<String, Infallible>()?),
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'function' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand })
+    }
+}
+
+/// A library as `FUNCTION LIST` replies it.
+fn library_reply(library: &Library, with_code: bool) -> Reply {
+    let functions = library
+        .functions
+        .iter()
+        .map(|function| {
+            Reply::fields([
+                ("name", Reply::from(function.name.as_str())),
+                ("description", Reply::Null),
+                (
+                    "flags",
+                    Reply::Set(
+                        function
+                            .flags
+                            .iter()
+                            .map(|flag| Reply::from(flag.as_str()))
+                            .collect(),
+                    ),
+                ),
+            ])
+        })
+        .collect();
+    let mut fields = vec![
+        (
+            Reply::from("library_name"),
+            Reply::from(library.name.as_str()),
+        ),
+        (Reply::from("engine"), Reply::from("LUA")),
+        (Reply::from("functions"), Reply::Array(functions)),
+    ];
+    if with_code {
+        fields.push((
+            Reply::from("library_code"),
+            Reply::from(library.code.as_str()),
+        ));
+    }
+    Reply::Map(fields)
+}
+
+impl Function {
+    /// `engine` runs the code of a library being loaded, to learn its functions.
+    pub fn apply(self, libraries: &Libraries, engine: &mut Engine) -> Reply {
+        match self.subcommand {
+            Subcommand::Load { replace, code } => {
+                let loaded = engine
+                    .load_library(Arc::new(code))
+                    .and_then(|library| libraries.insert(library, replace));
+                match loaded {
+                    Ok(name) => Reply::from(name),
+                    Err(e) => Reply::Error(e),
+                }
+            }
+            Subcommand::List { pattern, with_code } => Reply::Array(
+                libraries
+                    .list(pattern.as_deref())
+                    .iter()
+                    .map(|library| library_reply(library, with_code))
+                    .collect(),
+            ),
+            Subcommand::Delete(name) => match libraries.delete(&name) {
+                true => Reply::Simple(String::from("OK")),
+                false => Reply::Error(String::from("ERR Library not found")),
+            },
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zadd::parse_score;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{AddFlags, Database, GeoPoint};
+use std::convert::Infallible;
+
+pub(crate) struct GeoAdd {
+    key: String,
+    flags: AddFlags,
+    ch: bool,
+    members: Vec<(GeoPoint, String)>,
+}
+
+impl Builder for GeoAdd {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let mut flags = AddFlags::default();
+        let mut ch = false;
+
+        let first_longitude = loop {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "NX" => flags.nx = true,
+                "XX" => flags.xx = true,
+                "CH" => ch = true,
+                _ => break parse_score(&field)?,
+            }
+        };
+
+        if flags.nx && flags.xx {
+            return Err(Error::Protocol(String::from(
+                "XX and NX options at the same time are not compatible",
+            )));
+        }
+        if adpater.get_total() % 3 != 2 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+
+        let mut longitude = first_longitude;
+        let mut members = Vec::new();
+        loop {
+            let latitude = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+            let member = adpater.get_field::<String, Infallible>()?;
+            members.push((GeoPoint::new(longitude, latitude), member));
+            if adpater.get_total() == 0 {
+                break;
+            }
+            longitude = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+        }
+
+        Ok(Self {
+            key,
+            flags,
+            ch,
+            members,
+        })
+    }
+}
+
+impl Apply for GeoAdd {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let ch = self.ch;
+        Reply::from(
+            db.geoadd(self.key, self.members, self.flags)
+                .map(|(added, changed)| if ch { added + changed } else { added }),
+        )
+    }
+}
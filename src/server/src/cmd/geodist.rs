@@ -0,0 +1,43 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, GeoUnit, ParseUnitError};
+use std::convert::Infallible;
+
+pub(crate) struct GeoDist {
+    key: String,
+    from: String,
+    to: String,
+    unit: GeoUnit,
+}
+
+impl Builder for GeoDist {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let from = adpater.get_field::<String, Infallible>()?;
+        let to = adpater.get_field::<String, Infallible>()?;
+        let unit = match adpater.get_total() {
+            0 => GeoUnit::Meters,
+            1 => adpater.get_field::<GeoUnit, ParseUnitError>()?,
+            _ => return Err(Error::Protocol(String::from("syntax error"))),
+        };
+        Ok(Self {
+            key,
+            from,
+            to,
+            unit,
+        })
+    }
+}
+
+impl Apply for GeoDist {
+    fn apply(self, db: Database) -> Reply {
+        let unit = self.unit;
+        Reply::from(
+            db.geodist(self.key, self.from, self.to).map(|distance| {
+                distance.map(|distance| format!("{:.4}", unit.from_meters(distance)))
+            }),
+        )
+    }
+}
@@ -0,0 +1,28 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct GeoHash {
+    key: String,
+    members: Vec<String>,
+}
+
+impl Builder for GeoHash {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            members: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for GeoHash {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.geohash(self.key, self.members))
+    }
+}
@@ -0,0 +1,41 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct GeoPos {
+    key: String,
+    members: Vec<String>,
+}
+
+impl Builder for GeoPos {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            members: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for GeoPos {
+    fn apply(self, db: Database) -> Reply {
+        let points = db.geopos(self.key, self.members).map(|points| {
+            points
+                .into_iter()
+                .map(|point| {
+                    point.map(|point| {
+                        Reply::Array(vec![
+                            Reply::from(point.longitude),
+                            Reply::from(point.latitude),
+                        ])
+                    })
+                })
+                .collect::<Vec<Option<Reply>>>()
+        });
+        Reply::from(points)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zadd::parse_score;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{
+    Database, GeoMatch, GeoOrigin, GeoPoint, GeoSearch as SearchSpec, GeoShape, GeoUnit,
+    ParseUnitError,
+};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::sync::Arc;
+
+/// Arguments shared by `GEOSEARCH` and `GEOSEARCHSTORE`:
+/// `FROMMEMBER member | FROMLONLAT longitude latitude`,
+/// `BYRADIUS radius unit | BYBOX width height unit`,
+/// `[ASC|DESC] [COUNT count [ANY]]` and the reply or store options.
+pub(crate) struct SearchArgs {
+    pub(crate) spec: SearchSpec,
+    pub(crate) unit: GeoUnit,
+    pub(crate) with_coord: bool,
+    pub(crate) with_dist: bool,
+    pub(crate) with_hash: bool,
+    pub(crate) store_dist: bool,
+}
+
+impl SearchArgs {
+    pub(crate) fn build<'a>(adpater: &mut FieldBuilder<'a>, store: bool) -> Result<Self, Error> {
+        let syntax_error = || Error::Protocol(String::from("syntax error"));
+
+        let mut origin = None;
+        let mut shape = None;
+        let mut unit = GeoUnit::Meters;
+        let mut descending = None;
+        let mut count = None;
+        let mut any = false;
+        let mut with_coord = false;
+        let mut with_dist = false;
+        let mut with_hash = false;
+        let mut store_dist = false;
+
+        while adpater.get_total() > 0 {
+            let field = adpater.get_field::<String, Infallible>()?.to_uppercase();
+            match field.as_str() {
+                "FROMMEMBER" if origin.is_none() => {
+                    origin = Some(GeoOrigin::Member(Arc::new(
+                        adpater.get_field::<String, Infallible>()?,
+                    )))
+                }
+                "FROMLONLAT" if origin.is_none() => {
+                    let longitude = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+                    let latitude = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+                    origin = Some(GeoOrigin::Point(GeoPoint::new(longitude, latitude)))
+                }
+                "BYRADIUS" if shape.is_none() => {
+                    let radius = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+                    unit = adpater.get_field::<GeoUnit, ParseUnitError>()?;
+                    if radius < 0.0 {
+                        return Err(Error::Protocol(String::from("radius cannot be negative")));
+                    }
+                    shape = Some(GeoShape::Radius(unit.to_meters(radius)))
+                }
+                "BYBOX" if shape.is_none() => {
+                    let width = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+                    let height = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+                    unit = adpater.get_field::<GeoUnit, ParseUnitError>()?;
+                    if width < 0.0 || height < 0.0 {
+                        return Err(Error::Protocol(String::from(
+                            "height or width cannot be negative",
+                        )));
+                    }
+                    shape = Some(GeoShape::Box {
+                        width: unit.to_meters(width),
+                        height: unit.to_meters(height),
+                    })
+                }
+                "ASC" => descending = Some(false),
+                "DESC" => descending = Some(true),
+                "COUNT" => {
+                    let value = adpater.get_field::<usize, ParseIntError>()?;
+                    if value == 0 {
+                        return Err(Error::Protocol(String::from("COUNT must be > 0")));
+                    }
+                    count = Some(value);
+                }
+                "ANY" => any = true,
+                "WITHCOORD" if !store => with_coord = true,
+                "WITHDIST" if !store => with_dist = true,
+                "WITHHASH" if !store => with_hash = true,
+                "STOREDIST" if store => store_dist = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        let origin = origin.ok_or_else(|| {
+            Error::Protocol(String::from(
+                "exactly one of FROMMEMBER or FROMLONLAT can be specified",
+            ))
+        })?;
+        let shape = shape.ok_or_else(|| {
+            Error::Protocol(String::from(
+                "exactly one of BYRADIUS and BYBOX can be specified",
+            ))
+        })?;
+        if any && count.is_none() {
+            return Err(Error::Protocol(String::from(
+                "the ANY argument requires COUNT argument",
+            )));
+        }
+
+        Ok(Self {
+            spec: SearchSpec {
+                origin,
+                shape,
+                descending,
+                count,
+                any,
+            },
+            unit,
+            with_coord,
+            with_dist,
+            with_hash,
+            store_dist,
+        })
+    }
+}
+
+pub(crate) struct GeoSearch {
+    key: String,
+    args: SearchArgs,
+}
+
+impl Builder for GeoSearch {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            args: SearchArgs::build(adpater, false)?,
+        })
+    }
+}
+
+impl SearchArgs {
+    /// A match with the fields asked for, or its bare member when none were.
+    fn match_reply(&self, geo_match: GeoMatch) -> Reply {
+        let mut fields = vec![Reply::from(geo_match.member)];
+        if self.with_dist {
+            fields.push(Reply::from(format!(
+                "{:.4}",
+                self.unit.from_meters(geo_match.distance)
+            )));
+        }
+        if self.with_hash {
+            fields.push(Reply::from(geo_match.score as u64));
+        }
+        if self.with_coord {
+            fields.push(Reply::Array(vec![
+                Reply::from(geo_match.point.longitude),
+                Reply::from(geo_match.point.latitude),
+            ]));
+        }
+        match fields.len() {
+            1 => fields.remove(0),
+            _ => Reply::Array(fields),
+        }
+    }
+}
+
+impl Apply for GeoSearch {
+    fn apply(self, db: Database) -> Reply {
+        let matches = db.geosearch(self.key, &self.args.spec).map(|matches| {
+            matches
+                .into_iter()
+                .map(|geo_match| self.args.match_reply(geo_match))
+                .collect::<Vec<Reply>>()
+        });
+        Reply::from(matches)
+    }
+}
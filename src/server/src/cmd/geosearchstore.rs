@@ -0,0 +1,31 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::geosearch::SearchArgs;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct GeoSearchStore {
+    destination: String,
+    key: String,
+    args: SearchArgs,
+}
+
+impl Builder for GeoSearchStore {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            destination: adpater.get_field::<String, Infallible>()?,
+            key: adpater.get_field::<String, Infallible>()?,
+            args: SearchArgs::build(adpater, true)?,
+        })
+    }
+}
+
+impl Apply for GeoSearchStore {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let store_distance = self.args.store_dist.then_some(self.args.unit);
+        Reply::from(db.geosearch_store(self.destination, self.key, &self.args.spec, store_distance))
+    }
+}
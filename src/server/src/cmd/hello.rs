@@ -0,0 +1,116 @@
+use crate::cmd::auth::Auth;
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::{Protocol, Reply};
+use crate::service::Error;
+use std::convert::Infallible;
+
+pub(crate) struct Hello {
+    /// The protocol to switch to, the current one kept when not given.
+    version: Option<String>,
+    /// `AUTH <username> <password>`, authenticating before the protocol is switched.
+    auth: Option<Auth>,
+}
+
+impl Builder for Hello {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let version = match adpater.get_total() {
+            0 => None,
+            _ => Some(adpater.get_field::<String, Infallible>()?),
+        };
+        let mut auth = None;
+        while adpater.get_total() > 0 {
+            let option = adpater.get_field::<String, Infallible>()?.to_uppercase();
+            match option.as_str() {
+                "AUTH" if adpater.get_total() >= 2 => {
+                    auth = Some(Auth::new(
+                        adpater.get_field::<String, Infallible>()?,
+                        adpater.get_field::<String, Infallible>()?,
+                    ))
+                }
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+        Ok(Self { version, auth })
+    }
+}
+
+impl Hello {
+    /// Switches the connection to the protocol asked for, replying what `HELLO` tells
+    /// of the server in it. Only an authenticated connection may, unless it
+    /// authenticates with `AUTH` along.
+    pub fn apply(
+        self,
+        protocol: &mut Protocol,
+        id: u64,
+        requirepass: Option<&str>,
+        authenticated: &mut bool,
+    ) -> Reply {
+        let version = match self.version.map(|version| version.parse::<i64>()) {
+            None => *protocol,
+            Some(Ok(2)) => Protocol::Resp2,
+            Some(Ok(3)) => Protocol::Resp3,
+            Some(Ok(_)) => {
+                return Reply::Error(String::from("NOPROTO unsupported protocol version"))
+            }
+            Some(Err(_)) => {
+                return Reply::Error(String::from(
+                    "ERR Protocol version is not an integer or out of range",
+                ))
+            }
+        };
+        match self.auth {
+            Some(auth) => match auth.check(requirepass) {
+                Ok(()) => *authenticated = true,
+                Err(reply) => return reply,
+            },
+            None if !*authenticated => {
+                return Reply::Error(String::from(
+                    "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time",
+                ))
+            }
+            None => {}
+        }
+        *protocol = version;
+        Reply::fields([
+            ("server", Reply::from("ky")),
+            ("version", Reply::from(env!("CARGO_PKG_VERSION"))),
+            (
+                "proto",
+                Reply::from(match protocol {
+                    Protocol::Resp2 => 2,
+                    Protocol::Resp3 => 3,
+                }),
+            ),
+            ("id", Reply::from(id)),
+            ("mode", Reply::from("standalone")),
+            ("role", Reply::from("master")),
+            ("modules", Reply::Array(Vec::new())),
+        ])
+    }
+}
+
+#[test]
+fn test_hello() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(client.call(&["GET", "a"]).await, "+nil\r\n");
+        let hello = client.call(&["HELLO", "3"]).await;
+        assert!(hello.starts_with("%7\r\n$6\r\nserver\r\n$2\r\nky\r\n"));
+        assert!(hello.contains("$5\r\nproto\r\n:3\r\n"));
+        assert_eq!(client.call(&["GET", "a"]).await, "_\r\n");
+
+        assert_eq!(
+            client.call(&["HELLO", "4"]).await,
+            "-NOPROTO unsupported protocol version\r\n"
+        );
+        // still RESP3 after a failed switch
+        assert_eq!(client.call(&["GET", "a"]).await, "_\r\n");
+        let hello = client.call(&["HELLO", "2"]).await;
+        assert!(hello.starts_with("*14\r\n$6\r\nserver\r\n"));
+        assert_eq!(client.call(&["GET", "a"]).await, "+nil\r\n");
+    });
+}
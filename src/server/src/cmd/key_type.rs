@@ -0,0 +1,25 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+/// `TYPE`
+pub(crate) struct KeyType {
+    key: String,
+}
+
+impl Builder for KeyType {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for KeyType {
+    fn apply(self, db: Database) -> Reply {
+        Reply::Simple(String::from(db.key_type(self.key)))
+    }
+}
@@ -0,0 +1,24 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct Keys {
+    pattern: String,
+}
+
+impl Builder for Keys {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            pattern: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for Keys {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.keys(&self.pattern))
+    }
+}
@@ -7,7 +7,6 @@ use std::convert::Infallible;
 use std::marker::Unpin;
 use std::num::ParseIntError;
 use std::str::FromStr;
-use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
 
 pub(crate) struct LRange {
@@ -26,8 +25,8 @@ impl Builder for LRange {
     }
 }
 
-impl LRange {
-    pub fn apply(self, db: Database) -> Vec<Arc<String>> {
-        db.lrange(self.key, self.start, self.stop)
+impl Apply for LRange {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.lrange(self.key, self.start, self.stop))
     }
 }
@@ -0,0 +1,120 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{peak_memory, startup_memory, used_memory, Database, Databases};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+/// Under this much memory `MEMORY DOCTOR` has nothing to look at.
+const DOCTOR_MIN_MEMORY: usize = 5 * 1024 * 1024;
+
+enum Subcommand {
+    Usage { key: String, samples: usize },
+    Stats,
+    Doctor,
+}
+
+pub(crate) struct Memory {
+    subcommand: Subcommand,
+}
+
+impl Builder for Memory {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "USAGE" => {
+                let key = adpater.get_field::<String, Infallible>()?;
+                let mut samples = 5;
+                if adpater.get_total() > 0 {
+                    let option = adpater.get_field::<String, Infallible>()?;
+                    if !option.eq_ignore_ascii_case("SAMPLES") {
+                        return Err(Error::Protocol(String::from("syntax error")));
+                    }
+                    samples = adpater.get_field::<usize, ParseIntError>()?;
+                }
+                Subcommand::Usage { key, samples }
+            }
+            "STATS" => Subcommand::Stats,
+            "DOCTOR" => Subcommand::Doctor,
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'memory' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand })
+    }
+}
+
+impl Memory {
+    pub fn apply(self, db: Database, databases: &Databases) -> Reply {
+        let (total, peak, startup) = (used_memory(), peak_memory(), startup_memory());
+        let keys = || {
+            (0..databases.len())
+                .filter_map(|index| databases.get(index))
+                .map(|db| db.dbsize())
+                .sum::<usize>()
+        };
+        match self.subcommand {
+            Subcommand::Usage { key, samples } => Reply::from(db.memory_usage(key, samples)),
+            Subcommand::Stats => {
+                let dataset = total.saturating_sub(startup);
+                let keys = keys();
+                Reply::fields([
+                    ("peak.allocated", Reply::from(peak)),
+                    ("total.allocated", Reply::from(total)),
+                    ("startup.allocated", Reply::from(startup)),
+                    ("dataset.bytes", Reply::from(dataset)),
+                    ("keys.count", Reply::from(keys)),
+                    (
+                        "keys.bytes-per-key",
+                        Reply::from(dataset.checked_div(keys).unwrap_or(0)),
+                    ),
+                    (
+                        "peak.percentage",
+                        Reply::from(total as f64 * 100f64 / peak.max(1) as f64),
+                    ),
+                ])
+            }
+            Subcommand::Doctor => {
+                Reply::Verbatim(String::from("txt"), doctor(total, peak, startup, keys()))
+            }
+        }
+    }
+}
+
+/// What the allocator counts, then the one issue it can tell: a peak the allocator may
+/// not have given back.
+fn doctor(total: usize, peak: usize, startup: usize, keys: usize) -> String {
+    let mut report = format!(
+        "used: {} bytes, peak: {} bytes, startup: {} bytes, keys: {}\n",
+        total, peak, startup, keys
+    );
+    if total < DOCTOR_MIN_MEMORY {
+        report.push_str("Too little memory in use to tell of any issue.\n");
+    } else if peak as f64 > total as f64 * 1.5 {
+        report.push_str(
+            "The peak is over 150% of the memory in use, the allocator may still hold \
+             the difference.\n",
+        );
+    } else {
+        report.push_str("No memory issue found.\n");
+    }
+    report
+}
+
+#[test]
+fn test_doctor() {
+    let mb = 1024 * 1024;
+    assert_eq!(
+        doctor(mb, 2 * mb, mb, 3),
+        "used: 1048576 bytes, peak: 2097152 bytes, startup: 1048576 bytes, keys: 3\n\
+         Too little memory in use to tell of any issue.\n"
+    );
+    assert!(doctor(10 * mb, 20 * mb, mb, 0).ends_with("may still hold the difference.\n"));
+    assert!(doctor(10 * mb, 11 * mb, mb, 0).ends_with("No memory issue found.\n"));
+}
@@ -5,7 +5,6 @@ use crate::service::Error;
 use database::Database;
 use std::convert::Infallible;
 use std::str::FromStr;
-use std::sync::Arc;
 
 pub(crate) struct MGet {
     keys: Vec<String>,
@@ -21,8 +20,8 @@ impl Builder for MGet {
     }
 }
 
-impl MGet {
-    pub fn apply(self, db: Database) -> Vec<Arc<String>> {
-        db.mget(self.keys)
+impl Apply for MGet {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.mget(self.keys))
     }
 }
@@ -1,37 +1,205 @@
+mod auth;
+mod bzmpop;
+mod bzpopmax;
+mod bzpopmin;
+mod client;
+mod copy_key;
+mod dbsize;
 mod delete;
+mod discard;
+mod eval;
+mod evalsha;
+mod exec;
+mod exists;
+mod fcall;
 mod field_builder;
+mod flushall;
+mod flushdb;
+mod function;
+mod geoadd;
+mod geodist;
+mod geohash;
+mod geopos;
+mod geosearch;
+mod geosearchstore;
 mod get;
+mod hello;
+mod key_type;
+mod keys;
 mod llen;
 mod lpop;
 mod lpush;
 mod lrange;
+mod memory;
 mod mget;
+mod move_key;
 mod mset;
+mod multi;
+mod object;
 mod ping;
 mod pong;
+mod psubscribe;
+mod publish;
+mod pubsub;
+mod punsubscribe;
+mod quit;
+mod randomkey;
+mod rename;
+mod renamenx;
 mod rpop;
 mod rpush;
 mod sadd;
+mod scan;
+mod scard;
+mod script;
+mod select;
 mod set;
 mod smembers;
-mod scard;
+mod sscan;
+mod subscribe;
+mod swapdb;
+mod touch;
 mod traits;
+mod unlink;
+mod unsubscribe;
+mod unwatch;
+mod watch;
+mod xack;
+mod xadd;
+mod xautoclaim;
+mod xclaim;
+mod xdel;
+mod xgroup;
+mod xinfo;
+mod xlen;
+mod xpending;
+mod xrange;
+mod xread;
+mod xreadgroup;
+mod xrevrange;
+mod xtrim;
+mod zadd;
+mod zcard;
+mod zcount;
+mod zdiff;
+mod zdiffstore;
+mod zincrby;
+mod zinter;
+mod zinterstore;
+mod zlexcount;
+mod zpopmax;
+mod zpopmin;
+mod zrange;
+mod zrank;
+mod zrem;
+mod zremrangebylex;
+mod zremrangebyrank;
+mod zremrangebyscore;
+mod zrevrank;
+mod zscan;
+mod zscore;
+mod zunion;
+mod zunionstore;
 
+pub(crate) use auth::Auth;
+pub(crate) use bzmpop::BZMPop;
+pub(crate) use bzpopmax::BZPopMax;
+pub(crate) use bzpopmin::BZPopMin;
+pub(crate) use client::Client;
+pub(crate) use copy_key::CopyKey;
+pub(crate) use dbsize::DbSize;
 pub(crate) use delete::Delete;
+pub(crate) use discard::Discard;
+pub(crate) use eval::Eval;
+pub(crate) use evalsha::EvalSha;
+pub(crate) use exec::Exec;
+pub(crate) use exists::Exists;
+pub(crate) use fcall::{Fcall, FunctionCall};
 pub(crate) use field_builder::FieldBuilder;
+pub(crate) use flushall::FlushAll;
+pub(crate) use flushdb::FlushDb;
+pub(crate) use function::Function;
+pub(crate) use geoadd::GeoAdd;
+pub(crate) use geodist::GeoDist;
+pub(crate) use geohash::GeoHash;
+pub(crate) use geopos::GeoPos;
+pub(crate) use geosearch::GeoSearch;
+pub(crate) use geosearchstore::GeoSearchStore;
 pub(crate) use get::Get;
+pub(crate) use hello::Hello;
+pub(crate) use key_type::KeyType;
+pub(crate) use keys::Keys;
 pub(crate) use llen::LLen;
 pub(crate) use lpop::LPop;
 pub(crate) use lpush::LPush;
 pub(crate) use lrange::LRange;
+pub(crate) use memory::Memory;
 pub(crate) use mget::MGet;
+pub(crate) use move_key::Move;
 pub(crate) use mset::MSet;
+pub(crate) use multi::Multi;
+pub(crate) use object::Object;
 pub(crate) use ping::Ping;
 pub(crate) use pong::Pong;
+pub(crate) use psubscribe::PSubscribe;
+pub(crate) use publish::Publish;
+pub(crate) use pubsub::PubSubInfo;
+pub(crate) use punsubscribe::PUnsubscribe;
+pub(crate) use quit::Quit;
+pub(crate) use randomkey::RandomKey;
+pub(crate) use rename::Rename;
+pub(crate) use renamenx::RenameNx;
 pub(crate) use rpop::RPop;
 pub(crate) use rpush::RPush;
 pub(crate) use sadd::SAdd;
+pub(crate) use scan::Scan;
 pub(crate) use scard::Scard;
+pub(crate) use script::Script;
+pub(crate) use select::Select;
 pub(crate) use set::Set;
 pub(crate) use smembers::Smembers;
+pub(crate) use sscan::SScan;
+pub(crate) use subscribe::Subscribe;
+pub(crate) use swapdb::SwapDb;
+pub(crate) use touch::Touch;
 pub(crate) use traits::{Apply, Builder};
+pub(crate) use unlink::Unlink;
+pub(crate) use unsubscribe::Unsubscribe;
+pub(crate) use unwatch::Unwatch;
+pub(crate) use watch::Watch;
+pub(crate) use xack::XAck;
+pub(crate) use xadd::XAdd;
+pub(crate) use xautoclaim::XAutoClaim;
+pub(crate) use xclaim::XClaim;
+pub(crate) use xdel::XDel;
+pub(crate) use xgroup::XGroup;
+pub(crate) use xinfo::XInfo;
+pub(crate) use xlen::XLen;
+pub(crate) use xpending::XPending;
+pub(crate) use xrange::XRange;
+pub(crate) use xread::XRead;
+pub(crate) use xreadgroup::XReadGroup;
+pub(crate) use xrevrange::XRevRange;
+pub(crate) use xtrim::XTrim;
+pub(crate) use zadd::ZAdd;
+pub(crate) use zcard::ZCard;
+pub(crate) use zcount::ZCount;
+pub(crate) use zdiff::ZDiff;
+pub(crate) use zdiffstore::ZDiffStore;
+pub(crate) use zincrby::ZIncrBy;
+pub(crate) use zinter::ZInter;
+pub(crate) use zinterstore::ZInterStore;
+pub(crate) use zlexcount::ZLexCount;
+pub(crate) use zpopmax::ZPopMax;
+pub(crate) use zpopmin::ZPopMin;
+pub(crate) use zrange::ZRange;
+pub(crate) use zrank::ZRank;
+pub(crate) use zrem::ZRem;
+pub(crate) use zremrangebylex::ZRemRangeByLex;
+pub(crate) use zremrangebyrank::ZRemRangeByRank;
+pub(crate) use zremrangebyscore::ZRemRangeByScore;
+pub(crate) use zrevrank::ZRevRank;
+pub(crate) use zscan::ZScan;
+pub(crate) use zscore::ZScore;
+pub(crate) use zunion::ZUnion;
+pub(crate) use zunionstore::ZUnionStore;
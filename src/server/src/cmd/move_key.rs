@@ -0,0 +1,63 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, Databases};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+/// `MOVE`
+pub(crate) struct Move {
+    key: String,
+    db: usize,
+}
+
+impl Builder for Move {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            db: adpater.get_field::<usize, ParseIntError>()?,
+        })
+    }
+}
+
+impl Move {
+    pub fn apply(self, db: Database, databases: &Databases) -> Reply {
+        match databases.get(self.db) {
+            Some(target) => Reply::from(db.move_to(self.key, &target).map(|moved| moved as u8)),
+            None => Reply::Error(String::from("ERR DB index is out of range")),
+        }
+    }
+}
+
+#[test]
+fn test_rename_copy_move() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        client.call(&["SET", "a", "1"]).await;
+        assert_eq!(client.call(&["RENAME", "a", "b"]).await, "+OK\r\n");
+        assert_eq!(
+            client.call(&["RENAME", "a", "c"]).await,
+            "-ERR no such key\r\n"
+        );
+        client.call(&["SET", "c", "2"]).await;
+        assert_eq!(client.call(&["RENAMENX", "b", "c"]).await, ":0\r\n");
+        assert_eq!(client.call(&["COPY", "b", "c"]).await, ":0\r\n");
+        assert_eq!(client.call(&["COPY", "b", "c", "REPLACE"]).await, ":1\r\n");
+        assert_eq!(client.call(&["GET", "c"]).await, "$1\r\n1\r\n");
+        assert_eq!(client.call(&["COPY", "b", "b", "DB", "1"]).await, ":1\r\n");
+        // taken in the other database
+        assert_eq!(client.call(&["MOVE", "b", "1"]).await, ":0\r\n");
+        assert_eq!(client.call(&["MOVE", "c", "1"]).await, ":1\r\n");
+        assert_eq!(client.call(&["EXISTS", "c"]).await, ":0\r\n");
+        assert_eq!(
+            client.call(&["MOVE", "b", "16"]).await,
+            "-ERR DB index is out of range\r\n"
+        );
+        client.call(&["SELECT", "1"]).await;
+        assert_eq!(client.call(&["GET", "c"]).await, "$1\r\n1\r\n");
+    });
+}
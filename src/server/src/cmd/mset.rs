@@ -1,10 +1,9 @@
 use crate::cmd::field_builder::FieldBuilder;
 use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
 use crate::service::Error;
 use database::Database;
 use std::convert::Infallible;
-use std::str::FromStr;
-use std::sync::Arc;
 
 pub(crate) struct MSet {
     key_value_list: Vec<(String, String)>,
@@ -12,8 +11,13 @@ pub(crate) struct MSet {
 
 impl Builder for MSet {
     fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() == 0 || !adpater.get_total().is_multiple_of(2) {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'mset' command",
+            )));
+        }
         let mut list = vec![];
-        for _ in 0..(adpater.get_total() + 1) / 2 {
+        for _ in 0..adpater.get_total() / 2 {
             list.push((
                 adpater.get_field::<String, Infallible>()?,
                 adpater.get_field::<String, Infallible>()?,
@@ -26,10 +30,10 @@ impl Builder for MSet {
     }
 }
 
-impl MSet {
-    pub fn apply(self, db: Database) -> bool {
+impl Apply for MSet {
+    fn apply(self, db: Database) -> Reply {
         let mut db = db;
         db.mset(self.key_value_list);
-        true
+        Reply::Simple(String::from("OK"))
     }
 }
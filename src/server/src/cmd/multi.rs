@@ -0,0 +1,29 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use crate::transaction::Transaction;
+
+pub(crate) struct Multi {}
+
+impl Builder for Multi {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'multi' command",
+            )));
+        }
+        Ok(Self {})
+    }
+}
+
+impl Multi {
+    /// Starts queueing the commands of the connection.
+    pub fn apply(self, transaction: &mut Option<Transaction>) -> Reply {
+        if transaction.is_some() {
+            return Reply::Error(String::from("ERR MULTI calls can not be nested"));
+        }
+        *transaction = Some(Transaction::default());
+        Reply::Simple(String::from("OK"))
+    }
+}
@@ -0,0 +1,52 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+enum Subcommand {
+    Encoding,
+    IdleTime,
+    Freq,
+    RefCount,
+}
+
+/// `OBJECT`, reading a key doesn't count as an access to it.
+pub(crate) struct Object {
+    subcommand: Subcommand,
+    key: String,
+}
+
+impl Builder for Object {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "ENCODING" => Subcommand::Encoding,
+            "IDLETIME" => Subcommand::IdleTime,
+            "FREQ" => Subcommand::Freq,
+            "REFCOUNT" => Subcommand::RefCount,
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'object' command",
+                )))
+            }
+        };
+        let key = adpater.get_field::<String, Infallible>()?;
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand, key })
+    }
+}
+
+impl Apply for Object {
+    fn apply(self, db: Database) -> Reply {
+        match self.subcommand {
+            Subcommand::Encoding => Reply::from(db.object(self.key, |value| value.encoding())),
+            Subcommand::IdleTime => Reply::from(db.object(self.key, |value| value.idle_time())),
+            Subcommand::Freq => Reply::from(db.object(self.key, |value| value.frequency())),
+            Subcommand::RefCount => Reply::from(db.object(self.key, |value| value.refcount())),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::{write_push, Subscriber};
+use crate::reply::{Replies, Reply};
+use crate::service::Error;
+use std::convert::Infallible;
+
+pub(crate) struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+impl Builder for PSubscribe {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() == 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'psubscribe' command",
+            )));
+        }
+        Ok(Self {
+            patterns: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl PSubscribe {
+    /// Confirms each subscription with the count of them as a `psubscribe` push.
+    pub fn apply(self, subscriber: &mut Subscriber, out: &mut Replies) {
+        for pattern in self.patterns {
+            let count = subscriber.psubscribe(pattern.clone(), out);
+            write_push(
+                vec![
+                    Reply::from("psubscribe"),
+                    Reply::from(pattern),
+                    Reply::from(count),
+                ],
+                out,
+            );
+        }
+    }
+}
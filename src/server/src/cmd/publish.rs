@@ -0,0 +1,31 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::PubSub;
+use crate::reply::Reply;
+use crate::service::Error;
+use std::convert::Infallible;
+
+pub(crate) struct Publish {
+    channel: String,
+    message: String,
+}
+
+impl Builder for Publish {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let channel = adpater.get_field::<String, Infallible>()?;
+        let message = adpater.get_field::<String, Infallible>()?;
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'publish' command",
+            )));
+        }
+        Ok(Self { channel, message })
+    }
+}
+
+impl Publish {
+    /// Replies how many subscribers received the message.
+    pub fn apply(self, pubsub: &PubSub) -> Reply {
+        Reply::from(pubsub.publish(self.channel, self.message))
+    }
+}
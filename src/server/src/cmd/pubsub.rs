@@ -0,0 +1,62 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::PubSub;
+use crate::reply::Reply;
+use crate::service::Error;
+use std::convert::Infallible;
+
+enum Subcommand {
+    Channels(Option<String>),
+    NumSub(Vec<String>),
+    NumPat,
+    Patterns,
+}
+
+/// `PUBSUB`, which looks into the channels.
+pub(crate) struct PubSubInfo {
+    subcommand: Subcommand,
+}
+
+impl Builder for PubSubInfo {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "CHANNELS" => Subcommand::Channels(adpater.get_field_option::<String, Infallible>()?),
+            "NUMSUB" => Subcommand::NumSub(
+                (0..adpater.get_total())
+                    .map(|_| adpater.get_field::<String, Infallible>())
+                    .collect::<Result<Vec<String>, Error>>()?,
+            ),
+            "NUMPAT" => Subcommand::NumPat,
+            "PATTERNS" => Subcommand::Patterns,
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'pubsub' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand })
+    }
+}
+
+impl PubSubInfo {
+    pub fn apply(self, pubsub: &PubSub) -> Reply {
+        match self.subcommand {
+            Subcommand::Channels(pattern) => Reply::from(pubsub.channels(pattern.as_deref())),
+            Subcommand::NumSub(channels) => Reply::Array(
+                channels
+                    .into_iter()
+                    .flat_map(|channel| {
+                        let count = pubsub.numsub(&channel);
+                        [Reply::from(channel), Reply::from(count)]
+                    })
+                    .collect(),
+            ),
+            Subcommand::NumPat => Reply::from(pubsub.numpat()),
+            Subcommand::Patterns => Reply::from(pubsub.patterns()),
+        }
+    }
+}
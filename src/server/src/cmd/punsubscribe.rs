@@ -0,0 +1,53 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::{write_push, Subscriber};
+use crate::reply::{Replies, Reply};
+use crate::service::Error;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+pub(crate) struct PUnsubscribe {
+    /// Every pattern when empty.
+    patterns: Vec<String>,
+}
+
+impl Builder for PUnsubscribe {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            patterns: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl PUnsubscribe {
+    /// Confirms each pattern left as `UNSUBSCRIBE` does channels.
+    pub fn apply(self, subscriber: &mut Subscriber, out: &mut Replies) {
+        let patterns = match self.patterns.is_empty() {
+            true => subscriber.patterns(),
+            false => self.patterns.into_iter().map(Arc::new).collect(),
+        };
+        if patterns.is_empty() {
+            write_push(
+                vec![
+                    Reply::from("punsubscribe"),
+                    Reply::from(None::<Reply>),
+                    Reply::from(subscriber.count()),
+                ],
+                out,
+            );
+        }
+        for pattern in patterns {
+            let count = subscriber.punsubscribe(&pattern);
+            write_push(
+                vec![
+                    Reply::from("punsubscribe"),
+                    Reply::from(pattern),
+                    Reply::from(count),
+                ],
+                out,
+            );
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::service::Error;
+
+pub(crate) struct Quit {}
+
+impl Builder for Quit {
+    fn build<'a>(_: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {})
+    }
+}
@@ -0,0 +1,19 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+
+pub(crate) struct RandomKey {}
+
+impl Builder for RandomKey {
+    fn build<'a>(_: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {})
+    }
+}
+
+impl Apply for RandomKey {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.randomkey())
+    }
+}
@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct Rename {
+    key: String,
+    destination: String,
+}
+
+impl Builder for Rename {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            destination: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for Rename {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        match db.rename(self.key, self.destination, false) {
+            Ok(_) => Reply::Simple(String::from("OK")),
+            Err(e) => Reply::Error(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct RenameNx {
+    key: String,
+    destination: String,
+}
+
+impl Builder for RenameNx {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            destination: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for RenameNx {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(
+            db.rename(self.key, self.destination, true)
+                .map(|renamed| renamed as u8),
+        )
+    }
+}
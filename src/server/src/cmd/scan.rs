@@ -0,0 +1,66 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, ScanFilter};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+/// Parses `[MATCH pattern] [COUNT count]`, and `[TYPE type]` when `with_type`.
+pub(crate) fn parse_scan<'a>(
+    adpater: &mut FieldBuilder<'a>,
+    with_type: bool,
+) -> Result<(usize, ScanFilter), Error> {
+    let mut count = 10;
+    let mut filter = ScanFilter {
+        pattern: None,
+        key_type: None,
+    };
+    while adpater.get_total() > 0 {
+        let option = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        match option.as_str() {
+            "MATCH" => filter.pattern = Some(adpater.get_field::<String, Infallible>()?),
+            "COUNT" => {
+                count = adpater.get_field::<usize, ParseIntError>()?;
+                if count == 0 {
+                    return Err(Error::Protocol(String::from("syntax error")));
+                }
+            }
+            "TYPE" if with_type => {
+                filter.key_type = Some(adpater.get_field::<String, Infallible>()?)
+            }
+            _ => return Err(Error::Protocol(String::from("syntax error"))),
+        }
+    }
+    Ok((count, filter))
+}
+
+/// A page of the `SCAN` family as `[cursor, [item, ...]]`.
+pub(crate) fn page(cursor: u64, items: Vec<Reply>) -> Reply {
+    Reply::Array(vec![Reply::from(cursor.to_string()), Reply::Array(items)])
+}
+
+pub(crate) struct Scan {
+    cursor: u64,
+    count: usize,
+    filter: ScanFilter,
+}
+
+impl Builder for Scan {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let cursor = adpater.get_field::<u64, ParseIntError>()?;
+        let (count, filter) = parse_scan(adpater, true)?;
+        Ok(Self {
+            cursor,
+            count,
+            filter,
+        })
+    }
+}
+
+impl Apply for Scan {
+    fn apply(self, db: Database) -> Reply {
+        let (cursor, keys) = db.scan(self.cursor, self.count, &self.filter);
+        page(cursor, keys.into_iter().map(Reply::from).collect())
+    }
+}
@@ -0,0 +1,70 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::scripting::Scripts;
+use crate::service::Error;
+use std::convert::Infallible;
+
+enum Subcommand {
+    Load(String),
+    Exists(Vec<String>),
+    Flush,
+}
+
+pub(crate) struct Script {
+    subcommand: Subcommand,
+}
+
+impl Builder for Script {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let subcommand = match subcommand.as_str() {
+            "LOAD" => Subcommand::Load(adpater.get_field::<String, Infallible>()?),
+            "EXISTS" if adpater.get_total() > 0 => Subcommand::Exists(
+                (0..adpater.get_total())
+                    .map(|_| adpater.get_field::<String, Infallible>())
+                    .collect::<Result<Vec<String>, Error>>()?,
+            ),
+            "FLUSH" => {
+                // scripts are dropped right away either way
+                if let Some(mode) = adpater.get_field_option::<String, Infallible>()? {
+                    if !mode.eq_ignore_ascii_case("ASYNC") && !mode.eq_ignore_ascii_case("SYNC") {
+                        return Err(Error::Protocol(String::from("syntax error")));
+                    }
+                }
+                Subcommand::Flush
+            }
+            "EXISTS" => {
+                return Err(Error::Protocol(String::from(
+                    "wrong number of arguments for 'script|exists' command",
+                )))
+            }
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'script' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { subcommand })
+    }
+}
+
+impl Script {
+    pub fn apply(self, scripts: &Scripts) -> Reply {
+        match self.subcommand {
+            Subcommand::Load(script) => Reply::from(scripts.load(script)),
+            Subcommand::Exists(shas) => Reply::Array(
+                shas.iter()
+                    .map(|sha| Reply::from(scripts.exists(sha) as u8))
+                    .collect(),
+            ),
+            Subcommand::Flush => {
+                scripts.flush();
+                Reply::Simple(String::from("OK"))
+            }
+        }
+    }
+}
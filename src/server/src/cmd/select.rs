@@ -0,0 +1,29 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Databases;
+use std::num::ParseIntError;
+
+pub(crate) struct Select {
+    index: usize,
+}
+
+impl Builder for Select {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            index: adpater.get_field::<usize, ParseIntError>()?,
+        })
+    }
+}
+
+impl Select {
+    /// Points `db`, the connection's current database, at the selected one.
+    pub fn apply(self, databases: &Databases, db: &mut usize) -> Reply {
+        if self.index >= databases.len() {
+            return Reply::Error(String::from("ERR DB index is out of range"));
+        }
+        *db = self.index;
+        Reply::Simple(String::from("OK"))
+    }
+}
@@ -7,7 +7,6 @@ use std::convert::Infallible;
 use std::marker::Unpin;
 use std::num::ParseIntError;
 use std::str::FromStr;
-use std::sync::Arc;
 
 pub(crate) struct Smembers {
     key: String,
@@ -21,8 +20,8 @@ impl Builder for Smembers {
     }
 }
 
-impl Smembers {
-    pub fn apply(self, db: Database) -> Vec<Arc<String>> {
-        db.smembers(self.key)
+impl Apply for Smembers {
+    fn apply(self, db: Database) -> Reply {
+        Reply::Set(db.smembers(self.key).into_iter().map(Reply::from).collect())
     }
 }
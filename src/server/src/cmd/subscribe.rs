@@ -0,0 +1,42 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::{write_push, Subscriber};
+use crate::reply::{Replies, Reply};
+use crate::service::Error;
+use std::convert::Infallible;
+
+pub(crate) struct Subscribe {
+    channels: Vec<String>,
+}
+
+impl Builder for Subscribe {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() == 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'subscribe' command",
+            )));
+        }
+        Ok(Self {
+            channels: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Subscribe {
+    /// Confirms each subscription with the count of them as a `subscribe` push.
+    pub fn apply(self, subscriber: &mut Subscriber, out: &mut Replies) {
+        for channel in self.channels {
+            let count = subscriber.subscribe(channel.clone(), out);
+            write_push(
+                vec![
+                    Reply::from("subscribe"),
+                    Reply::from(channel),
+                    Reply::from(count),
+                ],
+                out,
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Databases;
+use std::num::ParseIntError;
+
+pub(crate) struct SwapDb {
+    first: usize,
+    second: usize,
+}
+
+impl Builder for SwapDb {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            first: adpater.get_field::<usize, ParseIntError>()?,
+            second: adpater.get_field::<usize, ParseIntError>()?,
+        })
+    }
+}
+
+impl SwapDb {
+    pub fn apply(self, databases: &Databases) -> Reply {
+        if databases.swap(self.first, self.second) {
+            Reply::Simple(String::from("OK"))
+        } else {
+            Reply::Error(String::from("ERR DB index is out of range"))
+        }
+    }
+}
+
+#[test]
+fn test_select_swapdb() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    let config = Config {
+        databases: 2,
+        ..Config::default()
+    };
+    serve(config, |addr| async move {
+        let mut first = Client::connect(addr).await;
+        let mut second = Client::connect(addr).await;
+        first.call(&["SET", "a", "0"]).await;
+        assert_eq!(first.call(&["SELECT", "1"]).await, "+OK\r\n");
+        assert_eq!(first.call(&["GET", "a"]).await, "+nil\r\n");
+        first.call(&["SET", "a", "1"]).await;
+        assert_eq!(
+            first.call(&["SELECT", "2"]).await,
+            "-ERR DB index is out of range\r\n"
+        );
+        assert_eq!(first.call(&["GET", "a"]).await, "$1\r\n1\r\n");
+
+        // every connection sees the swap at once
+        assert_eq!(second.call(&["SWAPDB", "0", "1"]).await, "+OK\r\n");
+        assert_eq!(second.call(&["GET", "a"]).await, "$1\r\n1\r\n");
+        assert_eq!(first.call(&["GET", "a"]).await, "$1\r\n0\r\n");
+        assert_eq!(
+            second.call(&["SWAPDB", "0", "2"]).await,
+            "-ERR DB index is out of range\r\n"
+        );
+    });
+}
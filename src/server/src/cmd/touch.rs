@@ -0,0 +1,58 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+/// `TOUCH`
+pub(crate) struct Touch {
+    keys: Vec<String>,
+}
+
+impl Builder for Touch {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() == 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'touch' command",
+            )));
+        }
+        Ok(Self {
+            keys: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for Touch {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.touch(self.keys))
+    }
+}
+
+#[test]
+fn test_touch_object() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        client.call(&["SET", "a", "1"]).await;
+        assert_eq!(client.call(&["TOUCH", "a", "b", "a"]).await, ":2\r\n");
+        assert_eq!(client.call(&["OBJECT", "REFCOUNT", "a"]).await, ":1\r\n");
+        assert_eq!(
+            client.call(&["OBJECT", "ENCODING", "a"]).await,
+            "$3\r\nraw\r\n"
+        );
+        let freq = client.call(&["OBJECT", "FREQ", "a"]).await;
+        assert!(freq[1..].trim_end().parse::<u8>().unwrap() > 0);
+        assert_eq!(client.call(&["OBJECT", "IDLETIME", "b"]).await, "+nil\r\n");
+        client.call(&["MULTI"]).await;
+        assert_eq!(
+            client.call(&["TOUCH"]).await,
+            "-ERR wrong number of arguments for 'touch' command\r\n"
+        );
+        client.call(&["DISCARD"]).await;
+    });
+}
@@ -0,0 +1,27 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct Unlink {
+    keys: Vec<String>,
+}
+
+impl Builder for Unlink {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            keys: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for Unlink {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.unlink(self.keys))
+    }
+}
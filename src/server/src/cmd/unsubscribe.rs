@@ -0,0 +1,54 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::pubsub::{write_push, Subscriber};
+use crate::reply::{Replies, Reply};
+use crate::service::Error;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+pub(crate) struct Unsubscribe {
+    /// Every channel when empty.
+    channels: Vec<String>,
+}
+
+impl Builder for Unsubscribe {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            channels: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Unsubscribe {
+    /// Confirms each channel left with the count of subscriptions left, once with a nil
+    /// channel when there was none to leave.
+    pub fn apply(self, subscriber: &mut Subscriber, out: &mut Replies) {
+        let channels = match self.channels.is_empty() {
+            true => subscriber.channels(),
+            false => self.channels.into_iter().map(Arc::new).collect(),
+        };
+        if channels.is_empty() {
+            write_push(
+                vec![
+                    Reply::from("unsubscribe"),
+                    Reply::from(None::<Reply>),
+                    Reply::from(subscriber.count()),
+                ],
+                out,
+            );
+        }
+        for channel in channels {
+            let count = subscriber.unsubscribe(&channel);
+            write_push(
+                vec![
+                    Reply::from("unsubscribe"),
+                    Reply::from(channel),
+                    Reply::from(count),
+                ],
+                out,
+            );
+        }
+    }
+}
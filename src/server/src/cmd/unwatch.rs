@@ -0,0 +1,25 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use crate::transaction::Watched;
+
+pub(crate) struct Unwatch {}
+
+impl Builder for Unwatch {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'unwatch' command",
+            )));
+        }
+        Ok(Self {})
+    }
+}
+
+impl Unwatch {
+    pub fn apply(self, watched: &mut Vec<Watched>) -> Reply {
+        watched.clear();
+        Reply::Simple(String::from("OK"))
+    }
+}
@@ -0,0 +1,72 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::reply::Reply;
+use crate::service::Error;
+use crate::transaction::Watched;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct Watch {
+    keys: Vec<String>,
+}
+
+impl Builder for Watch {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if adpater.get_total() == 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'watch' command",
+            )));
+        }
+        Ok(Self {
+            keys: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Watch {
+    /// Records the versions of the keys in `db`, the database numbered `index`.
+    pub fn apply(self, db: Database, index: usize, watched: &mut Vec<Watched>) -> Reply {
+        watched.extend(self.keys.into_iter().map(|key| Watched {
+            db: index,
+            version: db.version(key.clone()),
+            key,
+        }));
+        Reply::Simple(String::from("OK"))
+    }
+}
+
+#[test]
+fn test_watch() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        client.call(&["SET", "x", "1"]).await;
+        client.call(&["ZADD", "z", "1", "a"]).await;
+
+        client.call(&["WATCH", "x"]).await;
+        other.call(&["SET", "x", "2"]).await;
+        client.call(&["MULTI"]).await;
+        client.call(&["GET", "x"]).await;
+        assert_eq!(client.call(&["EXEC"]).await, "+nil\r\n");
+
+        client.call(&["WATCH", "x"]).await;
+        other.call(&["RENAME", "x", "y"]).await;
+        other.call(&["RENAME", "y", "x"]).await;
+        client.call(&["MULTI"]).await;
+        assert_eq!(client.call(&["EXEC"]).await, "+nil\r\n");
+
+        // none of these change the set
+        client.call(&["WATCH", "z"]).await;
+        other.call(&["ZREM", "z", "missing"]).await;
+        other.call(&["ZADD", "z", "1", "a"]).await;
+        other.call(&["LPOP", "z"]).await;
+        client.call(&["MULTI"]).await;
+        client.call(&["ZCARD", "z"]).await;
+        assert_eq!(client.call(&["EXEC"]).await, "*1\r\n:1\r\n");
+    });
+}
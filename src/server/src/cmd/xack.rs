@@ -0,0 +1,38 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamError, StreamId};
+use std::convert::Infallible;
+
+pub(crate) struct XAck {
+    key: String,
+    group: String,
+    ids: Vec<StreamId>,
+}
+
+impl Builder for XAck {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let group = adpater.get_field::<String, Infallible>()?;
+        if adpater.get_total() == 0 {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'xack' command",
+            )));
+        }
+        Ok(Self {
+            key,
+            group,
+            ids: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<StreamId, StreamError>())
+                .collect::<Result<Vec<StreamId>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for XAck {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.xack(self.key, self.group, &self.ids))
+    }
+}
@@ -0,0 +1,114 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xtrim::parse_trim;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{AddId, Database, StreamError, Trim};
+use std::convert::Infallible;
+use std::str::FromStr;
+
+pub(crate) struct XAdd {
+    key: String,
+    nomkstream: bool,
+    trim: Option<Trim>,
+    id: AddId,
+    fields: Vec<String>,
+}
+
+impl Builder for XAdd {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let mut nomkstream = false;
+        let mut trim = None;
+
+        let id = loop {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "NOMKSTREAM" => nomkstream = true,
+                strategy @ ("MAXLEN" | "MINID") => trim = Some(parse_trim(adpater, strategy)?),
+                _ => {
+                    break AddId::from_str(&field)
+                        .map_err(|e: StreamError| Error::Protocol(e.to_string()))?
+                }
+            }
+        };
+
+        let total = adpater.get_total();
+        if total == 0 || !total.is_multiple_of(2) {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'xadd' command",
+            )));
+        }
+        let fields = (0..total)
+            .map(|_| adpater.get_field::<String, Infallible>())
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        Ok(Self {
+            key,
+            nomkstream,
+            trim,
+            id,
+            fields,
+        })
+    }
+}
+
+impl Apply for XAdd {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(
+            db.xadd(self.key, self.id, self.fields, self.nomkstream, self.trim)
+                .map(|id| id.map(|id| id.to_string())),
+        )
+    }
+}
+
+#[test]
+fn test_xadd() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(
+            client.call(&["XADD", "s", "1-1", "a", "1"]).await,
+            "$3\r\n1-1\r\n"
+        );
+        assert_eq!(
+            client.call(&["XADD", "s", "1-*", "b", "2"]).await,
+            "$3\r\n1-2\r\n"
+        );
+        assert_eq!(
+            client.call(&["XADD", "s", "1-1", "c", "3"]).await,
+            "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["XADD", "s", "MAXLEN", "2", "2-0", "c", "3"])
+                .await,
+            "$3\r\n2-0\r\n"
+        );
+        assert_eq!(client.call(&["XLEN", "s"]).await, ":2\r\n");
+        assert_eq!(
+            client.call(&["XRANGE", "s", "-", "+"]).await,
+            "*2\r\n*2\r\n$3\r\n1-2\r\n*2\r\n$1\r\nb\r\n$1\r\n2\r\n\
+             *2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nc\r\n$1\r\n3\r\n"
+        );
+        assert_eq!(
+            client.call(&["XRANGE", "s", "2", "+", "COUNT", "1"]).await,
+            "*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nc\r\n$1\r\n3\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["XADD", "none", "NOMKSTREAM", "*", "a", "1"])
+                .await,
+            "+nil\r\n"
+        );
+        assert_eq!(client.call(&["XLEN", "none"]).await, ":0\r\n");
+
+        assert_eq!(client.call(&["XTRIM", "s", "MINID", "2"]).await, ":1\r\n");
+        assert_eq!(client.call(&["XTRIM", "s", "MAXLEN", "1"]).await, ":0\r\n");
+        assert_eq!(client.call(&["XTRIM", "s", "MAXLEN", "0"]).await, ":1\r\n");
+        assert_eq!(client.call(&["XLEN", "s"]).await, ":0\r\n");
+    });
+}
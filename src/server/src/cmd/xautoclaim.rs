@@ -0,0 +1,88 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xrange::{group_entries_reply, ids_reply, parse_start};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+pub(crate) struct XAutoClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle_ms: u64,
+    start: StreamId,
+    count: usize,
+    just_id: bool,
+}
+
+impl Builder for XAutoClaim {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let group = adpater.get_field::<String, Infallible>()?;
+        let consumer = adpater.get_field::<String, Infallible>()?;
+        let min_idle_ms = adpater.get_field::<u64, ParseIntError>()?;
+        let start = parse_start(&adpater.get_field::<String, Infallible>()?)?;
+
+        let mut count = 100;
+        let mut just_id = false;
+        while adpater.get_total() > 0 {
+            match adpater
+                .get_field::<String, Infallible>()?
+                .to_uppercase()
+                .as_str()
+            {
+                "COUNT" => {
+                    count = adpater.get_field::<usize, ParseIntError>()?;
+                    if count == 0 {
+                        return Err(Error::Protocol(String::from("COUNT must be > 0")));
+                    }
+                }
+                "JUSTID" => just_id = true,
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+
+        Ok(Self {
+            key,
+            group,
+            consumer,
+            min_idle_ms,
+            start,
+            count,
+            just_id,
+        })
+    }
+}
+
+impl Apply for XAutoClaim {
+    /// `[cursor, [entry, ...], [deleted ID, ...]]`, only the IDs of the entries with
+    /// `JUSTID`.
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let just_id = self.just_id;
+        Reply::from(
+            db.xautoclaim(
+                self.key,
+                self.group,
+                self.consumer,
+                self.min_idle_ms,
+                self.start,
+                self.count,
+                self.just_id,
+            )
+            .map(|(cursor, entries, deleted)| {
+                let entries = match just_id {
+                    true => ids_reply(entries.into_iter().map(|(id, _)| id).collect()),
+                    false => group_entries_reply(entries),
+                };
+                Reply::Array(vec![
+                    Reply::from(cursor.to_string()),
+                    entries,
+                    ids_reply(deleted),
+                ])
+            }),
+        )
+    }
+}
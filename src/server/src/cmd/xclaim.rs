@@ -0,0 +1,153 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xrange::{group_entries_reply, ids_reply};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{ClaimOptions, Database, StreamError, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+pub(crate) struct XClaim {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle_ms: u64,
+    ids: Vec<StreamId>,
+    options: ClaimOptions,
+}
+
+impl Builder for XClaim {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let group = adpater.get_field::<String, Infallible>()?;
+        let consumer = adpater.get_field::<String, Infallible>()?;
+        let min_idle_ms = adpater.get_field::<u64, ParseIntError>()?;
+
+        // IDs come first, the options start at the first field that is not one
+        let mut ids = Vec::new();
+        let mut options = ClaimOptions::default();
+        let mut in_options = false;
+        while adpater.get_total() > 0 {
+            let field = adpater.get_field::<String, Infallible>()?;
+            if !in_options {
+                if let Ok(id) = StreamId::from_str(&field) {
+                    ids.push(id);
+                    continue;
+                }
+                in_options = true;
+            }
+            match field.to_uppercase().as_str() {
+                "IDLE" => options.idle = Some(adpater.get_field::<u64, ParseIntError>()?),
+                "TIME" => options.time = Some(adpater.get_field::<u64, ParseIntError>()?),
+                "RETRYCOUNT" => {
+                    options.retry_count = Some(adpater.get_field::<u64, ParseIntError>()?)
+                }
+                "FORCE" => options.force = true,
+                "JUSTID" => options.just_id = true,
+                "LASTID" => options.last_id = Some(adpater.get_field::<StreamId, StreamError>()?),
+                _ => {
+                    return Err(Error::Protocol(String::from(
+                        "Invalid stream ID specified as stream command argument",
+                    )))
+                }
+            }
+        }
+        if ids.is_empty() {
+            return Err(Error::Protocol(String::from(
+                "wrong number of arguments for 'xclaim' command",
+            )));
+        }
+
+        Ok(Self {
+            key,
+            group,
+            consumer,
+            min_idle_ms,
+            ids,
+            options,
+        })
+    }
+}
+
+impl Apply for XClaim {
+    /// Claimed entries, with `None` fields under `JUSTID`.
+    /// The entries claimed, only their IDs with `JUSTID`.
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let just_id = self.options.just_id;
+        Reply::from(
+            db.xclaim(
+                self.key,
+                self.group,
+                self.consumer,
+                self.min_idle_ms,
+                &self.ids,
+                &self.options,
+            )
+            .map(|entries| match just_id {
+                true => ids_reply(entries.into_iter().map(|(id, _)| id).collect()),
+                false => group_entries_reply(entries),
+            }),
+        )
+    }
+}
+
+#[test]
+fn test_xclaim() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        client.call(&["XADD", "s", "1-0", "a", "1"]).await;
+        client.call(&["XADD", "s", "2-0", "b", "2"]).await;
+        client.call(&["XADD", "s", "3-0", "c", "3"]).await;
+        client.call(&["XGROUP", "CREATE", "s", "g", "0"]).await;
+        client
+            .call(&["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", ">"])
+            .await;
+
+        // not idle long enough yet
+        assert_eq!(
+            client
+                .call(&["XCLAIM", "s", "g", "bob", "3600000", "1-0"])
+                .await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            client.call(&["XCLAIM", "s", "g", "bob", "0", "1-0"]).await,
+            "*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["XCLAIM", "s", "g", "bob", "0", "2-0", "9-0", "JUSTID"])
+                .await,
+            "*1\r\n$3\r\n2-0\r\n"
+        );
+        assert_eq!(
+            client.call(&["XPENDING", "s", "g"]).await,
+            "*4\r\n:3\r\n$3\r\n1-0\r\n$3\r\n3-0\r\n\
+             *2\r\n*2\r\n$5\r\nalice\r\n$1\r\n1\r\n*2\r\n$3\r\nbob\r\n$1\r\n2\r\n"
+        );
+
+        client.call(&["XDEL", "s", "2-0"]).await;
+        assert_eq!(
+            client
+                .call(&["XAUTOCLAIM", "s", "g", "carol", "0", "0", "COUNT", "1"])
+                .await,
+            "*3\r\n$3\r\n2-0\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n*0\r\n"
+        );
+        // a deleted entry is dropped from the PEL and reported
+        assert_eq!(
+            client
+                .call(&["XAUTOCLAIM", "s", "g", "carol", "0", "2-0", "JUSTID"])
+                .await,
+            "*3\r\n$3\r\n0-0\r\n*1\r\n$3\r\n3-0\r\n*1\r\n$3\r\n2-0\r\n"
+        );
+        assert_eq!(
+            client.call(&["XPENDING", "s", "g"]).await,
+            "*4\r\n:2\r\n$3\r\n1-0\r\n$3\r\n3-0\r\n*1\r\n*2\r\n$5\r\ncarol\r\n$1\r\n2\r\n"
+        );
+    });
+}
@@ -0,0 +1,29 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamError, StreamId};
+use std::convert::Infallible;
+
+pub(crate) struct XDel {
+    key: String,
+    ids: Vec<StreamId>,
+}
+
+impl Builder for XDel {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            ids: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<StreamId, StreamError>())
+                .collect::<Result<Vec<StreamId>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for XDel {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.xdel(self.key, &self.ids))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamError, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+enum Subcommand {
+    /// `id` of `None` stands for `$`.
+    Create {
+        id: Option<StreamId>,
+        mkstream: bool,
+        entries_read: Option<u64>,
+    },
+    SetId {
+        id: Option<StreamId>,
+        entries_read: Option<u64>,
+    },
+    Destroy,
+    CreateConsumer(String),
+    DelConsumer(String),
+}
+
+pub(crate) struct XGroup {
+    key: String,
+    group: String,
+    subcommand: Subcommand,
+}
+
+fn parse_group_id(field: &str) -> Result<Option<StreamId>, Error> {
+    match field {
+        "$" => Ok(None),
+        _ => StreamId::from_str(field)
+            .map(Some)
+            .map_err(|e: StreamError| Error::Protocol(e.to_string())),
+    }
+}
+
+impl Builder for XGroup {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let key = adpater.get_field::<String, Infallible>()?;
+        let group = adpater.get_field::<String, Infallible>()?;
+
+        let subcommand = match subcommand.as_str() {
+            "CREATE" | "SETID" => {
+                let id = parse_group_id(&adpater.get_field::<String, Infallible>()?)?;
+                let mut mkstream = false;
+                let mut entries_read = None;
+                while adpater.get_total() > 0 {
+                    match adpater
+                        .get_field::<String, Infallible>()?
+                        .to_uppercase()
+                        .as_str()
+                    {
+                        "MKSTREAM" if subcommand == "CREATE" => mkstream = true,
+                        "ENTRIESREAD" => {
+                            entries_read = Some(adpater.get_field::<u64, ParseIntError>()?)
+                        }
+                        _ => return Err(Error::Protocol(String::from("syntax error"))),
+                    }
+                }
+                if subcommand == "CREATE" {
+                    Subcommand::Create {
+                        id,
+                        mkstream,
+                        entries_read,
+                    }
+                } else {
+                    Subcommand::SetId { id, entries_read }
+                }
+            }
+            "DESTROY" => Subcommand::Destroy,
+            "CREATECONSUMER" => {
+                Subcommand::CreateConsumer(adpater.get_field::<String, Infallible>()?)
+            }
+            "DELCONSUMER" => Subcommand::DelConsumer(adpater.get_field::<String, Infallible>()?),
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'xgroup' command",
+                )))
+            }
+        };
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+
+        Ok(Self {
+            key,
+            group,
+            subcommand,
+        })
+    }
+}
+
+impl Apply for XGroup {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        match self.subcommand {
+            Subcommand::Create {
+                id,
+                mkstream,
+                entries_read,
+            } => Reply::from(
+                db.xgroup_create(self.key, self.group, id, mkstream, entries_read)
+                    .map(|_| Reply::Simple(String::from("OK"))),
+            ),
+            Subcommand::SetId { id, entries_read } => Reply::from(
+                db.xgroup_setid(self.key, self.group, id, entries_read)
+                    .map(|_| Reply::Simple(String::from("OK"))),
+            ),
+            Subcommand::Destroy => Reply::from(
+                db.xgroup_destroy(self.key, self.group)
+                    .map(|destroyed| destroyed as usize),
+            ),
+            Subcommand::CreateConsumer(consumer) => Reply::from(
+                db.xgroup_createconsumer(self.key, self.group, consumer)
+                    .map(|created| created as usize),
+            ),
+            Subcommand::DelConsumer(consumer) => {
+                Reply::from(db.xgroup_delconsumer(self.key, self.group, consumer))
+            }
+        }
+    }
+}
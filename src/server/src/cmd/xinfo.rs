@@ -0,0 +1,107 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xrange::entry_reply;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{ConsumerInfo, Database, GroupInfo, StreamInfo};
+use std::convert::Infallible;
+
+enum Subcommand {
+    Stream,
+    Groups,
+    Consumers(String),
+}
+
+pub(crate) struct XInfo {
+    key: String,
+    subcommand: Subcommand,
+}
+
+impl Builder for XInfo {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let subcommand = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        let key = adpater.get_field::<String, Infallible>()?;
+        let subcommand = match subcommand.as_str() {
+            "STREAM" => Subcommand::Stream,
+            "GROUPS" => Subcommand::Groups,
+            "CONSUMERS" => Subcommand::Consumers(adpater.get_field::<String, Infallible>()?),
+            _ => {
+                return Err(Error::Protocol(String::from(
+                    "unknown subcommand for 'xinfo' command",
+                )))
+            }
+        };
+        // FULL is not supported
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { key, subcommand })
+    }
+}
+
+fn stream_reply(info: StreamInfo) -> Reply {
+    let entry = |entry: Option<_>| match entry {
+        Some((id, fields)) => entry_reply(id, Some(fields)),
+        None => Reply::Null,
+    };
+    Reply::fields([
+        ("length", Reply::from(info.length)),
+        (
+            "last-generated-id",
+            Reply::from(info.last_generated_id.to_string()),
+        ),
+        (
+            "max-deleted-entry-id",
+            Reply::from(info.max_deleted_id.to_string()),
+        ),
+        ("entries-added", Reply::from(info.entries_added)),
+        (
+            "recorded-first-entry-id",
+            Reply::from(info.recorded_first_id.to_string()),
+        ),
+        ("groups", Reply::from(info.groups)),
+        ("first-entry", entry(info.first_entry)),
+        ("last-entry", entry(info.last_entry)),
+    ])
+}
+
+fn group_reply(group: GroupInfo) -> Reply {
+    Reply::fields([
+        ("name", Reply::from(group.name)),
+        ("consumers", Reply::from(group.consumers)),
+        ("pending", Reply::from(group.pending)),
+        (
+            "last-delivered-id",
+            Reply::from(group.last_delivered_id.to_string()),
+        ),
+        ("entries-read", Reply::from(group.entries_read)),
+        ("lag", Reply::from(group.lag)),
+    ])
+}
+
+fn consumer_reply(consumer: ConsumerInfo) -> Reply {
+    Reply::fields([
+        ("name", Reply::from(consumer.name)),
+        ("pending", Reply::from(consumer.pending)),
+        ("idle", Reply::from(consumer.idle_ms)),
+        (
+            "inactive",
+            Reply::from(consumer.inactive_ms.map_or(-1, |inactive| inactive as i64)),
+        ),
+    ])
+}
+
+impl Apply for XInfo {
+    fn apply(self, db: Database) -> Reply {
+        let reply = match self.subcommand {
+            Subcommand::Stream => db.xinfo_stream(self.key).map(stream_reply),
+            Subcommand::Groups => db
+                .xinfo_groups(self.key)
+                .map(|groups| Reply::Array(groups.into_iter().map(group_reply).collect())),
+            Subcommand::Consumers(group) => db
+                .xinfo_consumers(self.key, group)
+                .map(|consumers| Reply::Array(consumers.into_iter().map(consumer_reply).collect())),
+        };
+        Reply::from(reply)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xrange::{parse_end, parse_start};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, PendingInfo, PendingSummary, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+struct Range {
+    min_idle_ms: Option<u64>,
+    start: StreamId,
+    end: StreamId,
+    count: usize,
+    consumer: Option<String>,
+}
+
+pub(crate) struct XPending {
+    key: String,
+    group: String,
+    range: Option<Range>,
+}
+
+impl Builder for XPending {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let group = adpater.get_field::<String, Infallible>()?;
+        if adpater.get_total() == 0 {
+            return Ok(Self {
+                key,
+                group,
+                range: None,
+            });
+        }
+
+        let mut min_idle_ms = None;
+        let mut start = adpater.get_field::<String, Infallible>()?;
+        if start.eq_ignore_ascii_case("IDLE") {
+            min_idle_ms = Some(adpater.get_field::<u64, ParseIntError>()?);
+            start = adpater.get_field::<String, Infallible>()?;
+        }
+        let start = parse_start(&start)?;
+        let end = parse_end(&adpater.get_field::<String, Infallible>()?)?;
+        let count = adpater.get_field::<usize, ParseIntError>()?;
+        let consumer = match adpater.get_total() {
+            0 => None,
+            1 => Some(adpater.get_field::<String, Infallible>()?),
+            _ => return Err(Error::Protocol(String::from("syntax error"))),
+        };
+
+        Ok(Self {
+            key,
+            group,
+            range: Some(Range {
+                min_idle_ms,
+                start,
+                end,
+                count,
+                consumer,
+            }),
+        })
+    }
+}
+
+/// `[count, first ID, last ID, [[consumer, count], ...]]`, nils past the count when
+/// nothing is pending.
+fn summary_reply(summary: PendingSummary) -> Reply {
+    let mut reply = vec![Reply::from(summary.count)];
+    match summary.range {
+        Some((first, last)) => reply.extend([
+            Reply::from(first.to_string()),
+            Reply::from(last.to_string()),
+            Reply::Array(
+                summary
+                    .consumers
+                    .into_iter()
+                    .map(|(consumer, count)| {
+                        Reply::Array(vec![Reply::from(consumer), Reply::from(count.to_string())])
+                    })
+                    .collect(),
+            ),
+        ]),
+        None => reply.extend([Reply::Null, Reply::Null, Reply::Null]),
+    }
+    Reply::Array(reply)
+}
+
+/// `[[id, consumer, idle, deliveries], ...]`.
+fn extended_reply(list: Vec<PendingInfo>) -> Reply {
+    Reply::Array(
+        list.into_iter()
+            .map(|pending| {
+                Reply::Array(vec![
+                    Reply::from(pending.id.to_string()),
+                    Reply::from(pending.consumer),
+                    Reply::from(pending.idle_ms),
+                    Reply::from(pending.delivery_count),
+                ])
+            })
+            .collect(),
+    )
+}
+
+impl Apply for XPending {
+    fn apply(self, db: Database) -> Reply {
+        let reply = match self.range {
+            None => db.xpending_summary(self.key, self.group).map(summary_reply),
+            Some(range) => db
+                .xpending(
+                    self.key,
+                    self.group,
+                    range.min_idle_ms,
+                    range.start,
+                    range.end,
+                    range.count,
+                    range.consumer,
+                )
+                .map(extended_reply),
+        };
+        Reply::from(reply)
+    }
+}
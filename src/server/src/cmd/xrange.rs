@@ -0,0 +1,111 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, GroupEntry, StreamEntry, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::sync::Arc;
+
+fn invalid() -> Error {
+    Error::Protocol(String::from(
+        "Invalid stream ID specified as stream command argument",
+    ))
+}
+
+/// Lower bound of `XRANGE`: `-`, an ID, or an exclusive `(ID`.
+pub(crate) fn parse_start(field: &str) -> Result<StreamId, Error> {
+    if field == "-" {
+        return Ok(StreamId::MIN);
+    }
+    match field.strip_prefix('(') {
+        Some(id) => StreamId::parse(id, 0)
+            .map_err(|_| invalid())?
+            .next()
+            .ok_or_else(|| Error::Protocol(String::from("invalid start ID for the interval"))),
+        None => StreamId::parse(field, 0).map_err(|_| invalid()),
+    }
+}
+
+/// Upper bound of `XRANGE`: `+`, an ID, or an exclusive `(ID`.
+pub(crate) fn parse_end(field: &str) -> Result<StreamId, Error> {
+    if field == "+" {
+        return Ok(StreamId::MAX);
+    }
+    match field.strip_prefix('(') {
+        Some(id) => StreamId::parse(id, u64::MAX)
+            .map_err(|_| invalid())?
+            .prev()
+            .ok_or_else(|| Error::Protocol(String::from("invalid end ID for the interval"))),
+        None => StreamId::parse(field, u64::MAX).map_err(|_| invalid()),
+    }
+}
+
+/// An entry as `[id, [field, value, ...]]`, its fields nil once deleted.
+pub(crate) fn entry_reply(id: StreamId, fields: Option<Vec<Arc<String>>>) -> Reply {
+    Reply::Array(vec![Reply::from(id.to_string()), Reply::from(fields)])
+}
+
+pub(crate) fn entries_reply(entries: Vec<StreamEntry<Arc<String>>>) -> Reply {
+    Reply::Array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| entry_reply(id, Some(fields)))
+            .collect(),
+    )
+}
+
+pub(crate) fn group_entries_reply(entries: Vec<GroupEntry<Arc<String>>>) -> Reply {
+    Reply::Array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| entry_reply(id, fields))
+            .collect(),
+    )
+}
+
+pub(crate) fn ids_reply(ids: Vec<StreamId>) -> Reply {
+    Reply::Array(
+        ids.into_iter()
+            .map(|id| Reply::from(id.to_string()))
+            .collect(),
+    )
+}
+
+pub(crate) fn parse_count<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Option<usize>, Error> {
+    if adpater.get_total() == 0 {
+        return Ok(None);
+    }
+    let field = adpater.get_field::<String, Infallible>()?;
+    if !field.eq_ignore_ascii_case("COUNT") || adpater.get_total() != 1 {
+        return Err(Error::Protocol(String::from("syntax error")));
+    }
+    Ok(Some(adpater.get_field::<usize, ParseIntError>()?))
+}
+
+pub(crate) struct XRange {
+    key: String,
+    start: StreamId,
+    end: StreamId,
+    count: Option<usize>,
+}
+
+impl Builder for XRange {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            start: parse_start(&adpater.get_field::<String, Infallible>()?)?,
+            end: parse_end(&adpater.get_field::<String, Infallible>()?)?,
+            count: parse_count(adpater)?,
+        })
+    }
+}
+
+impl Apply for XRange {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(
+            db.xrange(self.key, self.start, self.end, self.count, false)
+                .map(entries_reply),
+        )
+    }
+}
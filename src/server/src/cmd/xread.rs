@@ -0,0 +1,162 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::cmd::xrange::entries_reply;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamEntry, StreamError, StreamId, TypeError};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+pub(crate) struct XRead {
+    count: Option<usize>,
+    block: Option<Duration>,
+    keys: Vec<String>,
+    /// `None` stands for `$`, the last ID at the time of the call.
+    ids: Vec<Option<StreamId>>,
+}
+
+impl Builder for XRead {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let mut count = None;
+        let mut block = None;
+        loop {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "COUNT" => count = Some(adpater.get_field::<usize, ParseIntError>()?),
+                "BLOCK" => {
+                    block = Some(Duration::from_millis(
+                        adpater.get_field::<u64, ParseIntError>()?,
+                    ))
+                }
+                "STREAMS" => break,
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+
+        let total = adpater.get_total();
+        if total == 0 || !total.is_multiple_of(2) {
+            return Err(Error::Protocol(String::from(
+                "Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.",
+            )));
+        }
+        let mut fields = (0..total)
+            .map(|_| adpater.get_field::<String, Infallible>())
+            .collect::<Result<Vec<String>, Error>>()?;
+        let ids = fields
+            .split_off(total / 2)
+            .iter()
+            .map(|id| match id.as_str() {
+                "$" => Ok(None),
+                _ => StreamId::from_str(id)
+                    .map(Some)
+                    .map_err(|e: StreamError| Error::Protocol(e.to_string())),
+            })
+            .collect::<Result<Vec<Option<StreamId>>, Error>>()?;
+
+        Ok(Self {
+            count,
+            block,
+            keys: fields,
+            ids,
+        })
+    }
+}
+
+impl XRead {
+    /// Nil when nothing arrived, an array of the streams read otherwise.
+    pub async fn apply(self, db: Database) -> Reply {
+        let streams = self.read(db).await.map(|streams| {
+            streams.map(|streams| {
+                streams
+                    .into_iter()
+                    .map(|(key, entries)| {
+                        Reply::Array(vec![Reply::from(key), entries_reply(entries)])
+                    })
+                    .collect::<Vec<Reply>>()
+            })
+        });
+        Reply::from(streams)
+    }
+
+    /// `None` when nothing arrived, either right away or before the `BLOCK` timeout.
+    async fn read(
+        self,
+        db: Database,
+    ) -> Result<Option<Vec<(String, Vec<StreamEntry<Arc<String>>>)>>, TypeError> {
+        let mut ids = Vec::with_capacity(self.ids.len());
+        for (key, id) in self.keys.iter().zip(self.ids.iter()) {
+            ids.push(match id {
+                Some(id) => *id,
+                None => db.xlast_id(key.clone())?,
+            });
+        }
+
+        // BLOCK 0 waits forever
+        let deadline = self
+            .block
+            .filter(|block| !block.is_zero())
+            .map(|block| Instant::now() + block);
+        // parked before looking, so an entry added in between still wakes us
+        let blocked = self.block.map(|_| db.block(self.keys.iter().cloned()));
+        loop {
+            let mut streams = Vec::new();
+            for (key, id) in self.keys.iter().zip(ids.iter()) {
+                let entries = db.xread(key.clone(), *id, self.count)?;
+                if !entries.is_empty() {
+                    streams.push((key.clone(), entries));
+                }
+            }
+            if !streams.is_empty() {
+                return Ok(Some(streams));
+            }
+
+            match (&blocked, deadline) {
+                (None, _) => return Ok(None),
+                (Some(blocked), Some(deadline)) => {
+                    if timeout_at(deadline, blocked.wait()).await.is_err() {
+                        return Ok(None);
+                    }
+                }
+                (Some(blocked), None) => blocked.wait().await,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_xread_block() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut first = Client::connect(addr).await;
+        let mut second = Client::connect(addr).await;
+        let mut other = Client::connect(addr).await;
+        other.call(&["XADD", "s", "1-0", "a", "1"]).await;
+        assert_eq!(
+            first
+                .call(&["XREAD", "BLOCK", "50", "STREAMS", "s", "$"])
+                .await,
+            "+nil\r\n"
+        );
+
+        // an append is for every reader waiting on the stream
+        first
+            .send(&["XREAD", "BLOCK", "0", "STREAMS", "s", "$"])
+            .await;
+        second
+            .send(&["XREAD", "BLOCK", "0", "STREAMS", "other", "s", "0", "1-0"])
+            .await;
+        assert_eq!(first.try_read().await, None);
+        assert_eq!(second.try_read().await, None);
+        other.call(&["XADD", "s", "2-0", "b", "2"]).await;
+        let entries =
+            "*1\r\n*2\r\n$1\r\ns\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nb\r\n$1\r\n2\r\n";
+        assert_eq!(first.try_read().await.as_deref(), Some(entries));
+        assert_eq!(second.try_read().await.as_deref(), Some(entries));
+    });
+}
@@ -0,0 +1,228 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::Builder;
+use crate::cmd::xrange::group_entries_reply;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, Error as DatabaseError, GroupEntry, StreamError, StreamId};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+pub(crate) struct XReadGroup {
+    group: String,
+    consumer: String,
+    count: Option<usize>,
+    block: Option<Duration>,
+    noack: bool,
+    keys: Vec<String>,
+    /// `None` stands for `>`, the entries never delivered to the group.
+    ids: Vec<Option<StreamId>>,
+}
+
+impl Builder for XReadGroup {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        if !adpater
+            .get_field::<String, Infallible>()?
+            .eq_ignore_ascii_case("GROUP")
+        {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        let group = adpater.get_field::<String, Infallible>()?;
+        let consumer = adpater.get_field::<String, Infallible>()?;
+
+        let mut count = None;
+        let mut block = None;
+        let mut noack = false;
+        loop {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "COUNT" => count = Some(adpater.get_field::<usize, ParseIntError>()?),
+                "BLOCK" => {
+                    block = Some(Duration::from_millis(
+                        adpater.get_field::<u64, ParseIntError>()?,
+                    ))
+                }
+                "NOACK" => noack = true,
+                "STREAMS" => break,
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+
+        let total = adpater.get_total();
+        if total == 0 || !total.is_multiple_of(2) {
+            return Err(Error::Protocol(String::from(
+                "Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.",
+            )));
+        }
+        let mut fields = (0..total)
+            .map(|_| adpater.get_field::<String, Infallible>())
+            .collect::<Result<Vec<String>, Error>>()?;
+        let ids = fields
+            .split_off(total / 2)
+            .iter()
+            .map(|id| match id.as_str() {
+                ">" => Ok(None),
+                _ => StreamId::from_str(id)
+                    .map(Some)
+                    .map_err(|e: StreamError| Error::Protocol(e.to_string())),
+            })
+            .collect::<Result<Vec<Option<StreamId>>, Error>>()?;
+
+        Ok(Self {
+            group,
+            consumer,
+            count,
+            block,
+            noack,
+            keys: fields,
+            ids,
+        })
+    }
+}
+
+impl XReadGroup {
+    /// Nil when nothing arrived, an array of the streams read otherwise.
+    pub async fn apply(self, db: Database) -> Reply {
+        let streams = self.read(db).await.map(|streams| {
+            streams.map(|streams| {
+                streams
+                    .into_iter()
+                    .map(|(key, entries)| {
+                        Reply::Array(vec![Reply::from(key), group_entries_reply(entries)])
+                    })
+                    .collect::<Vec<Reply>>()
+            })
+        });
+        Reply::from(streams)
+    }
+
+    /// `None` when nothing arrived, either right away or before the `BLOCK` timeout.
+    /// Reading the pending history never blocks and always replies every stream.
+    async fn read(
+        self,
+        db: Database,
+    ) -> Result<Option<Vec<(String, Vec<GroupEntry<Arc<String>>>)>>, DatabaseError> {
+        let mut db = db;
+        let history = self.ids.iter().any(|id| id.is_some());
+        let block = self.block.filter(|_| !history);
+
+        // BLOCK 0 waits forever
+        let deadline = block
+            .filter(|block| !block.is_zero())
+            .map(|block| Instant::now() + block);
+        // parked before looking, so an entry added in between still wakes us
+        let blocked = block.map(|_| db.block(self.keys.iter().cloned()));
+        loop {
+            let mut streams = Vec::new();
+            for (key, id) in self.keys.iter().zip(self.ids.iter()) {
+                let entries = db.xreadgroup(
+                    key.clone(),
+                    self.group.clone(),
+                    self.consumer.clone(),
+                    *id,
+                    self.count,
+                    self.noack,
+                )?;
+                if !entries.is_empty() || id.is_some() {
+                    streams.push((key.clone(), entries));
+                }
+            }
+            if !streams.is_empty() {
+                return Ok(Some(streams));
+            }
+
+            match (&blocked, deadline) {
+                (None, _) => return Ok(None),
+                (Some(blocked), Some(deadline)) => {
+                    if timeout_at(deadline, blocked.wait()).await.is_err() {
+                        return Ok(None);
+                    }
+                }
+                (Some(blocked), None) => blocked.wait().await,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_xreadgroup() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        client.call(&["XADD", "s", "1-0", "a", "1"]).await;
+        client.call(&["XADD", "s", "2-0", "b", "2"]).await;
+        assert_eq!(
+            client.call(&["XGROUP", "CREATE", "s", "g", "0"]).await,
+            "+OK\r\n"
+        );
+
+        // new entries go to the consumer and stay pending until acknowledged
+        assert_eq!(
+            client
+                .call(&[
+                    "XREADGROUP",
+                    "GROUP",
+                    "g",
+                    "alice",
+                    "COUNT",
+                    "1",
+                    "STREAMS",
+                    "s",
+                    ">"
+                ])
+                .await,
+            "*1\r\n*2\r\n$1\r\ns\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"])
+                .await,
+            "*1\r\n*2\r\n$1\r\ns\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", ">"])
+                .await,
+            "+nil\r\n"
+        );
+        assert_eq!(
+            client.call(&["XPENDING", "s", "g"]).await,
+            "*4\r\n:2\r\n$3\r\n1-0\r\n$3\r\n2-0\r\n\
+             *2\r\n*2\r\n$5\r\nalice\r\n$1\r\n1\r\n*2\r\n$3\r\nbob\r\n$1\r\n1\r\n"
+        );
+
+        // reading the history replays the consumer's own pending entries
+        assert_eq!(
+            client
+                .call(&["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"])
+                .await,
+            "*1\r\n*2\r\n$1\r\ns\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+
+        assert_eq!(
+            client.call(&["XACK", "s", "g", "1-0", "9-0"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(client.call(&["XACK", "s", "g", "1-0"]).await, ":0\r\n");
+        assert_eq!(
+            client
+                .call(&["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"])
+                .await,
+            "*1\r\n*2\r\n$1\r\ns\r\n*0\r\n"
+        );
+        assert_eq!(
+            client.call(&["XPENDING", "s", "g"]).await,
+            "*4\r\n:1\r\n$3\r\n2-0\r\n$3\r\n2-0\r\n*1\r\n*2\r\n$3\r\nbob\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(client.call(&["XACK", "s", "g", "2-0"]).await, ":1\r\n");
+        assert_eq!(
+            client.call(&["XPENDING", "s", "g"]).await,
+            "*4\r\n:0\r\n+nil\r\n+nil\r\n+nil\r\n"
+        );
+    });
+}
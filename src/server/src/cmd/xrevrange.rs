@@ -0,0 +1,34 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::xrange::{entries_reply, parse_count, parse_end, parse_start};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamId};
+use std::convert::Infallible;
+
+pub(crate) struct XRevRange {
+    key: String,
+    end: StreamId,
+    start: StreamId,
+    count: Option<usize>,
+}
+
+impl Builder for XRevRange {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            end: parse_end(&adpater.get_field::<String, Infallible>()?)?,
+            start: parse_start(&adpater.get_field::<String, Infallible>()?)?,
+            count: parse_count(adpater)?,
+        })
+    }
+}
+
+impl Apply for XRevRange {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(
+            db.xrange(self.key, self.start, self.end, self.count, true)
+                .map(entries_reply),
+        )
+    }
+}
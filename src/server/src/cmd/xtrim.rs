@@ -0,0 +1,79 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, StreamError, StreamId, Trim, TrimStrategy};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// Parses `[=|~] threshold [LIMIT count]` following `MAXLEN` or `MINID`.
+pub(crate) fn parse_trim<'a>(
+    adpater: &mut FieldBuilder<'a>,
+    strategy: &str,
+) -> Result<Trim, Error> {
+    let mut approximate = false;
+    let mut threshold = adpater.get_field::<String, Infallible>()?;
+    if threshold == "~" || threshold == "=" {
+        approximate = threshold == "~";
+        threshold = adpater.get_field::<String, Infallible>()?;
+    }
+
+    let strategy = match strategy {
+        "MAXLEN" => TrimStrategy::MaxLen(
+            usize::from_str(&threshold).map_err(|e| Error::Protocol(e.to_string()))?,
+        ),
+        _ => TrimStrategy::MinId(
+            StreamId::from_str(&threshold)
+                .map_err(|e: StreamError| Error::Protocol(e.to_string()))?,
+        ),
+    };
+
+    let mut limit = None;
+    if adpater.get_total() > 0
+        && adpater
+            .peek_field::<String, Infallible>()?
+            .eq_ignore_ascii_case("LIMIT")
+    {
+        adpater.get_field::<String, Infallible>()?;
+        if !approximate {
+            return Err(Error::Protocol(String::from(
+                "syntax error, LIMIT cannot be used without the special ~ option",
+            )));
+        }
+        limit = Some(adpater.get_field::<usize, ParseIntError>()?);
+    }
+
+    Ok(Trim {
+        strategy,
+        approximate,
+        limit,
+    })
+}
+
+pub(crate) struct XTrim {
+    key: String,
+    trim: Trim,
+}
+
+impl Builder for XTrim {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let strategy = adpater.get_field::<String, Infallible>()?.to_uppercase();
+        if strategy != "MAXLEN" && strategy != "MINID" {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        let trim = parse_trim(adpater, &strategy)?;
+        if adpater.get_total() > 0 {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        Ok(Self { key, trim })
+    }
+}
+
+impl Apply for XTrim {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.xtrim(self.key, &self.trim))
+    }
+}
@@ -0,0 +1,149 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{AddFlags, Database};
+use std::convert::Infallible;
+use std::str::FromStr;
+
+pub(crate) struct ZAdd {
+    key: String,
+    flags: AddFlags,
+    ch: bool,
+    incr: bool,
+    members: Vec<(f64, String)>,
+}
+
+impl Builder for ZAdd {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let mut flags = AddFlags::default();
+        let mut ch = false;
+        let mut incr = false;
+
+        let first_score = loop {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "NX" => flags.nx = true,
+                "XX" => flags.xx = true,
+                "GT" => flags.gt = true,
+                "LT" => flags.lt = true,
+                "CH" => ch = true,
+                "INCR" => incr = true,
+                _ => break parse_score(&field)?,
+            }
+        };
+
+        if flags.nx && flags.xx {
+            return Err(Error::Protocol(String::from(
+                "XX and NX options at the same time are not compatible",
+            )));
+        }
+        if (flags.nx && (flags.gt || flags.lt)) || (flags.gt && flags.lt) {
+            return Err(Error::Protocol(String::from(
+                "GT, LT, and/or NX options at the same time are not compatible",
+            )));
+        }
+        if adpater.get_total().is_multiple_of(2) {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+
+        let mut members = vec![(first_score, adpater.get_field::<String, Infallible>()?)];
+        while adpater.get_total() > 0 {
+            let score = parse_score(&adpater.get_field::<String, Infallible>()?)?;
+            members.push((score, adpater.get_field::<String, Infallible>()?));
+        }
+
+        if incr && members.len() > 1 {
+            return Err(Error::Protocol(String::from(
+                "INCR option supports a single increment-element pair",
+            )));
+        }
+
+        Ok(Self {
+            key,
+            flags,
+            ch,
+            incr,
+            members,
+        })
+    }
+}
+
+pub(crate) fn parse_score(field: &str) -> Result<f64, Error> {
+    match f64::from_str(field) {
+        Ok(score) if !score.is_nan() => Ok(score),
+        _ => Err(Error::Protocol(String::from("value is not a valid float"))),
+    }
+}
+
+impl Apply for ZAdd {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        if self.incr {
+            let (increment, member) = self.members.into_iter().next().unwrap();
+            return Reply::from(db.zincrby(self.key, member, increment, self.flags));
+        }
+
+        let ch = self.ch;
+        Reply::from(
+            db.zadd(self.key, self.members, self.flags)
+                .map(|(added, changed)| if ch { added + changed } else { added }),
+        )
+    }
+}
+
+#[test]
+fn test_zadd() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(
+            client.call(&["ZADD", "z", "1", "a", "2", "b"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "NX", "5", "a", "3", "c"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "XX", "4", "a", "9", "d"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(client.call(&["ZSCORE", "z", "a"]).await, "$1\r\n4\r\n");
+        assert_eq!(client.call(&["ZSCORE", "z", "d"]).await, "+nil\r\n");
+        assert_eq!(
+            client.call(&["ZADD", "z", "XX", "CH", "5", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "GT", "CH", "1", "a"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "GT", "CH", "6", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "LT", "CH", "7", "a"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["ZADD", "z", "LT", "CH", "2", "a", "1", "e"])
+                .await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "INCR", "3", "a"]).await,
+            "$1\r\n5\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZADD", "z", "NX", "INCR", "1", "a"]).await,
+            "+nil\r\n"
+        );
+        assert_eq!(client.call(&["ZCARD", "z"]).await, ":4\r\n");
+    });
+}
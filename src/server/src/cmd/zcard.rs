@@ -0,0 +1,24 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct ZCard {
+    key: String,
+}
+
+impl Builder for ZCard {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for ZCard {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.zcard(self.key))
+    }
+}
@@ -0,0 +1,29 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, ParseRangeError, ScoreBound, ScoreRange};
+use std::convert::Infallible;
+
+pub(crate) struct ZCount {
+    key: String,
+    range: ScoreRange,
+}
+
+impl Builder for ZCount {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            range: ScoreRange::new(
+                adpater.get_field::<ScoreBound, ParseRangeError>()?,
+                adpater.get_field::<ScoreBound, ParseRangeError>()?,
+            ),
+        })
+    }
+}
+
+impl Apply for ZCount {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.zcount(self.key, &self.range))
+    }
+}
@@ -0,0 +1,35 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zunion::CombineArgs;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, SetOperation};
+use std::convert::Infallible;
+
+pub(crate) struct ZDiffStore {
+    destination: String,
+    args: CombineArgs,
+}
+
+impl Builder for ZDiffStore {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            destination: adpater.get_field::<String, Infallible>()?,
+            args: CombineArgs::build(adpater, false, false)?,
+        })
+    }
+}
+
+impl Apply for ZDiffStore {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let args = self.args;
+        Reply::from(db.zcombine_store(
+            self.destination,
+            SetOperation::Diff,
+            args.keys,
+            args.weights,
+            args.aggregate,
+        ))
+    }
+}
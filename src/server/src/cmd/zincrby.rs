@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zadd::parse_score;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{AddFlags, Database};
+use std::convert::Infallible;
+
+pub(crate) struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: String,
+}
+
+impl Builder for ZIncrBy {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            increment: parse_score(&adpater.get_field::<String, Infallible>()?)?,
+            member: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for ZIncrBy {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.zincrby(self.key, self.member, self.increment, AddFlags::default()))
+    }
+}
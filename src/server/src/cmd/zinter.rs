@@ -0,0 +1,27 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zrange::with_scores;
+use crate::cmd::zunion::CombineArgs;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, SetOperation};
+
+pub(crate) struct ZInter {
+    args: CombineArgs,
+}
+
+impl Builder for ZInter {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            args: CombineArgs::build(adpater, true, true)?,
+        })
+    }
+}
+
+impl Apply for ZInter {
+    fn apply(self, db: Database) -> Reply {
+        let args = self.args;
+        let list = db.zcombine(SetOperation::Inter, args.keys, args.weights, args.aggregate);
+        Reply::from(list.map(|list| Reply::Array(with_scores(list, args.with_scores))))
+    }
+}
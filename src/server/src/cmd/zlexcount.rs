@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, LexBound, LexRange, ParseRangeError};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+pub(crate) struct ZLexCount {
+    key: String,
+    range: LexRange<Arc<String>>,
+}
+
+impl Builder for ZLexCount {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            range: LexRange::new(
+                adpater.get_field::<LexBound<Arc<String>>, ParseRangeError>()?,
+                adpater.get_field::<LexBound<Arc<String>>, ParseRangeError>()?,
+            ),
+        })
+    }
+}
+
+impl Apply for ZLexCount {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.zlexcount(self.key, &self.range))
+    }
+}
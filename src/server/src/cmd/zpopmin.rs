@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zrange::with_scores;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+pub(crate) struct ZPopMin {
+    key: String,
+    count: Option<usize>,
+}
+
+impl Builder for ZPopMin {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            count: adpater.get_field_option::<usize, ParseIntError>()?,
+        })
+    }
+}
+
+impl Apply for ZPopMin {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        let list = db.zpop(self.key, self.count.unwrap_or(1), false);
+        Reply::from(list.map(|list| Reply::Array(with_scores(list, true))))
+    }
+}
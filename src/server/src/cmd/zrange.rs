@@ -0,0 +1,178 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, LexBound, LexRange, RangeSpec, ScoreBound, ScoreRange};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(PartialEq)]
+enum By {
+    Rank,
+    Score,
+    Lex,
+}
+
+pub(crate) struct ZRange {
+    key: String,
+    spec: RangeSpec<Arc<String>>,
+    rev: bool,
+    offset: usize,
+    count: Option<usize>,
+    with_scores: bool,
+    skip: bool,
+}
+
+impl Builder for ZRange {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let mut min = adpater.get_field::<String, Infallible>()?;
+        let mut max = adpater.get_field::<String, Infallible>()?;
+
+        let mut by = By::Rank;
+        let mut rev = false;
+        let mut limit = None;
+        let mut with_scores = false;
+        while adpater.get_total() > 0 {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "BYSCORE" => by = By::Score,
+                "BYLEX" => by = By::Lex,
+                "REV" => rev = true,
+                "WITHSCORES" => with_scores = true,
+                "LIMIT" if adpater.get_total() >= 2 => {
+                    limit = Some((
+                        adpater.get_field::<i64, ParseIntError>()?,
+                        adpater.get_field::<i64, ParseIntError>()?,
+                    ));
+                }
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+
+        if limit.is_some() && by == By::Rank {
+            return Err(Error::Protocol(String::from(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+            )));
+        }
+        if with_scores && by == By::Lex {
+            return Err(Error::Protocol(String::from(
+                "syntax error, WITHSCORES not supported in combination with BYLEX",
+            )));
+        }
+
+        // with REV the score and lex forms take `max` before `min`
+        if rev && by != By::Rank {
+            std::mem::swap(&mut min, &mut max);
+        }
+        let spec = parse_spec(by, &min, &max)?;
+
+        let (offset, count) = limit.unwrap_or((0, -1));
+        Ok(Self {
+            key,
+            spec,
+            rev,
+            offset: offset.max(0) as usize,
+            count: if count < 0 {
+                None
+            } else {
+                Some(count as usize)
+            },
+            with_scores,
+            skip: offset < 0,
+        })
+    }
+}
+
+fn parse_spec(by: By, min: &str, max: &str) -> Result<RangeSpec<Arc<String>>, Error> {
+    let protocol = |e: &dyn ToString| Error::Protocol(e.to_string());
+    match by {
+        By::Rank => Ok(RangeSpec::Rank(
+            i64::from_str(min).map_err(|e| protocol(&e))?,
+            i64::from_str(max).map_err(|e| protocol(&e))?,
+        )),
+        By::Score => Ok(RangeSpec::Score(ScoreRange::new(
+            ScoreBound::from_str(min).map_err(|e| protocol(&e))?,
+            ScoreBound::from_str(max).map_err(|e| protocol(&e))?,
+        ))),
+        By::Lex => Ok(RangeSpec::Lex(LexRange::new(
+            LexBound::from_str(min).map_err(|e| protocol(&e))?,
+            LexBound::from_str(max).map_err(|e| protocol(&e))?,
+        ))),
+    }
+}
+
+/// Flattens `(member, score)` pairs into the reply layout used by the sorted set commands.
+pub(crate) fn with_scores(list: Vec<(Arc<String>, f64)>, scores: bool) -> Vec<Reply> {
+    let mut replies = Vec::with_capacity(if scores { list.len() * 2 } else { list.len() });
+    for (member, score) in list {
+        replies.push(Reply::from(member));
+        if scores {
+            replies.push(Reply::from(score));
+        }
+    }
+    replies
+}
+
+impl Apply for ZRange {
+    fn apply(self, db: Database) -> Reply {
+        if self.skip {
+            return Reply::Array(Vec::new());
+        }
+        let list = db.zrange(self.key, &self.spec, self.rev, self.offset, self.count);
+        Reply::from(list.map(|list| Reply::Array(with_scores(list, self.with_scores))))
+    }
+}
+
+#[test]
+fn test_zrange() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        client
+            .call(&["ZADD", "z", "1", "a", "2", "b", "3", "c", "4", "d"])
+            .await;
+        assert_eq!(
+            client.call(&["ZRANGE", "z", "1", "-2"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["ZRANGE", "z", "0", "1", "REV", "WITHSCORES"])
+                .await,
+            "*4\r\n$1\r\nd\r\n$1\r\n4\r\n$1\r\nc\r\n$1\r\n3\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZRANGE", "z", "(1", "3", "BYSCORE"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["ZRANGE", "z", "+inf", "-inf", "BYSCORE", "REV", "LIMIT", "1", "2"])
+                .await,
+            "*2\r\n$1\r\nc\r\n$1\r\nb\r\n"
+        );
+        assert_eq!(
+            client.call(&["ZRANGE", "z", "[b", "(d", "BYLEX"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(
+            client
+                .call(&["ZRANGE", "z", "+", "-", "BYLEX", "REV", "LIMIT", "0", "1"])
+                .await,
+            "*1\r\n$1\r\nd\r\n"
+        );
+        assert_eq!(client.call(&["ZRANGE", "z", "5", "9"]).await, "*0\r\n");
+        assert_eq!(client.call(&["ZRANGE", "nope", "0", "-1"]).await, "*0\r\n");
+
+        client.call(&["SET", "s", "v"]).await;
+        assert_eq!(
+            client.call(&["ZRANGE", "s", "0", "-1"]).await,
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    });
+}
@@ -0,0 +1,29 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct ZRem {
+    key: String,
+    members: Vec<String>,
+}
+
+impl Builder for ZRem {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            members: (0..adpater.get_total())
+                .map(|_| adpater.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()?,
+        })
+    }
+}
+
+impl Apply for ZRem {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.zrem(self.key, self.members))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, LexBound, LexRange, ParseRangeError, RangeSpec};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+pub(crate) struct ZRemRangeByLex {
+    key: String,
+    range: LexRange<Arc<String>>,
+}
+
+impl Builder for ZRemRangeByLex {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            range: LexRange::new(
+                adpater.get_field::<LexBound<Arc<String>>, ParseRangeError>()?,
+                adpater.get_field::<LexBound<Arc<String>>, ParseRangeError>()?,
+            ),
+        })
+    }
+}
+
+impl Apply for ZRemRangeByLex {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.zremrange(self.key, &RangeSpec::Lex(self.range)))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, RangeSpec};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+pub(crate) struct ZRemRangeByRank {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+impl Builder for ZRemRangeByRank {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            start: adpater.get_field::<i64, ParseIntError>()?,
+            stop: adpater.get_field::<i64, ParseIntError>()?,
+        })
+    }
+}
+
+impl Apply for ZRemRangeByRank {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.zremrange(self.key, &RangeSpec::Rank(self.start, self.stop)))
+    }
+}
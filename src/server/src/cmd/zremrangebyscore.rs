@@ -0,0 +1,30 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, ParseRangeError, RangeSpec, ScoreBound, ScoreRange};
+use std::convert::Infallible;
+
+pub(crate) struct ZRemRangeByScore {
+    key: String,
+    range: ScoreRange,
+}
+
+impl Builder for ZRemRangeByScore {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            range: ScoreRange::new(
+                adpater.get_field::<ScoreBound, ParseRangeError>()?,
+                adpater.get_field::<ScoreBound, ParseRangeError>()?,
+            ),
+        })
+    }
+}
+
+impl Apply for ZRemRangeByScore {
+    fn apply(self, db: Database) -> Reply {
+        let mut db = db;
+        Reply::from(db.zremrange(self.key, &RangeSpec::Score(self.range)))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::Database;
+use std::convert::Infallible;
+
+pub(crate) struct ZRevRank {
+    key: String,
+    member: String,
+}
+
+impl Builder for ZRevRank {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            key: adpater.get_field::<String, Infallible>()?,
+            member: adpater.get_field::<String, Infallible>()?,
+        })
+    }
+}
+
+impl Apply for ZRevRank {
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(db.zrank(self.key, self.member, true))
+    }
+}
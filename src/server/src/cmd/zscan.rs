@@ -0,0 +1,49 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::scan::{page, parse_scan};
+use crate::cmd::traits::{Apply, Builder};
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Database, ScanFilter};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+pub(crate) struct ZScan {
+    key: String,
+    cursor: u64,
+    count: usize,
+    filter: ScanFilter,
+}
+
+impl Builder for ZScan {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        let key = adpater.get_field::<String, Infallible>()?;
+        let cursor = adpater.get_field::<u64, ParseIntError>()?;
+        let (count, filter) = parse_scan(adpater, false)?;
+        Ok(Self {
+            key,
+            cursor,
+            count,
+            filter,
+        })
+    }
+}
+
+impl Apply for ZScan {
+    /// Members are followed by their score.
+    fn apply(self, db: Database) -> Reply {
+        Reply::from(
+            db.zscan(self.key, self.cursor, self.count, &self.filter)
+                .map(|(cursor, members)| {
+                    page(
+                        cursor,
+                        members
+                            .into_iter()
+                            .flat_map(|(member, score)| {
+                                [Reply::from(member), Reply::from(score.to_string())]
+                            })
+                            .collect(),
+                    )
+                }),
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use crate::cmd::field_builder::FieldBuilder;
+use crate::cmd::traits::{Apply, Builder};
+use crate::cmd::zadd::parse_score;
+use crate::cmd::zrange::with_scores;
+use crate::reply::Reply;
+use crate::service::Error;
+use database::{Aggregate, Database, SetOperation};
+use std::convert::Infallible;
+use std::num::ParseIntError;
+
+/// Arguments shared by the `ZUNION`, `ZINTER` and `ZDIFF` families:
+/// `numkeys key [key ...] [WEIGHTS weight ...] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]`.
+pub(crate) struct CombineArgs {
+    pub(crate) keys: Vec<String>,
+    pub(crate) weights: Option<Vec<f64>>,
+    pub(crate) aggregate: Aggregate,
+    pub(crate) with_scores: bool,
+}
+
+impl CombineArgs {
+    pub(crate) fn build<'a>(
+        adpater: &mut FieldBuilder<'a>,
+        weighted: bool,
+        scores: bool,
+    ) -> Result<Self, Error> {
+        let numkeys = adpater.get_field::<usize, ParseIntError>()?;
+        if numkeys == 0 {
+            return Err(Error::Protocol(String::from(
+                "at least 1 input key is needed for this command",
+            )));
+        }
+        if numkeys > adpater.get_total() {
+            return Err(Error::Protocol(String::from("syntax error")));
+        }
+        let keys = (0..numkeys)
+            .map(|_| adpater.get_field::<String, Infallible>())
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let mut weights = None;
+        let mut aggregate = Aggregate::Sum;
+        let mut with_scores = false;
+        while adpater.get_total() > 0 {
+            let field = adpater.get_field::<String, Infallible>()?;
+            match field.to_uppercase().as_str() {
+                "WEIGHTS" if weighted && adpater.get_total() >= numkeys => {
+                    weights = Some(
+                        (0..numkeys)
+                            .map(|_| parse_score(&adpater.get_field::<String, Infallible>()?))
+                            .collect::<Result<Vec<f64>, Error>>()?,
+                    );
+                }
+                "AGGREGATE" if weighted && adpater.get_total() > 0 => {
+                    let field = adpater.get_field::<String, Infallible>()?;
+                    aggregate = match field.to_uppercase().as_str() {
+                        "SUM" => Aggregate::Sum,
+                        "MIN" => Aggregate::Min,
+                        "MAX" => Aggregate::Max,
+                        _ => return Err(Error::Protocol(String::from("syntax error"))),
+                    };
+                }
+                "WITHSCORES" if scores => with_scores = true,
+                _ => return Err(Error::Protocol(String::from("syntax error"))),
+            }
+        }
+
+        Ok(Self {
+            keys,
+            weights,
+            aggregate,
+            with_scores,
+        })
+    }
+}
+
+pub(crate) struct ZUnion {
+    args: CombineArgs,
+}
+
+impl Builder for ZUnion {
+    fn build<'a>(adpater: &mut FieldBuilder<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            args: CombineArgs::build(adpater, true, true)?,
+        })
+    }
+}
+
+impl Apply for ZUnion {
+    fn apply(self, db: Database) -> Reply {
+        let args = self.args;
+        let list = db.zcombine(SetOperation::Union, args.keys, args.weights, args.aggregate);
+        Reply::from(list.map(|list| Reply::Array(with_scores(list, args.with_scores))))
+    }
+}
@@ -0,0 +1,215 @@
+use database::{EvictionPolicy, KeyspaceEvents};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
+use std::path::PathBuf;
+
+/// Settings a `Server` can't run with.
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Settings of a `Server`.
+pub struct Config {
+    /// How many numbered databases clients can `SELECT`.
+    pub databases: usize,
+    /// Makes `DEL` free values in the background like `UNLINK`.
+    pub lazyfree_lazy_user_del: bool,
+    /// Bytes the dataset may use before keys are evicted, 0 for no limit.
+    pub maxmemory: usize,
+    pub maxmemory_policy: EvictionPolicy,
+    /// Keys sampled per slot when looking for one to evict.
+    pub maxmemory_samples: usize,
+    /// Where function libraries are saved to be loaded back on start, `None` keeps them
+    /// in memory only.
+    pub functions_file: Option<PathBuf>,
+    /// Messages a subscriber may leave unread before its connection is closed.
+    pub pubsub_queue_len: usize,
+    /// The keyspace events published to subscribers, none by default.
+    pub notify_keyspace_events: KeyspaceEvents,
+    /// The password clients `AUTH` with before running anything else, `None` to let
+    /// them in without one.
+    pub requirepass: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            databases: 16,
+            lazyfree_lazy_user_del: false,
+            maxmemory: 0,
+            maxmemory_policy: EvictionPolicy::NoEviction,
+            maxmemory_samples: 5,
+            functions_file: None,
+            pubsub_queue_len: 1024,
+            notify_keyspace_events: KeyspaceEvents::default(),
+            requirepass: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the settings the way `redis-server` does, from the config file given first
+    /// if any, then from `--name value` pairs, each overriding what came before.
+    pub fn parse_args<I>(mut self, args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter().peekable();
+        if let Some(path) = args.next_if(|arg| !arg.starts_with("--")) {
+            let file = fs::read_to_string(&path)
+                .map_err(|e| ConfigError(format!("can't read config file '{}': {}", path, e)))?;
+            for line in file.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (name, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                self.set(name, value.trim().trim_matches('"'))?;
+            }
+        }
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| ConfigError(format!("unexpected argument '{}'", arg)))?;
+            let value = args
+                .next()
+                .ok_or_else(|| ConfigError(format!("missing value for '{}'", arg)))?;
+            self.set(name, &value)?;
+        }
+        Ok(self)
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = || ConfigError(format!("invalid {} '{}'", name, value));
+        match name.to_lowercase().as_str() {
+            "databases" => self.databases = value.parse().map_err(|_| invalid())?,
+            "lazyfree-lazy-user-del" => {
+                self.lazyfree_lazy_user_del = parse_bool(value).ok_or_else(invalid)?
+            }
+            "maxmemory" => self.maxmemory = parse_memory(value).ok_or_else(invalid)?,
+            "maxmemory-policy" => self.maxmemory_policy = value.parse().map_err(|_| invalid())?,
+            "maxmemory-samples" => self.maxmemory_samples = value.parse().map_err(|_| invalid())?,
+            // an empty password lets clients in without one
+            "requirepass" => {
+                self.requirepass = Some(value)
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            }
+            "notify-keyspace-events" => {
+                self.notify_keyspace_events = value.parse().map_err(|_| invalid())?
+            }
+            // an empty path keeps the libraries in memory only
+            "functions-file" => {
+                self.functions_file = Some(value)
+                    .filter(|value| !value.is_empty())
+                    .map(PathBuf::from)
+            }
+            _ => return Err(ConfigError(format!("unknown config '{}'", name))),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        if self.databases == 0 {
+            return Err(ConfigError(String::from("databases must be at least 1")));
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Bytes written with the units Redis takes, `k` for 1000 and `kb` for 1024 and so on.
+fn parse_memory(value: &str) -> Option<usize> {
+    let value = value.to_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+#[test]
+fn test_validate() {
+    assert!(Config::default().validate().is_ok());
+    let config = Config {
+        databases: 0,
+        ..Config::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let config = Config::default()
+        .parse_args(args(&[
+            "--maxmemory",
+            "100mb",
+            "--maxmemory-policy",
+            "allkeys-lru",
+            "--functions-file",
+            "functions.ky",
+            "--notify-keyspace-events",
+            "KEA",
+            "--requirepass",
+            "secret",
+        ]))
+        .unwrap();
+    assert_eq!(config.maxmemory, 100 * 1024 * 1024);
+    assert_eq!(config.maxmemory_policy, EvictionPolicy::AllKeysLru);
+    assert_eq!(config.functions_file, Some(PathBuf::from("functions.ky")));
+    assert_eq!(Config::default().functions_file, None);
+    assert_eq!(config.notify_keyspace_events, "KEA".parse().unwrap());
+    assert_eq!(config.requirepass.as_deref(), Some("secret"));
+    assert_eq!(Config::default().requirepass, None);
+    assert!(Config::default()
+        .parse_args(args(&["--maxmemory", "1tb"]))
+        .is_err());
+    assert!(Config::default()
+        .parse_args(args(&["--maxmemory"]))
+        .is_err());
+    assert!(Config::default()
+        .parse_args(args(&["--notify-keyspace-events", "Kq"]))
+        .is_err());
+    assert!(Config::default()
+        .parse_args(args(&["--port", "1"]))
+        .is_err());
+
+    // the options given after the file override it
+    let path = std::env::temp_dir().join(format!("ky-{}.conf", std::process::id()));
+    fs::write(
+        &path,
+        "# ky\nmaxmemory 2k\nfunctions-file \"functions.ky\"\n\ndatabases 4\n",
+    )
+    .unwrap();
+    let config = Config::default()
+        .parse_args(args(&[path.to_str().unwrap(), "--functions-file", ""]))
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(config.maxmemory, 2000);
+    assert_eq!(config.databases, 4);
+    assert_eq!(config.functions_file, None);
+}
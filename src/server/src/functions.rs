@@ -0,0 +1,218 @@
+use crate::scripting::Engine;
+use database::glob_match;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Result as IoResult};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::error;
+
+/// A function a library registered.
+pub(crate) struct Registered {
+    pub(crate) name: String,
+    pub(crate) flags: Vec<String>,
+}
+
+impl Registered {
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "no-writes")
+    }
+}
+
+pub(crate) struct Library {
+    pub(crate) name: String,
+    /// Shared with the engines that ran it, which tell a replaced library by it.
+    pub(crate) code: Arc<String>,
+    pub(crate) functions: Vec<Registered>,
+}
+
+/// Letters, numbers and underscores, the names of libraries and functions.
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+/// The name of the library `#!lua name=<name>` starts `code` with.
+pub(crate) fn library_name(code: &str) -> Result<String, String> {
+    let header = code.lines().next().unwrap_or_default();
+    let header = header
+        .strip_prefix("#!")
+        .ok_or_else(|| String::from("ERR Missing library metadata"))?;
+    let mut fields = header.split_whitespace();
+    let engine = fields.next().unwrap_or_default();
+    if !engine.eq_ignore_ascii_case("lua") {
+        return Err(format!("ERR Engine '{}' not found", engine));
+    }
+    let mut name = None;
+    for field in fields {
+        match field.split_once('=') {
+            Some(("name", value)) => name = Some(value.to_string()),
+            _ => return Err(format!("ERR Invalid metadata value given: {}", field)),
+        }
+    }
+    let name = name.ok_or_else(|| String::from("ERR Library name was not given"))?;
+    if !is_valid_name(&name) {
+        return Err(String::from(
+            "ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long",
+        ));
+    }
+    Ok(name)
+}
+
+/// The libraries `FUNCTION LOAD` added, saved to `file` after every change and loaded
+/// back from it when the server starts.
+pub(crate) struct Libraries {
+    inner: Mutex<BTreeMap<String, Arc<Library>>>,
+    file: Option<PathBuf>,
+}
+
+impl Libraries {
+    pub(crate) fn open(file: Option<PathBuf>) -> Self {
+        let libraries = Self {
+            inner: Mutex::new(BTreeMap::new()),
+            file,
+        };
+        let codes = match libraries.file.as_ref().map(fs::read_to_string) {
+            Some(Ok(content)) => decode(&content),
+            Some(Err(e)) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Some(Err(e)) => {
+                error!("functions not loaded: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        let mut engine = Engine::new();
+        for code in codes {
+            match engine.load_library(Arc::new(code)) {
+                Ok(library) => {
+                    libraries
+                        .inner
+                        .lock()
+                        .insert(library.name.clone(), Arc::new(library));
+                }
+                Err(e) => error!("function library not loaded: {}", e),
+            }
+        }
+        libraries
+    }
+
+    /// Adds `library`, replying its name, unless it or one of its functions is there
+    /// already and `replace` isn't set.
+    pub(crate) fn insert(&self, library: Library, replace: bool) -> Result<String, String> {
+        let mut inner = self.inner.lock();
+        if inner.contains_key(&library.name) && !replace {
+            return Err(format!("ERR Library '{}' already exists", library.name));
+        }
+        for other in inner.values().filter(|other| other.name != library.name) {
+            for function in library.functions.iter() {
+                if other.function(&function.name).is_some() {
+                    return Err(format!("ERR Function {} already exists", function.name));
+                }
+            }
+        }
+        let name = library.name.clone();
+        inner.insert(name.clone(), Arc::new(library));
+        self.save(&inner);
+        Ok(name)
+    }
+
+    /// `false` when there's no such library.
+    pub(crate) fn delete(&self, name: &str) -> bool {
+        let mut inner = self.inner.lock();
+        let deleted = inner.remove(name).is_some();
+        if deleted {
+            self.save(&inner);
+        }
+        deleted
+    }
+
+    /// The libraries whose name matches `pattern`, all of them without one.
+    pub(crate) fn list(&self, pattern: Option<&str>) -> Vec<Arc<Library>> {
+        self.inner
+            .lock()
+            .values()
+            .filter(|library| {
+                pattern
+                    .is_none_or(|pattern| glob_match(pattern.as_bytes(), library.name.as_bytes()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The library registering the function `name`.
+    pub(crate) fn find(&self, name: &str) -> Option<Arc<Library>> {
+        self.inner
+            .lock()
+            .values()
+            .find(|library| library.function(name).is_some())
+            .cloned()
+    }
+
+    fn save(&self, inner: &BTreeMap<String, Arc<Library>>) {
+        if let Some(file) = self.file.as_ref() {
+            if let Err(e) = write_file(file, inner) {
+                error!("functions not saved: {}", e);
+            }
+        }
+    }
+}
+
+impl Library {
+    pub(crate) fn function(&self, name: &str) -> Option<&Registered> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+/// Writes the code of every library, each after its length, through a temporary file
+/// so a crash leaves the previous one whole.
+fn write_file(file: &PathBuf, inner: &BTreeMap<String, Arc<Library>>) -> IoResult<()> {
+    let mut content = String::new();
+    for library in inner.values() {
+        content.push_str(&library.code.len().to_string());
+        content.push('\n');
+        content.push_str(&library.code);
+        content.push('\n');
+    }
+    let temporary = file.with_extension("tmp");
+    fs::write(&temporary, content)?;
+    fs::rename(temporary, file)
+}
+
+fn decode(mut content: &str) -> Vec<String> {
+    let mut codes = Vec::new();
+    while let Some((len, rest)) = content.split_once('\n') {
+        let len = match len.parse::<usize>() {
+            Ok(len) if rest.is_char_boundary(len.min(rest.len())) && len < rest.len() => len,
+            _ => {
+                error!("functions file is corrupted");
+                break;
+            }
+        };
+        codes.push(rest[..len].to_string());
+        content = &rest[len + 1..];
+    }
+    codes
+}
+
+#[test]
+fn test_library_name() {
+    assert_eq!(
+        library_name("#!lua name=mylib\nreturn"),
+        Ok(String::from("mylib"))
+    );
+    assert_eq!(
+        library_name("return"),
+        Err(String::from("ERR Missing library metadata"))
+    );
+    assert_eq!(
+        library_name("#!js name=mylib"),
+        Err(String::from("ERR Engine 'js' not found"))
+    );
+    assert_eq!(
+        decode("3\nabc\n2\nde\n"),
+        vec![String::from("abc"), String::from("de")]
+    );
+}
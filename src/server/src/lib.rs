@@ -1,35 +1,74 @@
 mod cmd;
+mod config;
+mod functions;
 mod parse;
+mod pubsub;
 mod reply;
+mod scripting;
 mod service;
+#[cfg(test)]
+mod testing;
+mod transaction;
 
+pub use config::{Config, ConfigError};
+pub use database::Allocator;
 pub(crate) use service::Service;
 
-use database::Database;
+use database::{mark_startup, Databases};
+use functions::Libraries;
+use pubsub::PubSub;
+use scripting::Scripts;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::spawn;
 use tracing::info;
 
 pub struct Server {
     addr: SocketAddr,
+    config: Config,
 }
 
 impl Server {
-    pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+    pub fn new(addr: SocketAddr, config: Config) -> Result<Self, ConfigError> {
+        config.validate()?;
+        Ok(Self { addr, config })
     }
 
     pub async fn run(self) {
-        let db = Database::default();
         info!("listen on {}", self.addr);
         let listen = TcpListener::bind(self.addr).await.unwrap();
+        self.serve(listen).await
+    }
+
+    /// Serves the clients connecting to `listen`.
+    pub(crate) async fn serve(self, listen: TcpListener) {
+        let config = Arc::new(self.config);
+        let scripts = Arc::new(Scripts::default());
+        let libraries = Arc::new(Libraries::open(config.functions_file.clone()));
+        let pubsub = Arc::new(PubSub::new(config.pubsub_queue_len));
+        let events = pubsub.clone();
+        let databases = Databases::with_events(
+            config.databases,
+            config.notify_keyspace_events,
+            Box::new(move |channel, message| {
+                events.publish(channel, message);
+            }),
+        );
+        mark_startup();
 
         loop {
             let (stream, addr) = listen.accept().await.unwrap();
             info!("client {}", addr);
-            let service = Service::new(stream);
-            spawn(service.run(db.clone()));
+            let service = Service::new(
+                stream,
+                databases.clone(),
+                config.clone(),
+                scripts.clone(),
+                libraries.clone(),
+                pubsub.clone(),
+            );
+            spawn(service.run());
         }
     }
 }
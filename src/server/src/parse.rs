@@ -39,15 +39,11 @@ pub(super) fn parse_bulk(buf: &str) -> Result<(&str, Option<&str>), Error> {
         return Ok((buf, None));
     }
 
-    let (content, buf) = buf
-        .split_once("\r\n")
-        .ok_or(Error::Protocol(String::from("bulk sytanx error")))?;
-
+    // by its length, the content may hold line breaks too
     let size = size as usize;
-    if size == content.len() {
-        Ok((buf, Some(content)))
-    } else {
-        Err(Error::Protocol(String::from("bulk len error")))
+    match (buf.get(..size), buf.get(size..)) {
+        (Some(content), Some(buf)) if buf.starts_with("\r\n") => Ok((&buf[2..], Some(content))),
+        _ => Err(Error::Protocol(String::from("bulk len error"))),
     }
 }
 
@@ -71,3 +67,11 @@ fn test_parse_bulk_2() {
     let (_, Parse) = parse_bulk(&target).unwrap();
     assert_eq!(Parse, None);
 }
+
+#[test]
+fn test_parse_bulk_3() {
+    let target = "$7\r\nfoo\r\nba\r\n$1\r\nx\r\n";
+    let (rest, Parse) = parse_bulk(&target).unwrap();
+    assert_eq!(Parse, Some("foo\r\nba"));
+    assert_eq!(rest, "$1\r\nx\r\n");
+}
@@ -0,0 +1,460 @@
+use crate::reply::{Output, Protocol, Replies, Reply};
+use crate::transaction::now_or_never;
+use database::{glob_match, Invalidation};
+use parking_lot::Mutex;
+use std::borrow::Borrow;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{self, Receiver, Sender, WeakSender};
+
+/// A message published to a channel.
+pub(crate) struct Message {
+    /// The pattern the subscription matched the channel with.
+    pub(crate) pattern: Option<Arc<String>>,
+    pub(crate) channel: Arc<String>,
+    pub(crate) payload: Arc<String>,
+}
+
+impl Message {
+    /// The `message` or `pmessage` push a subscriber receives.
+    pub(crate) fn write(&self, out: &mut Replies) {
+        match self.pattern.as_ref() {
+            Some(pattern) => write_push(
+                vec![
+                    Reply::from("pmessage"),
+                    Reply::from(pattern.clone()),
+                    Reply::from(self.channel.clone()),
+                    Reply::from(self.payload.clone()),
+                ],
+                out,
+            ),
+            None => write_push(
+                vec![
+                    Reply::from("message"),
+                    Reply::from(self.channel.clone()),
+                    Reply::from(self.payload.clone()),
+                ],
+                out,
+            ),
+        }
+    }
+}
+
+/// An invalidation as the `invalidate` push a RESP3 client receives, or as the message
+/// of `__redis__:invalidate` for RESP2, its keys in an array or nil for all of them.
+/// Only written to a RESP2 client subscribed to something, as no other would expect it.
+pub(crate) fn write_invalidation(invalidation: &Invalidation, out: &mut Replies) {
+    let keys = match invalidation {
+        Invalidation::Key(key) => Reply::Array(vec![Reply::from(key.as_str())]),
+        Invalidation::All => Reply::Null,
+        Invalidation::RedirectBroken(redirect) => {
+            if out.protocol() == Protocol::Resp3 {
+                write_push(
+                    vec![Reply::from("tracking-redir-broken"), Reply::from(*redirect)],
+                    out,
+                );
+            }
+            return;
+        }
+    };
+    match out.protocol() {
+        Protocol::Resp2 => write_array(
+            vec![
+                Reply::from("message"),
+                Reply::from("__redis__:invalidate"),
+                keys,
+            ],
+            out,
+        ),
+        Protocol::Resp3 => write_push(vec![Reply::from("invalidate"), keys], out),
+    }
+}
+
+/// Writes `replies` as one array.
+pub(crate) fn write_array(replies: Vec<Reply>, out: &mut Replies) {
+    write_buffered(Reply::Array(replies), out);
+}
+
+/// Writes `replies` as one push.
+pub(crate) fn write_push(replies: Vec<Reply>, out: &mut Replies) {
+    write_buffered(Reply::Push(replies), out);
+}
+
+fn write_buffered(reply: Reply, out: &mut Replies) {
+    now_or_never(reply.write(out))
+        .expect("replies are buffered")
+        .expect("writing to memory never fails");
+}
+
+/// A channel or pattern name as the server keeps it, looked up by `&str`.
+#[derive(PartialEq, Eq, Hash)]
+struct Channel(Arc<String>);
+
+impl Borrow<str> for Channel {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The queues of the clients subscribed to a channel or a pattern, by client.
+type Subscribers = HashMap<u64, Sender<Message>>;
+
+/// Bytes a pattern starts with before any wildcard, which a channel must start with
+/// too before the whole pattern is matched against it.
+fn literal_prefix(pattern: &str) -> usize {
+    pattern
+        .bytes()
+        .position(|byte| matches!(byte, b'*' | b'?' | b'[' | b'\\'))
+        .unwrap_or(pattern.len())
+}
+
+#[derive(Default)]
+struct Registry {
+    channels: HashMap<Channel, Subscribers>,
+    /// With the length of their literal prefix.
+    patterns: HashMap<Channel, (usize, Subscribers)>,
+}
+
+impl Registry {
+    fn subscribers(&mut self, pattern: bool, name: &str) -> Option<&mut Subscribers> {
+        match pattern {
+            true => self
+                .patterns
+                .get_mut(name)
+                .map(|(_, subscribers)| subscribers),
+            false => self.channels.get_mut(name),
+        }
+    }
+
+    fn remove(&mut self, pattern: bool, name: &str) {
+        match pattern {
+            true => drop(self.patterns.remove(name)),
+            false => drop(self.channels.remove(name)),
+        }
+    }
+}
+
+/// The channels and patterns of the server and the clients subscribed to them.
+pub(crate) struct PubSub {
+    inner: Mutex<Registry>,
+    next_id: AtomicU64,
+    /// Messages a client may leave waiting before it is dropped as too slow.
+    queue_len: usize,
+}
+
+impl PubSub {
+    pub(crate) fn new(queue_len: usize) -> Self {
+        Self {
+            inner: Mutex::new(Registry::default()),
+            next_id: AtomicU64::new(1),
+            queue_len,
+        }
+    }
+
+    /// Queues `payload` for every subscriber of `channel` and of each pattern matching
+    /// it, replying how many messages were queued. A subscriber whose queue is full is
+    /// unsubscribed from everything, which closes its connection once it read what was
+    /// queued.
+    pub(crate) fn publish(&self, channel: String, payload: String) -> usize {
+        let channel = Arc::new(channel);
+        let payload = Arc::new(payload);
+        let mut inner = self.inner.lock();
+        let mut slow = Vec::new();
+        let mut received = 0;
+        let mut deliver = |pattern: Option<&Arc<String>>, subscribers: &Subscribers| {
+            for (id, sender) in subscribers.iter() {
+                let message = Message {
+                    pattern: pattern.cloned(),
+                    channel: channel.clone(),
+                    payload: payload.clone(),
+                };
+                match sender.try_send(message) {
+                    Ok(()) => received += 1,
+                    Err(TrySendError::Full(_)) => slow.push(*id),
+                    Err(TrySendError::Closed(_)) => {}
+                }
+            }
+        };
+        if let Some(subscribers) = inner.channels.get(channel.as_str()) {
+            deliver(None, subscribers);
+        }
+        for (Channel(pattern), (prefix, subscribers)) in inner.patterns.iter() {
+            if channel
+                .as_bytes()
+                .starts_with(&pattern.as_bytes()[..*prefix])
+                && glob_match(pattern.as_bytes(), channel.as_bytes())
+            {
+                deliver(Some(pattern), subscribers);
+            }
+        }
+        if !slow.is_empty() {
+            let evict = |subscribers: &mut Subscribers| {
+                subscribers.retain(|id, _| !slow.contains(id));
+                !subscribers.is_empty()
+            };
+            inner.channels.retain(|_, subscribers| evict(subscribers));
+            inner
+                .patterns
+                .retain(|_, (_, subscribers)| evict(subscribers));
+        }
+        received
+    }
+
+    /// Channels with subscribers, those matching `pattern` when given.
+    pub(crate) fn channels(&self, pattern: Option<&str>) -> Vec<Arc<String>> {
+        self.inner
+            .lock()
+            .channels
+            .keys()
+            .filter(|channel| {
+                pattern.is_none_or(|pattern| glob_match(pattern.as_bytes(), channel.0.as_bytes()))
+            })
+            .map(|channel| channel.0.clone())
+            .collect()
+    }
+
+    pub(crate) fn numsub(&self, channel: &str) -> usize {
+        self.inner
+            .lock()
+            .channels
+            .get(channel)
+            .map_or(0, |subscribers| subscribers.len())
+    }
+
+    /// Patterns with subscribers.
+    pub(crate) fn patterns(&self) -> Vec<Arc<String>> {
+        self.inner
+            .lock()
+            .patterns
+            .keys()
+            .map(|pattern| pattern.0.clone())
+            .collect()
+    }
+
+    pub(crate) fn numpat(&self) -> usize {
+        self.inner.lock().patterns.len()
+    }
+}
+
+/// The subscriptions of a connection, dropped with it.
+pub(crate) struct Subscriber {
+    pubsub: Arc<PubSub>,
+    id: u64,
+    channels: BTreeSet<Arc<String>>,
+    patterns: BTreeSet<Arc<String>>,
+    /// Only the entries of `pubsub` keep the queue open, so it closes once they're gone.
+    sender: WeakSender<Message>,
+    receiver: Option<Receiver<Message>>,
+}
+
+impl Subscriber {
+    pub(crate) fn new(pubsub: Arc<PubSub>) -> Self {
+        let id = pubsub.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, _) = mpsc::channel(1);
+        Self {
+            pubsub,
+            id,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            sender: sender.downgrade(),
+            receiver: None,
+        }
+    }
+
+    /// Unique among the connections, also the one `CLIENT ID` replies.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// How many channels and patterns the connection is subscribed to.
+    pub(crate) fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    pub(crate) fn channels(&self) -> Vec<Arc<String>> {
+        self.channels.iter().cloned().collect()
+    }
+
+    pub(crate) fn patterns(&self) -> Vec<Arc<String>> {
+        self.patterns.iter().cloned().collect()
+    }
+
+    /// Subscribes to `channel`, replying the count of subscriptions. Messages still
+    /// queued from before the connection last left everything go to `out` first.
+    pub(crate) fn subscribe(&mut self, channel: String, out: &mut Replies) -> usize {
+        self.join(false, channel, out)
+    }
+
+    pub(crate) fn psubscribe(&mut self, pattern: String, out: &mut Replies) -> usize {
+        self.join(true, pattern, out)
+    }
+
+    /// Unsubscribes from `channel`, replying the count of subscriptions left.
+    pub(crate) fn unsubscribe(&mut self, channel: &Arc<String>) -> usize {
+        self.leave(false, channel)
+    }
+
+    pub(crate) fn punsubscribe(&mut self, pattern: &Arc<String>) -> usize {
+        self.leave(true, pattern)
+    }
+
+    fn join(&mut self, pattern: bool, name: String, out: &mut Replies) -> usize {
+        let sender = match self.sender.upgrade() {
+            Some(sender) => sender,
+            // dropped as too slow, the connection closes once its queue is read
+            None if self.count() > 0 => return self.count(),
+            None => {
+                if let Some(receiver) = self.receiver.as_mut() {
+                    while let Ok(message) = receiver.try_recv() {
+                        message.write(out);
+                    }
+                }
+                let (sender, receiver) = mpsc::channel(self.pubsub.queue_len);
+                self.sender = sender.downgrade();
+                self.receiver = Some(receiver);
+                sender
+            }
+        };
+        let name = Arc::new(name);
+        let mut inner = self.pubsub.inner.lock();
+        if pattern {
+            inner
+                .patterns
+                .entry(Channel(name.clone()))
+                .or_insert_with(|| (literal_prefix(&name), Subscribers::new()))
+                .1
+                .insert(self.id, sender);
+            self.patterns.insert(name);
+        } else {
+            inner
+                .channels
+                .entry(Channel(name.clone()))
+                .or_default()
+                .insert(self.id, sender);
+            self.channels.insert(name);
+        }
+        drop(inner);
+        self.count()
+    }
+
+    fn leave(&mut self, pattern: bool, name: &Arc<String>) -> usize {
+        let removed = match pattern {
+            true => self.patterns.remove(name),
+            false => self.channels.remove(name),
+        };
+        if removed {
+            let mut inner = self.pubsub.inner.lock();
+            if let Some(subscribers) = inner.subscribers(pattern, name) {
+                subscribers.remove(&self.id);
+                if subscribers.is_empty() {
+                    inner.remove(pattern, name);
+                }
+            }
+        }
+        self.count()
+    }
+
+    /// The queue of messages, `None` before the first subscription.
+    pub(crate) fn receiver(&mut self) -> Option<&mut Receiver<Message>> {
+        self.receiver.as_mut()
+    }
+
+    /// Called once the queue closed, `true` when the connection was dropped as too slow
+    /// rather than having left everything.
+    pub(crate) fn closed(&mut self) -> bool {
+        self.receiver = None;
+        self.count() > 0
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        for channel in self.channels() {
+            self.unsubscribe(&channel);
+        }
+        for pattern in self.patterns() {
+            self.punsubscribe(&pattern);
+        }
+    }
+}
+
+#[test]
+fn test_publish() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut first = Client::connect(addr).await;
+        let mut second = Client::connect(addr).await;
+        let mut pattern = Client::connect(addr).await;
+        first.call(&["SUBSCRIBE", "news"]).await;
+        second.call(&["SUBSCRIBE", "news", "other"]).await;
+        second.read().await;
+        pattern.call(&["PSUBSCRIBE", "n*", "*s"]).await;
+        pattern.read().await;
+
+        assert_eq!(client.call(&["PUBLISH", "news", "hi"]).await, ":4\r\n");
+        for subscriber in [&mut first, &mut second] {
+            assert_eq!(
+                subscriber.read().await,
+                "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n"
+            );
+        }
+        // once for each pattern matching, in no given order
+        let mut messages = [pattern.read().await, pattern.read().await];
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "*4\r\n$8\r\npmessage\r\n$2\r\n*s\r\n$4\r\nnews\r\n$2\r\nhi\r\n",
+                "*4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$2\r\nhi\r\n",
+            ]
+        );
+        assert_eq!(client.call(&["PUBSUB", "NUMPAT"]).await, ":2\r\n");
+        assert_eq!(
+            client
+                .call(&["PUBSUB", "NUMSUB", "news", "other", "none"])
+                .await,
+            "*6\r\n$4\r\nnews\r\n:2\r\n$5\r\nother\r\n:1\r\n$4\r\nnone\r\n:0\r\n"
+        );
+        assert_eq!(client.call(&["PUBLISH", "other", "hi"]).await, ":1\r\n");
+        assert_eq!(client.call(&["PUBLISH", "void", "hi"]).await, ":0\r\n");
+        assert_eq!(first.try_read().await, None);
+    });
+}
+
+#[test]
+fn test_slow_subscriber() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+
+    let config = Config {
+        pubsub_queue_len: 1,
+        ..Config::default()
+    };
+    serve(config, |addr| async move {
+        let mut client = Client::connect(addr).await;
+        let mut subscriber = Client::connect(addr).await;
+        subscriber.call(&["SUBSCRIBE", "news"]).await;
+
+        // sent at once, the subscriber can't take the first message before the second
+        for payload in ["1", "2", "3"] {
+            client.send(&["PUBLISH", "news", payload]).await;
+        }
+        assert_eq!(client.read().await, ":1\r\n");
+        assert_eq!(client.read().await, ":0\r\n");
+        assert_eq!(client.read().await, ":0\r\n");
+        assert_eq!(
+            subscriber.read().await,
+            "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$1\r\n1\r\n"
+        );
+        assert!(subscriber.is_closed().await);
+        assert_eq!(
+            client.call(&["PUBSUB", "NUMSUB", "news"]).await,
+            "*2\r\n$4\r\nnews\r\n:0\r\n"
+        );
+    });
+}
@@ -1,10 +1,14 @@
 use crate::service::Error as MyError;
+use std::borrow::Cow;
 use std::convert::{From, Into};
 use std::error::Error;
-use std::io::{IoSlice, Result as IoResult};
+use std::io::{ErrorKind, IoSlice, Result as IoResult};
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
 use std::string::ToString;
 use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 const SIMPLE_STRINGS: &[u8; 1] = b"+";
 const ERRORS: &[u8; 1] = b"-";
@@ -12,6 +16,14 @@ const NUMBER: &[u8; 1] = b":";
 const LINE: &[u8; 2] = b"\r\n";
 const BULK_STRINGS: &[u8; 1] = b"$";
 const ARRAY: &[u8; 1] = b"*";
+const NULL: &[u8; 1] = b"_";
+const DOUBLE: &[u8; 1] = b",";
+const BOOLEAN: &[u8; 1] = b"#";
+const BIG_NUMBER: &[u8; 1] = b"(";
+const VERBATIM: &[u8; 1] = b"=";
+const MAP: &[u8; 1] = b"%";
+const SET: &[u8; 1] = b"~";
+const PUSH: &[u8; 1] = b">";
 
 // pub(super) async fn reply_simple<A>(write_stream: &mut A, content: &[u8]) -> IoResult<()>
 // where
@@ -79,6 +91,20 @@ pub(super) enum Reply {
     Number(String),
     Error(String),
     ArcString(Arc<String>),
+    /// Written `+nil` to RESP2 clients, as it always was.
+    Null,
+    Double(f64),
+    Boolean(bool),
+    BigNumber(String),
+    /// Text with the three letters of its format, `txt` or `mkd`.
+    Verbatim(String, String),
+    Array(Vec<Reply>),
+    /// Flattened into an array for RESP2, as are sets and pushes.
+    Map(Vec<(Reply, Reply)>),
+    Set(Vec<Reply>),
+    Push(Vec<Reply>),
+    /// Replies a command already wrote out, as those `EXEC` gathers.
+    Written(Vec<u8>),
 }
 
 impl From<String> for Reply {
@@ -153,12 +179,12 @@ impl From<isize> for Reply {
 }
 impl From<f32> for Reply {
     fn from(inner: f32) -> Self {
-        Reply::Bulk(inner.to_string())
+        Reply::Double(inner as f64)
     }
 }
 impl From<f64> for Reply {
     fn from(inner: f64) -> Self {
-        Reply::Bulk(inner.to_string())
+        Reply::Double(inner)
     }
 }
 impl From<MyError> for Reply {
@@ -173,7 +199,7 @@ where
     fn from(inner: Option<I>) -> Self {
         match inner {
             Some(inner) => inner.into(),
-            None => Reply::Simple(String::from("nil")),
+            None => Reply::Null,
         }
     }
 }
@@ -194,77 +220,261 @@ impl From<Arc<String>> for Reply {
         Reply::ArcString(inner)
     }
 }
+impl<I> From<Vec<I>> for Reply
+where
+    I: Into<Reply>,
+{
+    fn from(inner: Vec<I>) -> Self {
+        Reply::Array(inner.into_iter().map(Into::into).collect())
+    }
+}
 
 impl Reply {
-    #[inline]
+    /// Writes the reply, nested ones and all, with as few vectored writes as it takes.
     pub(super) async fn write<A>(&self, write_stream: &mut A) -> IoResult<()>
     where
-        A: AsyncWriteExt + Unpin,
+        A: Output,
     {
+        let mut pieces = Vec::new();
+        self.pieces(write_stream.protocol() == Protocol::Resp3, &mut pieces);
+        let mut slices = pieces
+            .iter()
+            .map(|piece| IoSlice::new(piece))
+            .collect::<Vec<IoSlice>>();
+        slices_write(&mut slices, write_stream).await
+    }
+
+    /// Name value pairs as a map.
+    pub(super) fn fields<const N: usize>(fields: [(&str, Reply); N]) -> Self {
+        Self::Map(
+            fields
+                .into_iter()
+                .map(|(name, value)| (Reply::from(name), value))
+                .collect(),
+        )
+    }
+
+    /// The bytes of the reply in order, borrowed from it where they can be.
+    fn pieces<'a>(&'a self, resp3: bool, pieces: &mut Vec<Cow<'a, [u8]>>) {
         match self {
-            Self::Simple(inner) => {
-                let buffer = [
-                    IoSlice::new(SIMPLE_STRINGS),
-                    IoSlice::new(inner.as_bytes()),
-                    IoSlice::new(LINE),
-                ];
-                write_stream.write_vectored(&buffer).await?;
+            Self::Simple(inner) => line(SIMPLE_STRINGS, inner.as_bytes(), pieces),
+            Self::Number(inner) => line(NUMBER, inner.as_bytes(), pieces),
+            Self::Error(inner) => line(ERRORS, inner.as_bytes(), pieces),
+            Self::Bulk(inner) => bulk(BULK_STRINGS, [Cow::Borrowed(inner.as_bytes())], pieces),
+            Self::ArcString(inner) => bulk(BULK_STRINGS, [Cow::Borrowed(inner.as_bytes())], pieces),
+            Self::Null if resp3 => line(NULL, b"".as_slice(), pieces),
+            Self::Null => line(SIMPLE_STRINGS, b"nil".as_slice(), pieces),
+            Self::Double(inner) if resp3 => {
+                let inner = match inner.is_nan() {
+                    true => String::from("nan"),
+                    false => inner.to_string(),
+                };
+                line(DOUBLE, inner.into_bytes(), pieces)
+            }
+            Self::Double(inner) => bulk(
+                BULK_STRINGS,
+                [Cow::Owned(inner.to_string().into_bytes())],
+                pieces,
+            ),
+            Self::Boolean(inner) if resp3 => {
+                line(BOOLEAN, if *inner { b"t" } else { b"f" }.as_slice(), pieces)
             }
-            Self::Number(inner) => {
-                let buffer = [
-                    IoSlice::new(NUMBER),
-                    IoSlice::new(inner.as_bytes()),
-                    IoSlice::new(LINE),
-                ];
-                write_stream.write_vectored(&buffer).await?;
+            Self::Boolean(inner) => {
+                line(NUMBER, if *inner { b"1" } else { b"0" }.as_slice(), pieces)
             }
-            Self::Error(inner) => {
-                let buffer = [
-                    IoSlice::new(ERRORS),
-                    IoSlice::new(inner.as_bytes()),
-                    IoSlice::new(LINE),
-                ];
-                write_stream.write_vectored(&buffer).await?;
+            Self::BigNumber(inner) if resp3 => line(BIG_NUMBER, inner.as_bytes(), pieces),
+            Self::BigNumber(inner) => bulk(BULK_STRINGS, [Cow::Borrowed(inner.as_bytes())], pieces),
+            Self::Verbatim(format, inner) if resp3 => bulk(
+                VERBATIM,
+                [
+                    Cow::Borrowed(format.as_bytes()),
+                    Cow::Borrowed(b":"),
+                    Cow::Borrowed(inner.as_bytes()),
+                ],
+                pieces,
+            ),
+            Self::Verbatim(_, inner) => {
+                bulk(BULK_STRINGS, [Cow::Borrowed(inner.as_bytes())], pieces)
             }
-            Self::Bulk(inner) => {
-                let len = inner.len();
-                let len_str = len.to_string();
-                let buffer = [
-                    IoSlice::new(BULK_STRINGS),
-                    IoSlice::new(len_str.as_bytes()),
-                    IoSlice::new(LINE),
-                    IoSlice::new(inner.as_bytes()),
-                    IoSlice::new(LINE),
-                ];
-                write_stream.write_vectored(&buffer).await?;
+            Self::Array(items) => {
+                line(ARRAY, items.len().to_string().into_bytes(), pieces);
+                for item in items {
+                    item.pieces(resp3, pieces);
+                }
             }
-            Self::ArcString(inner) => {
-                let len = inner.len();
-                let len_str = len.to_string();
-                let buffer = [
-                    IoSlice::new(BULK_STRINGS),
-                    IoSlice::new(len_str.as_bytes()),
-                    IoSlice::new(LINE),
-                    IoSlice::new(inner.as_bytes()),
-                    IoSlice::new(LINE),
-                ];
-                write_stream.write_vectored(&buffer).await?;
+            Self::Map(pairs) => {
+                match resp3 {
+                    true => line(MAP, pairs.len().to_string().into_bytes(), pieces),
+                    false => line(ARRAY, (pairs.len() * 2).to_string().into_bytes(), pieces),
+                }
+                for (name, value) in pairs {
+                    name.pieces(resp3, pieces);
+                    value.pieces(resp3, pieces);
+                }
             }
+            Self::Set(items) | Self::Push(items) => {
+                let header = match self {
+                    _ if !resp3 => ARRAY,
+                    Self::Set(_) => SET,
+                    _ => PUSH,
+                };
+                line(header, items.len().to_string().into_bytes(), pieces);
+                for item in items {
+                    item.pieces(resp3, pieces);
+                }
+            }
+            Self::Written(inner) => pieces.push(Cow::Borrowed(inner)),
         }
-        Ok(())
     }
+}
 
-    pub(super) async fn array_len_write<A>(len: usize, write_stream: &mut A) -> IoResult<()>
-    where
-        A: AsyncWriteExt + Unpin,
-    {
-        let len = len.to_string();
-        let buffer = [
-            IoSlice::new(ARRAY),
-            IoSlice::new(len.as_bytes()),
-            IoSlice::new(LINE),
-        ];
-        write_stream.write_vectored(&buffer).await?;
-        Ok(())
+async fn slices_write<A>(mut slices: &mut [IoSlice<'_>], write_stream: &mut A) -> IoResult<()>
+where
+    A: Output,
+{
+    while !slices.is_empty() {
+        match write_stream.write_vectored(slices).await? {
+            0 => return Err(ErrorKind::WriteZero.into()),
+            written => IoSlice::advance_slices(&mut slices, written),
+        }
     }
+    Ok(())
+}
+
+fn line<'a>(
+    header: &'static [u8],
+    content: impl Into<Cow<'a, [u8]>>,
+    pieces: &mut Vec<Cow<'a, [u8]>>,
+) {
+    pieces.push(Cow::Borrowed(header));
+    pieces.push(content.into());
+    pieces.push(Cow::Borrowed(LINE));
+}
+
+/// `parts` as one string of their length.
+fn bulk<'a, const N: usize>(
+    header: &'static [u8],
+    parts: [Cow<'a, [u8]>; N],
+    pieces: &mut Vec<Cow<'a, [u8]>>,
+) {
+    let len = parts.iter().map(|part| part.len()).sum::<usize>();
+    pieces.push(Cow::Borrowed(header));
+    pieces.push(Cow::Owned(len.to_string().into_bytes()));
+    pieces.push(Cow::Borrowed(LINE));
+    pieces.extend(parts);
+    pieces.push(Cow::Borrowed(LINE));
+}
+
+/// The protocol a connection is replied in, picked with `HELLO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+/// Where replies are written, in the protocol of whoever reads them.
+pub(crate) trait Output: AsyncWrite + Unpin {
+    fn protocol(&self) -> Protocol;
+}
+
+/// Replies read back by the server itself, as those of the commands scripts call.
+impl Output for Vec<u8> {
+    fn protocol(&self) -> Protocol {
+        Protocol::Resp2
+    }
+}
+
+/// The replies buffered for a connection, in the protocol it negotiated.
+#[derive(Default)]
+pub(crate) struct Replies {
+    buffer: Vec<u8>,
+    pub(crate) protocol: Protocol,
+}
+
+impl Replies {
+    pub(crate) fn new(protocol: Protocol) -> Self {
+        Self {
+            buffer: Vec::new(),
+            protocol,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+impl Output for Replies {
+    fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+}
+
+impl Deref for Replies {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for Replies {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl AsyncWrite for Replies {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IoResult<usize>> {
+        Pin::new(&mut self.buffer).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<IoResult<usize>> {
+        Pin::new(&mut self.buffer).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.buffer.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        Pin::new(&mut self.buffer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        Pin::new(&mut self.buffer).poll_shutdown(cx)
+    }
+}
+
+#[test]
+fn test_nested_write() {
+    let reply = Reply::Array(vec![
+        Reply::from("a"),
+        Reply::Map(vec![(Reply::from("b"), Reply::Set(vec![Reply::from(1)]))]),
+        Reply::Null,
+    ]);
+    let write = |protocol| {
+        let mut out = Replies::new(protocol);
+        crate::transaction::now_or_never(reply.write(&mut out))
+            .unwrap()
+            .unwrap();
+        String::from_utf8(out.into_inner()).unwrap()
+    };
+    assert_eq!(
+        write(Protocol::Resp2),
+        "*3\r\n$1\r\na\r\n*2\r\n$1\r\nb\r\n*1\r\n:1\r\n+nil\r\n"
+    );
+    assert_eq!(
+        write(Protocol::Resp3),
+        "*3\r\n$1\r\na\r\n%1\r\n$1\r\nb\r\n~1\r\n:1\r\n_\r\n"
+    );
 }
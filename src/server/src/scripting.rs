@@ -0,0 +1,518 @@
+use crate::functions::{is_valid_name, library_name, Library, Registered};
+use crate::reply::{Protocol, Replies, Reply};
+use crate::transaction::now_or_never;
+use mlua::{
+    Function, Lua, LuaOptions, MultiValue, RegistryKey, Result as LuaResult, StdLib, Table,
+    Value as LuaValue, Variadic,
+};
+use parking_lot::Mutex;
+use sha1::{Digest, Sha1};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// `redis.call` raises the error replies `redis.pcall` returns.
+const PRELUDE: &str = r#"
+dofile = nil
+loadfile = nil
+redis = {}
+function redis.status_reply(status) return {ok = status} end
+function redis.error_reply(err) return {err = err} end
+function redis.call(...)
+    local reply = redis.pcall(...)
+    if type(reply) == "table" and reply.err then error(reply, 0) end
+    return reply
+end
+"#;
+
+pub(crate) fn sha1_hex(data: &[u8]) -> String {
+    Sha1::digest(data)
+        .iter()
+        .fold(String::with_capacity(40), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+/// Scripts of the server by the SHA1 of their source, for `EVALSHA`.
+#[derive(Default)]
+pub(crate) struct Scripts {
+    inner: Mutex<HashMap<String, Arc<String>>>,
+}
+
+impl Scripts {
+    /// Caches `script`, replying its SHA1.
+    pub(crate) fn load(&self, script: String) -> String {
+        let sha = sha1_hex(script.as_bytes());
+        self.inner.lock().insert(sha.clone(), Arc::new(script));
+        sha
+    }
+
+    pub(crate) fn get(&self, sha: &str) -> Option<Arc<String>> {
+        self.inner.lock().get(&sha.to_lowercase()).cloned()
+    }
+
+    pub(crate) fn exists(&self, sha: &str) -> bool {
+        self.inner.lock().contains_key(&sha.to_lowercase())
+    }
+
+    pub(crate) fn flush(&self) {
+        self.inner.lock().clear();
+    }
+}
+
+/// Functions of a library an engine ran.
+struct Loaded {
+    code: Arc<String>,
+    functions: HashMap<String, RegistryKey>,
+}
+
+/// The Lua interpreter of a connection, with the scripts it compiled and the function
+/// libraries it ran.
+pub(crate) struct Engine {
+    lua: Lua,
+    compiled: HashMap<String, RegistryKey>,
+    libraries: HashMap<String, Loaded>,
+}
+
+impl Engine {
+    pub(crate) fn new() -> Self {
+        let lua = Lua::new_with(
+            StdLib::TABLE | StdLib::STRING | StdLib::MATH,
+            LuaOptions::new(),
+        )
+        .expect("standard libraries load");
+        lua.load(PRELUDE).exec().expect("prelude runs");
+        let sha1hex = lua
+            .create_function(|_, data: mlua::String| Ok(sha1_hex(data.as_bytes())))
+            .expect("function is created");
+        lua.globals()
+            .get::<_, Table>("redis")
+            .and_then(|redis| redis.set("sha1hex", sha1hex))
+            .expect("redis table is set");
+        Self {
+            lua,
+            compiled: HashMap::new(),
+            libraries: HashMap::new(),
+        }
+    }
+
+    /// Runs `script`, known by its `sha`, writing its reply to `out`. `call` runs the
+    /// commands of `redis.call` and `redis.pcall`, replying them as RESP.
+    pub(crate) fn run<C>(
+        &mut self,
+        sha: &str,
+        script: &str,
+        keys: Vec<String>,
+        args: Vec<String>,
+        call: C,
+        out: &mut Replies,
+    ) where
+        C: FnMut(Vec<String>) -> Vec<u8>,
+    {
+        let reply = match self.compile(sha, script) {
+            Ok(()) => self.call(&self.compiled[sha], keys, args, true, call, out.protocol),
+            Err(e) => Err(Reply::Error(format!("ERR Error compiling script: {}", e))),
+        };
+        write_result(reply, out);
+    }
+
+    /// Runs the function `name` of `library` as `run` does a script, running the
+    /// library first unless this engine did already.
+    pub(crate) fn run_function<C>(
+        &mut self,
+        library: &Library,
+        name: &str,
+        keys: Vec<String>,
+        args: Vec<String>,
+        call: C,
+        out: &mut Replies,
+    ) where
+        C: FnMut(Vec<String>) -> Vec<u8>,
+    {
+        let current = self
+            .libraries
+            .get(&library.name)
+            .is_some_and(|loaded| Arc::ptr_eq(&loaded.code, &library.code));
+        let reply = match current {
+            true => Ok(()),
+            false => self.load_library(library.code.clone()).map(drop),
+        };
+        let reply = match reply.map(|()| self.libraries[&library.name].functions.get(name)) {
+            Ok(Some(function)) => self.call(function, keys, args, false, call, out.protocol),
+            Ok(None) => Err(Reply::Error(String::from("ERR Function not found"))),
+            Err(e) => Err(Reply::Error(e)),
+        };
+        write_result(reply, out);
+    }
+
+    /// Runs the code of a library, which registers its functions with
+    /// `redis.register_function`.
+    pub(crate) fn load_library(&mut self, code: Arc<String>) -> Result<Library, String> {
+        let name = library_name(&code)?;
+        // past the header, which Lua would not parse, keeping the lines numbered
+        let body = code.find('\n').map_or("", |at| &code[at..]);
+        let function = self
+            .lua
+            .load(body)
+            .set_name("@user_function")
+            .into_function()
+            .map_err(|e| format!("ERR Error compiling function: {}", e))?;
+
+        let registered = RefCell::new(Vec::<(String, RegistryKey, Vec<String>)>::new());
+        let failure = RefCell::new(None::<String>);
+        let result = self.lua.scope(|scope| {
+            let register = scope.create_function(|lua, args: Variadic<LuaValue>| {
+                let registering = register_args(args).and_then(|(name, callback, flags)| {
+                    if registered
+                        .borrow()
+                        .iter()
+                        .any(|(other, _, _)| *other == name)
+                    {
+                        return Err(String::from("ERR Function already exists in the library"));
+                    }
+                    Ok((name, callback, flags))
+                });
+                match registering {
+                    Ok((name, callback, flags)) => {
+                        let callback = lua.create_registry_value(callback)?;
+                        registered.borrow_mut().push((name, callback, flags));
+                        Ok(())
+                    }
+                    Err(e) => {
+                        failure.borrow_mut().get_or_insert(e.clone());
+                        Err(mlua::Error::RuntimeError(e))
+                    }
+                }
+            })?;
+            let redis = self.lua.globals().get::<_, Table>("redis")?;
+            redis.set("register_function", register)?;
+            let result = function.call::<_, ()>(());
+            redis.set("register_function", LuaValue::Nil)?;
+            result
+        });
+        if let Some(e) = failure.into_inner() {
+            return Err(e);
+        }
+        result.map_err(|e| format!("ERR Error registering functions: {}", e))?;
+
+        let registered = registered.into_inner();
+        if registered.is_empty() {
+            return Err(String::from("ERR No functions registered"));
+        }
+        let library = Library {
+            name: name.clone(),
+            code: code.clone(),
+            functions: registered
+                .iter()
+                .map(|(name, _, flags)| Registered {
+                    name: name.clone(),
+                    flags: flags.clone(),
+                })
+                .collect(),
+        };
+        let functions = registered
+            .into_iter()
+            .map(|(name, callback, _)| (name, callback))
+            .collect();
+        self.libraries.insert(name, Loaded { code, functions });
+        self.lua.expire_registry_values();
+        Ok(library)
+    }
+
+    fn compile(&mut self, sha: &str, script: &str) -> LuaResult<()> {
+        if !self.compiled.contains_key(sha) {
+            let function = self
+                .lua
+                .load(script)
+                .set_name("@user_script")
+                .into_function()?;
+            let key = self.lua.create_registry_value(function)?;
+            self.compiled.insert(sha.to_string(), key);
+        }
+        Ok(())
+    }
+
+    /// Calls `function`, with `KEYS` and `ARGV` set as globals for a script or passed
+    /// along as two tables to a function, replying in `protocol`.
+    fn call<C>(
+        &self,
+        function: &RegistryKey,
+        keys: Vec<String>,
+        args: Vec<String>,
+        globals: bool,
+        mut call: C,
+        protocol: Protocol,
+    ) -> Result<Vec<u8>, Reply>
+    where
+        C: FnMut(Vec<String>) -> Vec<u8>,
+    {
+        let lua = &self.lua;
+        let run = || -> LuaResult<Result<Vec<u8>, Reply>> {
+            let function = lua.registry_value::<Function>(function)?;
+            let mut params = vec![LuaValue::Function(function)];
+            let globals_table = lua.globals();
+            if globals {
+                globals_table.set("KEYS", keys)?;
+                globals_table.set("ARGV", args)?;
+            } else {
+                params.push(LuaValue::Table(lua.create_sequence_from(keys)?));
+                params.push(LuaValue::Table(lua.create_sequence_from(args)?));
+            }
+            let redis = globals_table.get::<_, Table>("redis")?;
+            let pcall = globals_table.get::<_, Function>("pcall")?;
+            lua.scope(|scope| {
+                let bridge = scope.create_function_mut(|lua, args: Variadic<LuaValue>| {
+                    let args = match command_args(args) {
+                        Ok(args) => args,
+                        Err(e) => return error_table(lua, e),
+                    };
+                    let reply = call(args);
+                    resp_to_lua(lua, &mut reply.as_slice())
+                })?;
+                redis.set("pcall", bridge)?;
+                let mut results = pcall
+                    .call::<_, MultiValue>(MultiValue::from_vec(params))?
+                    .into_iter();
+                let ok = matches!(results.next(), Some(LuaValue::Boolean(true)));
+                let value = results.next().unwrap_or(LuaValue::Nil);
+                if ok {
+                    let mut out = Replies::new(protocol);
+                    now_or_never(lua_to_reply(value, protocol).write(&mut out))
+                        .expect("replies are buffered")
+                        .expect("writing to memory never fails");
+                    return Ok(Ok(out.into_inner()));
+                }
+                let message = match value {
+                    LuaValue::Table(ref table) => table.get::<_, String>("err").ok(),
+                    _ => None,
+                };
+                Ok(Err(match message {
+                    Some(message) => Reply::Error(message),
+                    None => {
+                        Reply::Error(format!("ERR Error running script: {}", lua_error(&value)))
+                    }
+                }))
+            })
+        };
+        run().unwrap_or_else(|e| Err(Reply::Error(format!("ERR Error running script: {}", e))))
+    }
+}
+
+fn write_result(reply: Result<Vec<u8>, Reply>, out: &mut Replies) {
+    match reply {
+        Ok(reply) => out.extend(reply),
+        Err(reply) => now_or_never(reply.write(out))
+            .expect("replies are buffered")
+            .expect("writing to memory never fails"),
+    }
+}
+
+/// Flags `redis.register_function` takes.
+const FLAGS: &[&str] = &[
+    "no-writes",
+    "allow-oom",
+    "allow-stale",
+    "no-cluster",
+    "allow-cross-slot-keys",
+];
+
+/// The name, callback and flags of `redis.register_function(name, callback)` or of
+/// `redis.register_function{function_name=..., callback=..., flags={...}}`.
+fn register_args(
+    args: Variadic<LuaValue<'_>>,
+) -> Result<(String, Function<'_>, Vec<String>), String> {
+    let mut args = args.into_iter();
+    let (name, callback, flags) = match (args.next(), args.next(), args.next()) {
+        (Some(LuaValue::Table(table)), None, None) => (
+            table.get::<_, LuaValue>("function_name").ok(),
+            table.get::<_, LuaValue>("callback").ok(),
+            table.get::<_, Option<Table>>("flags").map_err(|_| {
+                String::from("ERR flags argument to redis.register_function must be a table representing function flags")
+            })?,
+        ),
+        (Some(name), Some(callback), None) => (Some(name), Some(callback), None),
+        _ => {
+            return Err(String::from(
+                "ERR wrong number of arguments to redis.register_function",
+            ))
+        }
+    };
+    let name = match name {
+        Some(LuaValue::String(name)) => name.to_string_lossy().into_owned(),
+        _ => {
+            return Err(String::from(
+                "ERR function_name argument given to redis.register_function must be a string",
+            ))
+        }
+    };
+    if !is_valid_name(&name) {
+        return Err(String::from(
+            "ERR Function names can only contain letters, numbers, or underscores(_) and must be at least one character long",
+        ));
+    }
+    let callback = match callback {
+        Some(LuaValue::Function(callback)) => callback,
+        _ => {
+            return Err(String::from(
+                "ERR callback argument given to redis.register_function must be a function",
+            ))
+        }
+    };
+    let flags = match flags {
+        Some(flags) => flags
+            .sequence_values::<String>()
+            .map(|flag| match flag {
+                Ok(flag) if FLAGS.contains(&flag.as_str()) => Ok(flag),
+                _ => Err(String::from("ERR unknown flag given")),
+            })
+            .collect::<Result<Vec<String>, String>>()?,
+        None => Vec::new(),
+    };
+    Ok((name, callback, flags))
+}
+
+/// Arguments of `redis.call`, which Lua passes as strings or numbers.
+fn command_args(args: Variadic<LuaValue>) -> Result<Vec<String>, &'static str> {
+    if args.is_empty() {
+        return Err("ERR Please specify at least one argument for this redis lib call");
+    }
+    args.iter()
+        .map(|arg| match arg {
+            LuaValue::String(string) => Ok(string.to_string_lossy().into_owned()),
+            LuaValue::Integer(integer) => Ok(integer.to_string()),
+            LuaValue::Number(number) if number.fract() == 0.0 => Ok((*number as i64).to_string()),
+            LuaValue::Number(number) => Ok(number.to_string()),
+            _ => Err("ERR Lua redis lib command arguments must be strings or integers"),
+        })
+        .collect()
+}
+
+fn error_table<'lua>(lua: &'lua Lua, err: &str) -> LuaResult<LuaValue<'lua>> {
+    let table = lua.create_table()?;
+    table.set("err", err)?;
+    Ok(LuaValue::Table(table))
+}
+
+fn lua_error(value: &LuaValue<'_>) -> String {
+    match value {
+        LuaValue::String(string) => string.to_string_lossy().into_owned(),
+        value => value.type_name().to_string(),
+    }
+}
+
+/// The next line of a RESP reply, without its `\r\n`.
+fn resp_line<'a>(input: &mut &'a [u8]) -> &'a [u8] {
+    let end = input
+        .windows(2)
+        .position(|window| window == b"\r\n")
+        .unwrap_or(input.len());
+    let line = &input[..end];
+    *input = &input[(end + 2).min(input.len())..];
+    line
+}
+
+/// Converts a reply as Redis hands replies to scripts: integers to numbers, bulk
+/// strings to strings, arrays to tables, status and error replies to tables with an
+/// `ok` or an `err` field and nil to false.
+fn resp_to_lua<'lua>(lua: &'lua Lua, input: &mut &[u8]) -> LuaResult<LuaValue<'lua>> {
+    let line = resp_line(input);
+    let (kind, rest) = match line.split_first() {
+        Some((kind, rest)) => (*kind, String::from_utf8_lossy(rest).into_owned()),
+        None => return Ok(LuaValue::Boolean(false)),
+    };
+    match kind {
+        b'+' if rest == "nil" => Ok(LuaValue::Boolean(false)),
+        b'+' => {
+            let table = lua.create_table()?;
+            table.set("ok", rest)?;
+            Ok(LuaValue::Table(table))
+        }
+        b'-' => error_table(lua, &rest),
+        b':' => Ok(LuaValue::Integer(rest.parse().unwrap_or(0))),
+        b'$' => match rest.parse::<usize>() {
+            Ok(len) => {
+                let len = len.min(input.len());
+                let string = lua.create_string(&input[..len])?;
+                *input = &input[(len + 2).min(input.len())..];
+                Ok(LuaValue::String(string))
+            }
+            Err(_) => Ok(LuaValue::Boolean(false)),
+        },
+        b'*' => match rest.parse::<usize>() {
+            Ok(len) => {
+                let table = lua.create_table()?;
+                for index in 1..=len {
+                    table.set(index, resp_to_lua(lua, input)?)?;
+                }
+                Ok(LuaValue::Table(table))
+            }
+            Err(_) => Ok(LuaValue::Boolean(false)),
+        },
+        _ => Ok(LuaValue::Boolean(false)),
+    }
+}
+
+/// Converts what a script returns back: numbers are truncated to integers, true is 1,
+/// false and nil are nil, and an array stops at its first nil. Tables with a `double`,
+/// `big_number`, `verbatim_string`, `map` or `set` field are the RESP3 types, and
+/// booleans are booleans for RESP3 clients.
+fn lua_to_reply(value: LuaValue<'_>, protocol: Protocol) -> Reply {
+    match value {
+        LuaValue::Boolean(boolean) if protocol == Protocol::Resp3 => Reply::Boolean(boolean),
+        LuaValue::Boolean(true) => Reply::from(1),
+        LuaValue::Integer(integer) => Reply::from(integer),
+        LuaValue::Number(number) => Reply::from(number as i64),
+        LuaValue::String(string) => Reply::Bulk(string.to_string_lossy().into_owned()),
+        LuaValue::Table(table) => {
+            if let Ok(err) = table.get::<_, String>("err") {
+                Reply::Error(err)
+            } else if let Ok(ok) = table.get::<_, String>("ok") {
+                Reply::Simple(ok)
+            } else if let Ok(double) = table.get::<_, f64>("double") {
+                Reply::Double(double)
+            } else if let Ok(number) = table.get::<_, String>("big_number") {
+                Reply::BigNumber(number)
+            } else if let Ok(verbatim) = table.get::<_, Table>("verbatim_string") {
+                Reply::Verbatim(
+                    verbatim.get::<_, String>("format").unwrap_or_default(),
+                    verbatim.get::<_, String>("string").unwrap_or_default(),
+                )
+            } else if let Ok(map) = table.get::<_, Table>("map") {
+                Reply::Map(
+                    map.pairs::<LuaValue, LuaValue>()
+                        .map_while(Result::ok)
+                        .map(|(key, value)| {
+                            (lua_to_reply(key, protocol), lua_to_reply(value, protocol))
+                        })
+                        .collect(),
+                )
+            } else if let Ok(set) = table.get::<_, Table>("set") {
+                Reply::Set(
+                    set.pairs::<LuaValue, LuaValue>()
+                        .map_while(Result::ok)
+                        .map(|(member, _)| lua_to_reply(member, protocol))
+                        .collect(),
+                )
+            } else {
+                Reply::Array(
+                    table
+                        .sequence_values::<LuaValue>()
+                        .map_while(Result::ok)
+                        .map(|item| lua_to_reply(item, protocol))
+                        .collect(),
+                )
+            }
+        }
+        _ => Reply::from(None::<Reply>),
+    }
+}
+
+/// A script to run with its keys and arguments, from `EVAL` or `EVALSHA`.
+pub(crate) struct Call {
+    pub(crate) sha: String,
+    pub(crate) script: Arc<String>,
+    pub(crate) keys: Vec<String>,
+    pub(crate) args: Vec<String>,
+}
@@ -1,29 +1,117 @@
 // use super::command::Command;
 use super::parse::{parse_array_len, parse_bulk};
 // use super::reply::{reply_array_size, reply_bulk, reply_integer};
-use super::reply::Reply;
+use super::reply::{Protocol, Replies, Reply};
 use crate::cmd::{
-    Apply, Builder, Delete, FieldBuilder, Get, LLen, LPop, LPush, LRange, MGet, Ping, Pong, RPop,
-    RPush, SAdd, Set, Smembers, Scard,
+    Apply, Auth, BZMPop, BZPopMax, BZPopMin, Builder, Client, CopyKey, DbSize, Delete, Discard,
+    Eval, EvalSha, Exec, Exists, Fcall, FieldBuilder, FlushAll, FlushDb, Function, FunctionCall,
+    GeoAdd, GeoDist, GeoHash, GeoPos, GeoSearch, GeoSearchStore, Get, Hello, KeyType, Keys, LLen,
+    LPop, LPush, LRange, MGet, MSet, Memory, Move, Multi, Object, PSubscribe, PUnsubscribe, Ping,
+    Pong, PubSubInfo, Publish, Quit, RPop, RPush, RandomKey, Rename, RenameNx, SAdd, SScan, Scan,
+    Scard, Script, Select, Set, Smembers, Subscribe, SwapDb, Touch, Unlink, Unsubscribe, Unwatch,
+    Watch, XAck, XAdd, XAutoClaim, XClaim, XDel, XGroup, XInfo, XLen, XPending, XRange, XRead,
+    XReadGroup, XRevRange, XTrim, ZAdd, ZCard, ZCount, ZDiff, ZDiffStore, ZIncrBy, ZInter,
+    ZInterStore, ZLexCount, ZPopMax, ZPopMin, ZRange, ZRank, ZRem, ZRemRangeByLex, ZRemRangeByRank,
+    ZRemRangeByScore, ZRevRank, ZScan, ZScore, ZUnion, ZUnionStore,
 };
+use crate::config::Config;
+use crate::functions::Libraries;
+use crate::pubsub::{write_array, write_invalidation, PubSub, Subscriber};
+use crate::scripting::{Call, Engine, Scripts};
+use crate::transaction::{block_in_place, check, command_keys, now_or_never, Transaction, Watched};
 // use db::{List, Strings};
-use database::Database;
+use database::{ClientTracking, Database, Databases, Exclusive};
 use parking_lot::RwLock;
 use std::cmp::Eq;
 use std::fmt::Write;
+use std::future::poll_fn;
 use std::hash::Hash;
 use std::io::Error as IoError;
 use std::iter::ExactSizeIterator;
+use std::mem;
 use std::num::ParseIntError;
+use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::task::Poll;
 use std::time::Duration;
 use thiserror::Error;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter, ReadHalf, WriteHalf};
+use tokio::io::{
+    split, AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, ReadHalf,
+    WriteHalf,
+};
 use tokio::net::TcpStream;
 use tokio::task::spawn_local;
 use tracing::{error, trace};
 
+/// The command changes the dataset, so read-only scripts may not call it.
+const WRITE: u8 = 1;
+/// The command is refused once `maxmemory` is reached and nothing is left to evict.
+const DENY_OOM: u8 = 1 << 1;
+
+/// Flags of the commands that have any.
+const COMMAND_FLAGS: &[(&str, u8)] = &[
+    ("SET", WRITE | DENY_OOM),
+    ("MSET", WRITE | DENY_OOM),
+    ("DEL", WRITE),
+    ("UNLINK", WRITE),
+    ("RENAME", WRITE),
+    ("RENAMENX", WRITE),
+    ("COPY", WRITE | DENY_OOM),
+    ("MOVE", WRITE),
+    ("SWAPDB", WRITE),
+    ("FLUSHDB", WRITE),
+    ("FLUSHALL", WRITE),
+    ("RPUSH", WRITE | DENY_OOM),
+    ("LPUSH", WRITE | DENY_OOM),
+    ("LPOP", WRITE),
+    ("RPOP", WRITE),
+    ("SADD", WRITE | DENY_OOM),
+    ("ZADD", WRITE | DENY_OOM),
+    ("ZREM", WRITE),
+    ("ZINCRBY", WRITE | DENY_OOM),
+    ("ZREMRANGEBYRANK", WRITE),
+    ("ZREMRANGEBYSCORE", WRITE),
+    ("ZREMRANGEBYLEX", WRITE),
+    ("ZPOPMIN", WRITE),
+    ("ZPOPMAX", WRITE),
+    ("ZUNIONSTORE", WRITE | DENY_OOM),
+    ("ZINTERSTORE", WRITE | DENY_OOM),
+    ("ZDIFFSTORE", WRITE | DENY_OOM),
+    ("BZPOPMIN", WRITE),
+    ("BZPOPMAX", WRITE),
+    ("BZMPOP", WRITE),
+    ("XADD", WRITE | DENY_OOM),
+    ("XTRIM", WRITE),
+    ("XDEL", WRITE),
+    ("XGROUP", WRITE | DENY_OOM),
+    ("XACK", WRITE),
+    ("XREADGROUP", WRITE | DENY_OOM),
+    ("XCLAIM", WRITE),
+    ("XAUTOCLAIM", WRITE),
+    ("GEOADD", WRITE | DENY_OOM),
+    ("GEOSEARCHSTORE", WRITE | DENY_OOM),
+    ("EVAL", DENY_OOM),
+    ("EVALSHA", DENY_OOM),
+    ("FCALL", DENY_OOM),
+];
+
+fn has_flag(method: &str, flag: u8) -> bool {
+    COMMAND_FLAGS
+        .iter()
+        .any(|(name, flags)| *name == method && flags & flag != 0)
+}
+
+/// Commands a client subscribed to some channel may still send.
+const SUBSCRIBED: &[&str] = &[
+    "SUBSCRIBE",
+    "UNSUBSCRIBE",
+    "PSUBSCRIBE",
+    "PUNSUBSCRIBE",
+    "PING",
+    "QUIT",
+];
+
 #[derive(Debug, Error)]
 pub(crate) enum Error {
     #[error("io error `{0}`")]
@@ -43,26 +131,69 @@ pub(crate) enum Error {
 
 pub(crate) struct Service {
     read_stream: BufReader<ReadHalf<TcpStream>>,
-    write_stream: BufWriter<WriteHalf<TcpStream>>,
+    /// Replies of the command being processed, sent once it is done. Writing them
+    /// never waits, which lets `EXEC` run its commands without yielding.
+    write_stream: Replies,
+    socket: WriteHalf<TcpStream>,
+    databases: Databases,
+    /// Index of the database picked with `SELECT`.
+    db: usize,
+    config: Arc<Config>,
+    /// Commands queued since `MULTI`.
+    transaction: Option<Transaction>,
+    watched: Vec<Watched>,
+    scripts: Arc<Scripts>,
+    libraries: Arc<Libraries>,
+    pubsub: Arc<PubSub>,
+    subscriber: Subscriber,
+    tracking: ClientTracking,
+    /// What `CLIENT CACHING` asked of the next command.
+    caching: Option<bool>,
+    /// Started by the first script the connection runs.
+    engine: Option<Engine>,
+    /// Set once the client gave `requirepass`, from the start without one.
+    authenticated: bool,
 }
 
 impl Service {
-    pub(crate) fn new(stream: TcpStream) -> Self {
-        let (read_stream, write_stream) = split(stream);
+    pub(crate) fn new(
+        stream: TcpStream,
+        databases: Databases,
+        config: Arc<Config>,
+        scripts: Arc<Scripts>,
+        libraries: Arc<Libraries>,
+        pubsub: Arc<PubSub>,
+    ) -> Self {
+        let (read_stream, socket) = split(stream);
         let read_stream = BufReader::new(read_stream);
-        let write_stream = BufWriter::new(write_stream);
+        let subscriber = Subscriber::new(pubsub.clone());
+        let tracking = databases.client_tracking(subscriber.id());
+        let authenticated = config.requirepass.is_none();
         Self {
             read_stream,
-            write_stream,
+            write_stream: Replies::default(),
+            socket,
+            databases,
+            db: 0,
+            config,
+            transaction: None,
+            watched: Vec::new(),
+            scripts,
+            libraries,
+            subscriber,
+            tracking,
+            caching: None,
+            pubsub,
+            engine: None,
+            authenticated,
         }
     }
 
-    pub(crate) async fn run(mut self, db: Database) {
+    pub(crate) async fn run(mut self) {
         'main: loop {
-            let map = db.clone();
-            match self.process(map).await {
+            match self.process().await {
                 Ok(reply) => {
-                    if let Err(e) = self.write_stream.flush().await {
+                    if let Err(e) = self.send().await {
                         error!("{}", e);
                         break;
                     }
@@ -76,7 +207,11 @@ impl Service {
         }
     }
 
-    async fn process(&mut self, db: Database) -> Result<(), Error> {
+    async fn process(&mut self) -> Result<(), Error> {
+        if !self.wait().await? {
+            return Ok(());
+        }
+
         let mut buff = String::new();
         self.read_buff(&mut buff).await?;
 
@@ -86,10 +221,10 @@ impl Service {
         };
 
         let mut content = String::new();
-        for _ in 0..size * 2 {
-            buff.clear();
+        for _ in 0..size {
             self.read_buff(&mut buff).await?;
             content.push_str(&buff);
+            self.read_bulk(&buff, &mut content).await?;
         }
 
         let (content_str, method) = match parse_bulk(content.as_str()) {
@@ -99,8 +234,325 @@ impl Service {
 
         let method = method.ok_or(Error::Protocol(String::from("bulk parse error")))?;
         let mut builder = FieldBuilder::new(content_str, size - 1);
+        // looked up once the command is read, as SWAPDB may have moved it meanwhile
+        let db = self.databases.get(self.db).unwrap();
+
+        let method = method.to_uppercase();
+        if !self.authenticated && !matches!(method.as_str(), "AUTH" | "HELLO" | "QUIT") {
+            let reply = Reply::Error(String::from("NOAUTH Authentication required."));
+            reply.write(&mut self.write_stream).await?;
+            return Ok(());
+        }
+
+        if self.config.maxmemory > 0
+            && !self.databases.evict(
+                self.config.maxmemory,
+                self.config.maxmemory_policy,
+                self.config.maxmemory_samples,
+            )
+            && has_flag(&method, DENY_OOM)
+        {
+            let reply = Reply::Error(String::from(
+                "OOM command not allowed when used memory > 'maxmemory'.",
+            ));
+            reply.write(&mut self.write_stream).await?;
+            if let Some(transaction) = self.transaction.as_mut() {
+                transaction.abort();
+            }
+            return Ok(());
+        }
+
+        // RESP3 tells the pushes from the replies, which lets its clients go on
+        if self.write_stream.protocol == Protocol::Resp2
+            && self.subscriber.count() > 0
+            && !SUBSCRIBED.contains(&method.as_str())
+        {
+            let reply = Reply::Error(format!(
+                "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+                method.to_lowercase()
+            ));
+            reply.write(&mut self.write_stream).await?;
+            return Ok(());
+        }
+
+        // kept through `MULTI` for the commands it queues
+        let caching = method == "CLIENT" || self.transaction.is_some();
+        let result = match method.as_str() {
+            "PING"
+                if self.subscriber.count() > 0 && self.write_stream.protocol == Protocol::Resp2 =>
+            {
+                Ping::build(&mut builder)?;
+                write_array(
+                    vec![Reply::from("pong"), Reply::from("")],
+                    &mut self.write_stream,
+                );
+                Ok(())
+            }
+            "SUBSCRIBE" => {
+                let subscribe = Subscribe::build(&mut builder)?;
+                subscribe.apply(&mut self.subscriber, &mut self.write_stream);
+                Ok(())
+            }
+            "UNSUBSCRIBE" => {
+                let unsubscribe = Unsubscribe::build(&mut builder)?;
+                unsubscribe.apply(&mut self.subscriber, &mut self.write_stream);
+                Ok(())
+            }
+            "PSUBSCRIBE" => {
+                let psubscribe = PSubscribe::build(&mut builder)?;
+                psubscribe.apply(&mut self.subscriber, &mut self.write_stream);
+                Ok(())
+            }
+            "PUNSUBSCRIBE" => {
+                let punsubscribe = PUnsubscribe::build(&mut builder)?;
+                punsubscribe.apply(&mut self.subscriber, &mut self.write_stream);
+                Ok(())
+            }
+            "HELLO" => {
+                let hello = Hello::build(&mut builder)?;
+                let reply = hello.apply(
+                    &mut self.write_stream.protocol,
+                    self.tracking.id(),
+                    self.config.requirepass.as_deref(),
+                    &mut self.authenticated,
+                );
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "AUTH" => {
+                let auth = Auth::build(&mut builder)?;
+                let reply = auth.apply(self.config.requirepass.as_deref(), &mut self.authenticated);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "QUIT" => {
+                Quit::build(&mut builder)?;
+                Reply::Simple(String::from("OK"))
+                    .write(&mut self.write_stream)
+                    .await?;
+                self.send().await?;
+                Err(Error::Close)
+            }
+            "MULTI" => {
+                let multi = Multi::build(&mut builder)?;
+                let reply = multi.apply(&mut self.transaction);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "EXEC" => {
+                let exec = Exec::build(&mut builder)?;
+                match exec.apply(&mut self.transaction, &mut self.watched) {
+                    Ok((transaction, watched)) => self.exec(transaction, watched),
+                    Err(reply) => {
+                        reply.write(&mut self.write_stream).await?;
+                        Ok(())
+                    }
+                }
+            }
+            "DISCARD" => {
+                let discard = Discard::build(&mut builder)?;
+                let reply = discard.apply(&mut self.transaction, &mut self.watched);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "WATCH" => {
+                let watch = Watch::build(&mut builder)?;
+                let reply = match self.transaction.as_mut() {
+                    Some(transaction) => {
+                        transaction.abort();
+                        Reply::Error(String::from("ERR WATCH inside MULTI is not allowed"))
+                    }
+                    None => watch.apply(db, self.db, &mut self.watched),
+                };
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            _ => match self.transaction.as_mut() {
+                Some(transaction) => {
+                    let reply = transaction.queue(method, content_str, size - 1);
+                    reply.write(&mut self.write_stream).await?;
+                    Ok(())
+                }
+                None => self.execute(&method, builder, db).await,
+            },
+        };
+        if !caching {
+            self.caching = None;
+        }
+        result
+    }
+
+    /// Runs the commands of a transaction as an array of their replies, holding the
+    /// slots of their keys and of the watched ones meanwhile. Every reply is buffered, so
+    /// only a blocking command may have to wait, which it doesn't and replies nil as on
+    /// a timeout.
+    fn exec(&mut self, transaction: Transaction, watched: Vec<Watched>) -> Result<(), Error> {
+        // waits for the slots to be left by other clients, which the other tasks of this
+        // worker shouldn't have to
+        let _exclusive = block_in_place(|| match transaction.keys() {
+            Some(keys) => {
+                let db = self.databases.get(self.db).unwrap();
+                let watched_keys = watched
+                    .iter()
+                    .map(|watched| (self.databases.get(watched.db).unwrap(), watched.key.clone()));
+                Exclusive::keys(
+                    keys.into_iter()
+                        .map(|key| (db.clone(), key))
+                        .chain(watched_keys),
+                )
+            }
+            None => Exclusive::all(&self.databases),
+        });
+
+        // any watched key written since fails the whole transaction
+        let changed = watched.iter().any(|watched| {
+            let db = self.databases.get(watched.db).unwrap();
+            db.version(watched.key.clone()) != watched.version
+        });
+        if changed {
+            now_or_never(Reply::from(None::<Reply>).write(&mut self.write_stream))
+                .expect("replies are buffered")?;
+            return Ok(());
+        }
+
+        let queue = transaction.into_queue();
+        let mut replies = Vec::with_capacity(queue.len());
+        for queued in queue {
+            let db = self.databases.get(self.db).unwrap();
+            let builder = FieldBuilder::new(&queued.content, queued.total);
+            // each command writes its reply on its own, taken back to go in the array
+            let replies_before = Replies::new(self.write_stream.protocol);
+            let replies_before = mem::replace(&mut self.write_stream, replies_before);
+            let result = now_or_never(self.execute(&queued.method, builder, db));
+            let written = mem::replace(&mut self.write_stream, replies_before).into_inner();
+            replies.push(match result {
+                Some(Err(Error::Protocol(e))) => Reply::Error(format!("ERR {}", e)),
+                Some(result) => {
+                    result?;
+                    Reply::Written(written)
+                }
+                None => Reply::from(None::<Reply>),
+            });
+        }
+        now_or_never(Reply::Array(replies).write(&mut self.write_stream))
+            .expect("replies are buffered")?;
+        Ok(())
+    }
+
+    /// Runs a script holding the slots of its keys, which are then all it may reach.
+    fn eval(&mut self, call: Call) {
+        let db = self.databases.get(self.db).unwrap();
+        let _exclusive = block_in_place(|| {
+            Exclusive::keys(call.keys.iter().map(|key| (db.clone(), key.clone())))
+        });
+        let mut engine = self.engine.take().unwrap_or_else(Engine::new);
+        let mut out = Replies::new(self.write_stream.protocol);
+        engine.run(
+            &call.sha,
+            &call.script,
+            call.keys,
+            call.args,
+            |args| self.script_call(args, false),
+            &mut out,
+        );
+        self.engine = Some(engine);
+        self.write_stream.extend(out.into_inner());
+    }
+
+    /// Runs a library function as `eval` does a script.
+    fn fcall(&mut self, call: FunctionCall) {
+        let db = self.databases.get(self.db).unwrap();
+        let _exclusive = block_in_place(|| {
+            Exclusive::keys(call.keys.iter().map(|key| (db.clone(), key.clone())))
+        });
+        let mut engine = self.engine.take().unwrap_or_else(Engine::new);
+        let mut out = Replies::new(self.write_stream.protocol);
+        engine.run_function(
+            &call.library,
+            &call.function,
+            call.keys,
+            call.args,
+            |args| self.script_call(args, call.read_only),
+            &mut out,
+        );
+        self.engine = Some(engine);
+        self.write_stream.extend(out.into_inner());
+    }
 
-        match method.to_uppercase().as_str() {
+    /// Runs a command for `redis.call`, replying it as RESP.
+    fn script_call(&mut self, mut args: Vec<String>, read_only: bool) -> Vec<u8> {
+        let method = args.remove(0).to_uppercase();
+        let mut content = String::new();
+        for arg in args.iter() {
+            let _ = write!(content, "${}\r\n{}\r\n", arg.len(), arg);
+        }
+        let total = args.len();
+
+        let db = self.databases.get(self.db).unwrap();
+        let denied = match method.as_str() {
+            "EVAL" | "EVALSHA" | "SCRIPT" | "FUNCTION" | "FCALL" | "FCALL_RO" | "CLIENT"
+            | "AUTH" | "HELLO" | "QUIT" | "SUBSCRIBE" | "UNSUBSCRIBE" | "PSUBSCRIBE"
+            | "PUNSUBSCRIBE" | "MULTI" | "EXEC" | "DISCARD" | "WATCH" | "UNWATCH" => Some(
+                String::from("ERR This Redis command is not allowed from script"),
+            ),
+            _ if read_only && has_flag(&method, WRITE) => Some(String::from(
+                "ERR Write commands are not allowed from read-only scripts.",
+            )),
+            _ => match check(&method, &mut FieldBuilder::new(&content, total)) {
+                Err(Error::Protocol(e)) => Some(format!("ERR {}", e)),
+                Err(e) => Some(format!("ERR {}", e)),
+                Ok(()) => match command_keys(&method, args) {
+                    Some(keys) if keys.iter().all(|key| db.holds(key)) => None,
+                    Some(_) => Some(String::from(
+                        "ERR Script attempted to access a key not declared in KEYS",
+                    )),
+                    None => Some(String::from(
+                        "ERR This Redis command is not allowed from script",
+                    )),
+                },
+            },
+        };
+
+        // scripts read the replies as RESP2 whatever the connection speaks
+        let stream = std::mem::take(&mut self.write_stream);
+        let reply = match denied {
+            Some(e) => Some(Reply::Error(e)),
+            None => {
+                match now_or_never(self.execute(&method, FieldBuilder::new(&content, total), db)) {
+                    Some(Err(Error::Protocol(e))) => Some(Reply::Error(format!("ERR {}", e))),
+                    Some(Err(e)) => Some(Reply::Error(format!("ERR {}", e))),
+                    Some(Ok(())) => None,
+                    None => Some(Reply::from(None::<Reply>)),
+                }
+            }
+        };
+        if let Some(reply) = reply {
+            now_or_never(reply.write(&mut self.write_stream))
+                .expect("replies are buffered")
+                .expect("writing to memory never fails");
+        }
+        std::mem::replace(&mut self.write_stream, stream).into_inner()
+    }
+
+    async fn execute(
+        &mut self,
+        method: &str,
+        mut builder: FieldBuilder<'_>,
+        db: Database,
+    ) -> Result<(), Error> {
+        let db = match self.tracking.options() {
+            Some(options) => {
+                let track_reads = !has_flag(method, WRITE)
+                    && !options.bcast
+                    && match self.caching {
+                        Some(caching) => caching,
+                        None => !options.optin,
+                    };
+                self.tracking.database(db, track_reads)
+            }
+            None => db,
+        };
+        match method {
             // "COMMAND" => Ok(Message::Command),
             "PING" => {
                 let ping = Ping::build(&mut builder).unwrap();
@@ -128,8 +580,187 @@ impl Service {
                 Ok(())
             }
             "DEL" => {
-                let mut delete = Delete::build(&mut builder)?;
-                let reply = delete.apply(db);
+                let delete = Delete::build(&mut builder)?;
+                let reply = delete.apply(db, self.config.lazyfree_lazy_user_del);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "UNLINK" => {
+                let unlink = Unlink::build(&mut builder)?;
+                let reply = unlink.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "EXISTS" => {
+                let exists = Exists::build(&mut builder)?;
+                let reply = exists.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "TYPE" => {
+                let key_type = KeyType::build(&mut builder)?;
+                let reply = key_type.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "KEYS" => {
+                let keys = Keys::build(&mut builder)?;
+                let reply = keys.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "DBSIZE" => {
+                let dbsize = DbSize::build(&mut builder)?;
+                let reply = dbsize.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "RANDOMKEY" => {
+                let randomkey = RandomKey::build(&mut builder)?;
+                let reply = randomkey.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "SCAN" => {
+                let scan = Scan::build(&mut builder)?;
+                let reply = scan.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "SSCAN" => {
+                let sscan = SScan::build(&mut builder)?;
+                let reply = sscan.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZSCAN" => {
+                let zscan = ZScan::build(&mut builder)?;
+                let reply = zscan.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "RENAME" => {
+                let rename = Rename::build(&mut builder)?;
+                let reply = rename.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "RENAMENX" => {
+                let renamenx = RenameNx::build(&mut builder)?;
+                let reply = renamenx.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "COPY" => {
+                let copy = CopyKey::build(&mut builder)?;
+                let reply = copy.apply(db, &self.databases);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "MOVE" => {
+                let move_key = Move::build(&mut builder)?;
+                let reply = move_key.apply(db, &self.databases);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "SELECT" => {
+                let select = Select::build(&mut builder)?;
+                let reply = select.apply(&self.databases, &mut self.db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "SWAPDB" => {
+                let swapdb = SwapDb::build(&mut builder)?;
+                let reply = swapdb.apply(&self.databases);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "FLUSHDB" => {
+                let flushdb = FlushDb::build(&mut builder)?;
+                let reply = flushdb.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "FLUSHALL" => {
+                let flushall = FlushAll::build(&mut builder)?;
+                let reply = flushall.apply(&self.databases);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "MEMORY" => {
+                let memory = Memory::build(&mut builder)?;
+                let reply = memory.apply(db, &self.databases);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "UNWATCH" => {
+                let unwatch = Unwatch::build(&mut builder)?;
+                let reply = unwatch.apply(&mut self.watched);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "OBJECT" => {
+                let object = Object::build(&mut builder)?;
+                let reply = object.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "TOUCH" => {
+                let touch = Touch::build(&mut builder)?;
+                let reply = touch.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "EVAL" => {
+                let eval = Eval::build(&mut builder)?;
+                let call = eval.apply(&self.scripts);
+                self.eval(call);
+                Ok(())
+            }
+            "EVALSHA" => {
+                let evalsha = EvalSha::build(&mut builder)?;
+                match evalsha.apply(&self.scripts) {
+                    Ok(call) => self.eval(call),
+                    Err(reply) => reply.write(&mut self.write_stream).await?,
+                }
+                Ok(())
+            }
+            "SCRIPT" => {
+                let script = Script::build(&mut builder)?;
+                let reply = script.apply(&self.scripts);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "FUNCTION" => {
+                let function = Function::build(&mut builder)?;
+                let engine = self.engine.get_or_insert_with(Engine::new);
+                let reply = function.apply(&self.libraries, engine);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "FCALL" | "FCALL_RO" => {
+                let fcall = Fcall::build(&mut builder)?;
+                match fcall.apply(&self.libraries, method == "FCALL_RO") {
+                    Ok(call) => self.fcall(call),
+                    Err(reply) => reply.write(&mut self.write_stream).await?,
+                }
+                Ok(())
+            }
+            "CLIENT" => {
+                let client = Client::build(&mut builder)?;
+                let reply = client.apply(&mut self.tracking, &mut self.caching);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "PUBLISH" => {
+                let publish = Publish::build(&mut builder)?;
+                let reply = publish.apply(&self.pubsub);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "PUBSUB" => {
+                let pubsub = PubSubInfo::build(&mut builder)?;
+                let reply = pubsub.apply(&self.pubsub);
                 reply.write(&mut self.write_stream).await?;
                 Ok(())
             }
@@ -146,16 +777,9 @@ impl Service {
                 Ok(())
             }
             "LRANGE" => {
-                let mut lrange = LRange::build(&mut builder)?;
-                let list = lrange.apply(db);
-
-                let list_len = list.len();
-                Reply::array_len_write(list_len, &mut self.write_stream).await?;
-
-                for item in list {
-                    let reply = Reply::from(item);
-                    reply.write(&mut self.write_stream).await?;
-                }
+                let lrange = LRange::build(&mut builder)?;
+                let reply = lrange.apply(db);
+                reply.write(&mut self.write_stream).await?;
                 Ok(())
             }
             "LPOP" => {
@@ -177,16 +801,15 @@ impl Service {
                 Ok(())
             }
             "MGET" => {
-                let mut mget = MGet::build(&mut builder)?;
-                let list = mget.apply(db);
-
-                let list_len = list.len();
-                Reply::array_len_write(list_len, &mut self.write_stream).await?;
-
-                for item in list {
-                    let reply = Reply::from(item);
-                    reply.write(&mut self.write_stream).await?;
-                }
+                let mget = MGet::build(&mut builder)?;
+                let reply = mget.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "MSET" => {
+                let mset = MSet::build(&mut builder)?;
+                let reply = mset.apply(db);
+                reply.write(&mut self.write_stream).await?;
                 Ok(())
             }
             "SADD" => {
@@ -196,16 +819,9 @@ impl Service {
                 Ok(())
             }
             "SMEMBERS" => {
-                let mut smembers = Smembers::build(&mut builder)?;
-                let list = smembers.apply(db);
-
-                let list_len = list.len();
-                Reply::array_len_write(list_len, &mut self.write_stream).await?;
-
-                for item in list {
-                    let reply = Reply::from(item);
-                    reply.write(&mut self.write_stream).await?;
-                }
+                let smembers = Smembers::build(&mut builder)?;
+                let reply = smembers.apply(db);
+                reply.write(&mut self.write_stream).await?;
                 Ok(())
             }
             "SCARD" => {
@@ -214,10 +830,329 @@ impl Service {
                 reply.write(&mut self.write_stream).await?;
                 Ok(())
             }
+            "ZADD" => {
+                let zadd = ZAdd::build(&mut builder)?;
+                let reply = zadd.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZREM" => {
+                let zrem = ZRem::build(&mut builder)?;
+                let reply = zrem.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZSCORE" => {
+                let zscore = ZScore::build(&mut builder)?;
+                let reply = zscore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZINCRBY" => {
+                let zincrby = ZIncrBy::build(&mut builder)?;
+                let reply = zincrby.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZCARD" => {
+                let zcard = ZCard::build(&mut builder)?;
+                let reply = zcard.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZRANK" => {
+                let zrank = ZRank::build(&mut builder)?;
+                let reply = zrank.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZREVRANK" => {
+                let zrevrank = ZRevRank::build(&mut builder)?;
+                let reply = zrevrank.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZCOUNT" => {
+                let zcount = ZCount::build(&mut builder)?;
+                let reply = zcount.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZLEXCOUNT" => {
+                let zlexcount = ZLexCount::build(&mut builder)?;
+                let reply = zlexcount.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZREMRANGEBYRANK" => {
+                let zremrangebyrank = ZRemRangeByRank::build(&mut builder)?;
+                let reply = zremrangebyrank.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZREMRANGEBYSCORE" => {
+                let zremrangebyscore = ZRemRangeByScore::build(&mut builder)?;
+                let reply = zremrangebyscore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZREMRANGEBYLEX" => {
+                let zremrangebylex = ZRemRangeByLex::build(&mut builder)?;
+                let reply = zremrangebylex.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZRANGE" => {
+                let zrange = ZRange::build(&mut builder)?;
+                let reply = zrange.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZPOPMIN" => {
+                let zpopmin = ZPopMin::build(&mut builder)?;
+                let reply = zpopmin.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZPOPMAX" => {
+                let zpopmax = ZPopMax::build(&mut builder)?;
+                let reply = zpopmax.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZUNIONSTORE" => {
+                let zunionstore = ZUnionStore::build(&mut builder)?;
+                let reply = zunionstore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZINTERSTORE" => {
+                let zinterstore = ZInterStore::build(&mut builder)?;
+                let reply = zinterstore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZDIFFSTORE" => {
+                let zdiffstore = ZDiffStore::build(&mut builder)?;
+                let reply = zdiffstore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZUNION" => {
+                let zunion = ZUnion::build(&mut builder)?;
+                let reply = zunion.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZINTER" => {
+                let zinter = ZInter::build(&mut builder)?;
+                let reply = zinter.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "ZDIFF" => {
+                let zdiff = ZDiff::build(&mut builder)?;
+                let reply = zdiff.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "BZPOPMIN" => {
+                let bzpopmin = BZPopMin::build(&mut builder)?;
+                let reply = bzpopmin.apply(db).await;
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "BZPOPMAX" => {
+                let bzpopmax = BZPopMax::build(&mut builder)?;
+                let reply = bzpopmax.apply(db).await;
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "BZMPOP" => {
+                let bzmpop = BZMPop::build(&mut builder)?;
+                let reply = bzmpop.apply(db).await;
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XADD" => {
+                let xadd = XAdd::build(&mut builder)?;
+                let reply = xadd.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XLEN" => {
+                let xlen = XLen::build(&mut builder)?;
+                let reply = xlen.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XTRIM" => {
+                let xtrim = XTrim::build(&mut builder)?;
+                let reply = xtrim.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XDEL" => {
+                let xdel = XDel::build(&mut builder)?;
+                let reply = xdel.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XRANGE" => {
+                let xrange = XRange::build(&mut builder)?;
+                let reply = xrange.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XREVRANGE" => {
+                let xrevrange = XRevRange::build(&mut builder)?;
+                let reply = xrevrange.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XREAD" => {
+                let xread = XRead::build(&mut builder)?;
+                let reply = xread.apply(db).await;
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XGROUP" => {
+                let xgroup = XGroup::build(&mut builder)?;
+                let reply = xgroup.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XACK" => {
+                let xack = XAck::build(&mut builder)?;
+                let reply = xack.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XREADGROUP" => {
+                let xreadgroup = XReadGroup::build(&mut builder)?;
+                let reply = xreadgroup.apply(db).await;
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XPENDING" => {
+                let xpending = XPending::build(&mut builder)?;
+                let reply = xpending.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XCLAIM" => {
+                let xclaim = XClaim::build(&mut builder)?;
+                let reply = xclaim.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XAUTOCLAIM" => {
+                let xautoclaim = XAutoClaim::build(&mut builder)?;
+                let reply = xautoclaim.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "XINFO" => {
+                let xinfo = XInfo::build(&mut builder)?;
+                let reply = xinfo.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEOADD" => {
+                let geoadd = GeoAdd::build(&mut builder)?;
+                let reply = geoadd.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEODIST" => {
+                let geodist = GeoDist::build(&mut builder)?;
+                let reply = geodist.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEOHASH" => {
+                let geohash = GeoHash::build(&mut builder)?;
+                let reply = geohash.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEOPOS" => {
+                let geopos = GeoPos::build(&mut builder)?;
+                let reply = geopos.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEOSEARCH" => {
+                let geosearch = GeoSearch::build(&mut builder)?;
+                let reply = geosearch.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
+            "GEOSEARCHSTORE" => {
+                let geosearchstore = GeoSearchStore::build(&mut builder)?;
+                let reply = geosearchstore.apply(db);
+                reply.write(&mut self.write_stream).await?;
+                Ok(())
+            }
             _ => Err(Error::Protocol(String::from("command error"))),
         }
     }
 
+    async fn send(&mut self) -> Result<(), IoError> {
+        self.socket.write_all(&self.write_stream).await?;
+        self.write_stream.clear();
+        Ok(())
+    }
+
+    /// Waits for the next command, writing the messages of the subscriptions and the
+    /// invalidations that arrive meanwhile. `false` when it returns before one, to send
+    /// what it wrote.
+    async fn wait(&mut self) -> Result<bool, Error> {
+        let mut receiver = self.subscriber.receiver();
+        let invalidations = self.tracking.receiver();
+        let read_stream = &mut self.read_stream;
+        let event = poll_fn(|cx| {
+            if let Some(receiver) = receiver.as_mut() {
+                if let Poll::Ready(message) = receiver.poll_recv(cx) {
+                    return Poll::Ready(Some(Ok(message)));
+                }
+            }
+            // the sender lives as long as the connection
+            if let Poll::Ready(Some(invalidation)) = invalidations.poll_recv(cx) {
+                return Poll::Ready(Some(Err(invalidation)));
+            }
+            // a failed read shows up again when the command is read
+            Pin::new(&mut *read_stream).poll_fill_buf(cx).map(|_| None)
+        })
+        .await;
+        match event {
+            None => Ok(true),
+            Some(Ok(Some(message))) => {
+                message.write(&mut self.write_stream);
+                if let Some(receiver) = receiver {
+                    while let Ok(message) = receiver.try_recv() {
+                        message.write(&mut self.write_stream);
+                    }
+                }
+                Ok(false)
+            }
+            Some(Ok(None)) if self.subscriber.closed() => Err(Error::Close),
+            Some(Ok(None)) => Ok(false),
+            Some(Err(invalidation)) => {
+                let pushed =
+                    self.subscriber.count() > 0 || self.write_stream.protocol == Protocol::Resp3;
+                let mut invalidation = Some(invalidation);
+                while let Some(next) = invalidation {
+                    if pushed {
+                        write_invalidation(&next, &mut self.write_stream);
+                    }
+                    invalidation = self.tracking.receiver().try_recv().ok();
+                }
+                Ok(false)
+            }
+        }
+    }
+
     async fn read_buff<'b>(&mut self, buff: &'b mut String) -> Result<(), Error> {
         buff.clear();
         match self.read_stream.read_line(buff).await? {
@@ -227,6 +1162,19 @@ impl Service {
         }
     }
 
+    /// Reads the content of the bulk string `header` announces, line breaks included.
+    async fn read_bulk(&mut self, header: &str, content: &mut String) -> Result<(), Error> {
+        let len = header
+            .strip_prefix('$')
+            .and_then(|len| len.trim_end().parse::<usize>().ok())
+            .ok_or(Error::Protocol(String::from("bulk sytanx error")))?;
+        let mut bulk = vec![0; len + 2];
+        self.read_stream.read_exact(&mut bulk).await?;
+        let bulk = String::from_utf8(bulk).map_err(|e| Error::Protocol(e.to_string()))?;
+        content.push_str(&bulk);
+        Ok(())
+    }
+
     const fn command_reply() -> &'static [u8] {
         b"*3\r\n*6\r\n$4\r\nping\r\n:-1\r\n*2\r\n+stable\r\n+fast\r\n:0\r\n:0\r\n:0\r\n*6\r\n$7\r\ncommand\r\n:0\r\n*3\r\n+random\r\n+loading\r\n+stable\r\n:0\r\n:0\r\n:0\r\n*6\r\n$3\r\nset\r\n:-3\r\n*2\r\n+write\r\n+denyoom\r\n:1\r\n:1\r\n:1\r\n"
     }
@@ -239,3 +1187,36 @@ impl Service {
         b"+OK\r\n"
     }
 }
+
+#[test]
+fn test_maxmemory() {
+    use crate::testing::{serve, Client};
+    use crate::Config;
+    use database::EvictionPolicy;
+
+    let oom = "-OOM command not allowed when used memory > 'maxmemory'.\r\n";
+    for policy in [EvictionPolicy::NoEviction, EvictionPolicy::AllKeysRandom] {
+        let config = Config {
+            maxmemory: 1,
+            maxmemory_policy: policy,
+            ..Config::default()
+        };
+        serve(config, |addr| async move {
+            let mut client = Client::connect(addr).await;
+            assert_eq!(client.call(&["SET", "a", "1"]).await, oom);
+            assert_eq!(client.call(&["MSET", "a", "1", "b", "2"]).await, oom);
+            assert_eq!(client.call(&["EVAL", "return 1", "0"]).await, oom);
+            assert_eq!(client.call(&["GET", "a"]).await, "+nil\r\n");
+            assert_eq!(client.call(&["DBSIZE"]).await, ":0\r\n");
+        });
+    }
+
+    serve(Config::default(), |addr| async move {
+        let mut client = Client::connect(addr).await;
+        assert_eq!(client.call(&["MSET", "a", "1", "b", "2"]).await, "+OK\r\n");
+        assert_eq!(
+            client.call(&["MGET", "a", "b"]).await,
+            "*2\r\n$1\r\n1\r\n$1\r\n2\r\n"
+        );
+    });
+}
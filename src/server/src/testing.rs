@@ -0,0 +1,97 @@
+use crate::{Allocator, Config, Server};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Builder;
+use tokio::spawn;
+use tokio::time::timeout;
+
+// counts the bytes in use for the servers under test to check `maxmemory` against
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+/// Runs `test` against a server listening on a free port with `config`.
+pub(crate) fn serve<F>(config: Config, test: impl FnOnce(SocketAddr) -> F) -> F::Output
+where
+    F: Future,
+{
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let listen = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listen.local_addr().unwrap();
+            spawn(Server::new(addr, config).unwrap().serve(listen));
+            test(addr).await
+        })
+}
+
+/// A connection reading the replies back as the RESP they were sent in.
+pub(crate) struct Client {
+    stream: BufReader<TcpStream>,
+}
+
+impl Client {
+    pub(crate) async fn connect(addr: SocketAddr) -> Self {
+        Self {
+            stream: BufReader::new(TcpStream::connect(addr).await.unwrap()),
+        }
+    }
+
+    pub(crate) async fn send(&mut self, args: &[&str]) {
+        let mut command = format!("*{}\r\n", args.len());
+        for arg in args {
+            command.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+        }
+        self.stream
+            .get_mut()
+            .write_all(command.as_bytes())
+            .await
+            .unwrap();
+    }
+
+    /// The next reply, with whatever it nests.
+    pub(crate) async fn read(&mut self) -> String {
+        let mut reply = String::new();
+        let mut pending = 1;
+        while pending > 0 {
+            pending -= 1;
+            let start = reply.len();
+            if self.stream.read_line(&mut reply).await.unwrap() == 0 {
+                panic!("connection closed");
+            }
+            let len = reply[start + 1..].trim_end().parse::<i64>().unwrap_or(0);
+            match reply.as_bytes()[start] {
+                b'$' | b'=' if len >= 0 => {
+                    let mut bulk = vec![0; len as usize + 2];
+                    self.stream.read_exact(&mut bulk).await.unwrap();
+                    reply.push_str(&String::from_utf8(bulk).unwrap());
+                }
+                b'*' | b'~' | b'>' if len > 0 => pending += len,
+                b'%' if len > 0 => pending += len * 2,
+                _ => {}
+            }
+        }
+        reply
+    }
+
+    /// The next reply, `None` when none comes in a moment.
+    pub(crate) async fn try_read(&mut self) -> Option<String> {
+        timeout(Duration::from_millis(100), self.read()).await.ok()
+    }
+
+    /// Whether the server closed the connection, once every reply was read.
+    pub(crate) async fn is_closed(&mut self) -> bool {
+        let mut line = String::new();
+        let read = timeout(Duration::from_millis(100), self.stream.read_line(&mut line));
+        matches!(read.await, Ok(Ok(0)))
+    }
+
+    pub(crate) async fn call(&mut self, args: &[&str]) -> String {
+        self.send(args).await;
+        self.read().await
+    }
+}
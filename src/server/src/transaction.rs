@@ -0,0 +1,389 @@
+use crate::cmd::{
+    BZMPop, BZPopMax, BZPopMin, Builder, Client, CopyKey, DbSize, Delete, Eval, EvalSha, Exists,
+    Fcall, FieldBuilder, FlushAll, FlushDb, Function, GeoAdd, GeoDist, GeoHash, GeoPos, GeoSearch,
+    GeoSearchStore, Get, KeyType, Keys, LLen, LPop, LPush, LRange, MGet, MSet, Memory, Move,
+    Object, Ping, Pong, PubSubInfo, Publish, RPop, RPush, RandomKey, Rename, RenameNx, SAdd, SScan,
+    Scan, Scard, Script, Select, Set, Smembers, SwapDb, Touch, Unlink, Unwatch, XAck, XAdd,
+    XAutoClaim, XClaim, XDel, XGroup, XInfo, XLen, XPending, XRange, XRead, XReadGroup, XRevRange,
+    XTrim, ZAdd, ZCard, ZCount, ZDiff, ZDiffStore, ZIncrBy, ZInter, ZInterStore, ZLexCount,
+    ZPopMax, ZPopMin, ZRange, ZRank, ZRem, ZRemRangeByLex, ZRemRangeByRank, ZRemRangeByScore,
+    ZRevRank, ZScan, ZScore, ZUnion, ZUnionStore,
+};
+use crate::reply::Reply;
+use crate::service::Error;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::task;
+
+/// A command queued after `MULTI`, parsed again when `EXEC` runs it.
+pub(crate) struct Queued {
+    pub(crate) method: String,
+    pub(crate) content: String,
+    pub(crate) total: usize,
+}
+
+/// A key the connection `WATCH`es, with its version back then.
+pub(crate) struct Watched {
+    pub(crate) db: usize,
+    pub(crate) key: String,
+    pub(crate) version: Option<u64>,
+}
+
+/// Commands a client queued between `MULTI` and `EXEC`.
+#[derive(Default)]
+pub(crate) struct Transaction {
+    queue: Vec<Queued>,
+    /// Set once a command failed to queue, `EXEC` then runs none of them.
+    aborted: bool,
+}
+
+impl Transaction {
+    /// Queues a command whose arguments parse, replying the error otherwise.
+    pub(crate) fn queue(&mut self, method: String, content: &str, total: usize) -> Reply {
+        match check(&method, &mut FieldBuilder::new(content, total)) {
+            Ok(()) => {
+                self.queue.push(Queued {
+                    method,
+                    content: content.to_string(),
+                    total,
+                });
+                Reply::Simple(String::from("QUEUED"))
+            }
+            Err(e) => {
+                self.aborted = true;
+                let e = match e {
+                    Error::Protocol(e) => e,
+                    e => e.to_string(),
+                };
+                Reply::Error(format!("ERR {}", e))
+            }
+        }
+    }
+
+    pub(crate) fn abort(&mut self) {
+        self.aborted = true;
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Keys the queued commands touch, `None` when some can't be told from the arguments.
+    pub(crate) fn keys(&self) -> Option<Vec<String>> {
+        let mut keys = Vec::new();
+        for queued in self.queue.iter() {
+            let mut builder = FieldBuilder::new(&queued.content, queued.total);
+            let args = (0..queued.total)
+                .map(|_| builder.get_field::<String, Infallible>())
+                .collect::<Result<Vec<String>, Error>>()
+                .ok()?;
+            keys.extend(command_keys(&queued.method, args)?);
+        }
+        Some(keys)
+    }
+
+    pub(crate) fn into_queue(self) -> Vec<Queued> {
+        self.queue
+    }
+}
+
+/// Parses a command without running it.
+pub(crate) fn check(method: &str, builder: &mut FieldBuilder) -> Result<(), Error> {
+    match method {
+        "PING" => Ping::build(builder).map(drop),
+        "PONG" => Pong::build(builder).map(drop),
+        "SET" => Set::build(builder).map(drop),
+        "GET" => Get::build(builder).map(drop),
+        "DEL" => Delete::build(builder).map(drop),
+        "UNLINK" => Unlink::build(builder).map(drop),
+        "EXISTS" => Exists::build(builder).map(drop),
+        "TYPE" => KeyType::build(builder).map(drop),
+        "KEYS" => Keys::build(builder).map(drop),
+        "DBSIZE" => DbSize::build(builder).map(drop),
+        "RANDOMKEY" => RandomKey::build(builder).map(drop),
+        "SCAN" => Scan::build(builder).map(drop),
+        "SSCAN" => SScan::build(builder).map(drop),
+        "ZSCAN" => ZScan::build(builder).map(drop),
+        "RENAME" => Rename::build(builder).map(drop),
+        "RENAMENX" => RenameNx::build(builder).map(drop),
+        "COPY" => CopyKey::build(builder).map(drop),
+        "MOVE" => Move::build(builder).map(drop),
+        "SELECT" => Select::build(builder).map(drop),
+        "SWAPDB" => SwapDb::build(builder).map(drop),
+        "FLUSHDB" => FlushDb::build(builder).map(drop),
+        "FLUSHALL" => FlushAll::build(builder).map(drop),
+        "MEMORY" => Memory::build(builder).map(drop),
+        "OBJECT" => Object::build(builder).map(drop),
+        "TOUCH" => Touch::build(builder).map(drop),
+        "RPUSH" => RPush::build(builder).map(drop),
+        "LPUSH" => LPush::build(builder).map(drop),
+        "LRANGE" => LRange::build(builder).map(drop),
+        "LPOP" => LPop::build(builder).map(drop),
+        "RPOP" => RPop::build(builder).map(drop),
+        "LLEN" => LLen::build(builder).map(drop),
+        "MGET" => MGet::build(builder).map(drop),
+        "MSET" => MSet::build(builder).map(drop),
+        "SADD" => SAdd::build(builder).map(drop),
+        "SMEMBERS" => Smembers::build(builder).map(drop),
+        "SCARD" => Scard::build(builder).map(drop),
+        "ZADD" => ZAdd::build(builder).map(drop),
+        "ZREM" => ZRem::build(builder).map(drop),
+        "ZSCORE" => ZScore::build(builder).map(drop),
+        "ZINCRBY" => ZIncrBy::build(builder).map(drop),
+        "ZCARD" => ZCard::build(builder).map(drop),
+        "ZRANK" => ZRank::build(builder).map(drop),
+        "ZREVRANK" => ZRevRank::build(builder).map(drop),
+        "ZCOUNT" => ZCount::build(builder).map(drop),
+        "ZLEXCOUNT" => ZLexCount::build(builder).map(drop),
+        "ZREMRANGEBYRANK" => ZRemRangeByRank::build(builder).map(drop),
+        "ZREMRANGEBYSCORE" => ZRemRangeByScore::build(builder).map(drop),
+        "ZREMRANGEBYLEX" => ZRemRangeByLex::build(builder).map(drop),
+        "ZRANGE" => ZRange::build(builder).map(drop),
+        "ZPOPMIN" => ZPopMin::build(builder).map(drop),
+        "ZPOPMAX" => ZPopMax::build(builder).map(drop),
+        "ZUNIONSTORE" => ZUnionStore::build(builder).map(drop),
+        "ZINTERSTORE" => ZInterStore::build(builder).map(drop),
+        "ZDIFFSTORE" => ZDiffStore::build(builder).map(drop),
+        "ZUNION" => ZUnion::build(builder).map(drop),
+        "ZINTER" => ZInter::build(builder).map(drop),
+        "ZDIFF" => ZDiff::build(builder).map(drop),
+        "BZPOPMIN" => BZPopMin::build(builder).map(drop),
+        "BZPOPMAX" => BZPopMax::build(builder).map(drop),
+        "BZMPOP" => BZMPop::build(builder).map(drop),
+        "XADD" => XAdd::build(builder).map(drop),
+        "XLEN" => XLen::build(builder).map(drop),
+        "XTRIM" => XTrim::build(builder).map(drop),
+        "XDEL" => XDel::build(builder).map(drop),
+        "XRANGE" => XRange::build(builder).map(drop),
+        "XREVRANGE" => XRevRange::build(builder).map(drop),
+        "XREAD" => XRead::build(builder).map(drop),
+        "XGROUP" => XGroup::build(builder).map(drop),
+        "XACK" => XAck::build(builder).map(drop),
+        "XREADGROUP" => XReadGroup::build(builder).map(drop),
+        "XPENDING" => XPending::build(builder).map(drop),
+        "XCLAIM" => XClaim::build(builder).map(drop),
+        "XAUTOCLAIM" => XAutoClaim::build(builder).map(drop),
+        "XINFO" => XInfo::build(builder).map(drop),
+        "GEOADD" => GeoAdd::build(builder).map(drop),
+        "GEODIST" => GeoDist::build(builder).map(drop),
+        "GEOHASH" => GeoHash::build(builder).map(drop),
+        "GEOPOS" => GeoPos::build(builder).map(drop),
+        "GEOSEARCH" => GeoSearch::build(builder).map(drop),
+        "GEOSEARCHSTORE" => GeoSearchStore::build(builder).map(drop),
+        "UNWATCH" => Unwatch::build(builder).map(drop),
+        "EVAL" => Eval::build(builder).map(drop),
+        "EVALSHA" => EvalSha::build(builder).map(drop),
+        "SCRIPT" => Script::build(builder).map(drop),
+        "FUNCTION" => Function::build(builder).map(drop),
+        "FCALL" | "FCALL_RO" => Fcall::build(builder).map(drop),
+        "PUBLISH" => Publish::build(builder).map(drop),
+        "PUBSUB" => PubSubInfo::build(builder).map(drop),
+        "CLIENT" => Client::build(builder).map(drop),
+        _ => Err(Error::Protocol(format!(
+            "unknown command '{}'",
+            method.to_lowercase()
+        ))),
+    }
+}
+
+/// Keys of a command with well formed `args`, `None` for the commands that reach every
+/// key or another database.
+pub(crate) fn command_keys(method: &str, mut args: Vec<String>) -> Option<Vec<String>> {
+    // keys after a count, as `ZUNION` and `BZMPOP` take them
+    let counted = |args: &mut Vec<String>, at: usize| -> Option<Vec<String>> {
+        let count = args.get(at)?.parse::<usize>().ok()?;
+        Some(args.drain(at + 1..).take(count).collect())
+    };
+
+    match method {
+        "PING" | "PONG" | "UNWATCH" | "SCRIPT" | "FUNCTION" | "PUBLISH" | "PUBSUB" | "CLIENT" => {
+            Some(Vec::new())
+        }
+        // `MEMORY STATS` and `MEMORY DOCTOR` count the keys of every slot
+        "MEMORY" if !args.first()?.eq_ignore_ascii_case("USAGE") => None,
+        "MEMORY" | "OBJECT" | "XGROUP" | "XINFO" => Some(args.drain(..).skip(1).take(1).collect()),
+        "COPY" if args.iter().any(|arg| arg.eq_ignore_ascii_case("DB")) => None,
+        "RENAME" | "RENAMENX" | "COPY" | "GEOSEARCHSTORE" => {
+            args.truncate(2);
+            Some(args)
+        }
+        "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "MGET" => Some(args),
+        "MSET" => Some(args.into_iter().step_by(2).collect()),
+        "BZPOPMIN" | "BZPOPMAX" => {
+            args.pop();
+            Some(args)
+        }
+        "ZUNION" | "ZINTER" | "ZDIFF" => counted(&mut args, 0),
+        "BZMPOP" | "EVAL" | "EVALSHA" | "FCALL" | "FCALL_RO" => counted(&mut args, 1),
+        "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => {
+            let mut keys = counted(&mut args, 1)?;
+            keys.push(args.swap_remove(0));
+            Some(keys)
+        }
+        "XREAD" | "XREADGROUP" => {
+            // as many keys as ids follow `STREAMS`
+            let streams = args
+                .iter()
+                .position(|arg| arg.eq_ignore_ascii_case("STREAMS"))?;
+            let mut keys = args.split_off(streams + 1);
+            keys.truncate(keys.len() / 2);
+            Some(keys)
+        }
+        "KEYS" | "DBSIZE" | "RANDOMKEY" | "SCAN" | "MOVE" | "SELECT" | "SWAPDB" | "FLUSHDB"
+        | "FLUSHALL" => None,
+        _ => {
+            args.truncate(1);
+            Some(args)
+        }
+    }
+}
+
+/// Polls `future` once, `None` when it would have to wait.
+pub(crate) fn now_or_never<F>(future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    let future = pin!(future);
+    match future.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Runs `f`, which may wait on a lock, on this thread while the other tasks of a
+/// multi-threaded runtime move to another worker meanwhile.
+pub(crate) fn block_in_place<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+        Ok(RuntimeFlavor::MultiThread) => task::block_in_place(f),
+        _ => f(),
+    }
+}
+
+#[test]
+fn test_command_keys() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+    assert_eq!(
+        command_keys("SET", args(&["a", "1", "EX", "10"])),
+        Some(args(&["a"]))
+    );
+    assert_eq!(
+        command_keys(
+            "ZUNIONSTORE",
+            args(&["d", "2", "a", "b", "WEIGHTS", "1", "2"])
+        ),
+        Some(args(&["a", "b", "d"]))
+    );
+    assert_eq!(
+        command_keys(
+            "XREAD",
+            args(&["COUNT", "1", "STREAMS", "a", "b", "0", "0"])
+        ),
+        Some(args(&["a", "b"]))
+    );
+    assert_eq!(
+        command_keys("BZPOPMIN", args(&["a", "b", "0"])),
+        Some(args(&["a", "b"]))
+    );
+    assert_eq!(
+        command_keys("EVAL", args(&["return 1", "2", "a", "b", "c"])),
+        Some(args(&["a", "b"]))
+    );
+    assert_eq!(command_keys("MOVE", args(&["a", "1"])), None);
+}
+
+#[test]
+fn test_multi_slot_keys() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let some = |keys: &[&str]| Some(args(keys));
+    let commands = [
+        ("DEL", args(&["a", "b"]), some(&["a", "b"])),
+        ("UNLINK", args(&["a", "b"]), some(&["a", "b"])),
+        ("EXISTS", args(&["a", "b"]), some(&["a", "b"])),
+        ("TOUCH", args(&["a", "b"]), some(&["a", "b"])),
+        ("MGET", args(&["a", "b"]), some(&["a", "b"])),
+        ("MSET", args(&["a", "1", "b", "2"]), some(&["a", "b"])),
+        ("RENAME", args(&["a", "b"]), some(&["a", "b"])),
+        ("RENAMENX", args(&["a", "b"]), some(&["a", "b"])),
+        ("COPY", args(&["a", "b", "REPLACE"]), some(&["a", "b"])),
+        ("COPY", args(&["a", "b", "DB", "1"]), None),
+        (
+            "ZUNION",
+            args(&["2", "a", "b", "WITHSCORES"]),
+            some(&["a", "b"]),
+        ),
+        ("ZINTER", args(&["2", "a", "b"]), some(&["a", "b"])),
+        ("ZDIFF", args(&["2", "a", "b"]), some(&["a", "b"])),
+        (
+            "ZUNIONSTORE",
+            args(&["d", "2", "a", "b"]),
+            some(&["a", "b", "d"]),
+        ),
+        (
+            "ZINTERSTORE",
+            args(&["d", "2", "a", "b"]),
+            some(&["a", "b", "d"]),
+        ),
+        (
+            "ZDIFFSTORE",
+            args(&["d", "2", "a", "b"]),
+            some(&["a", "b", "d"]),
+        ),
+        ("BZPOPMIN", args(&["a", "b", "0"]), some(&["a", "b"])),
+        ("BZPOPMAX", args(&["a", "b", "0"]), some(&["a", "b"])),
+        (
+            "BZMPOP",
+            args(&["0", "2", "a", "b", "MIN"]),
+            some(&["a", "b"]),
+        ),
+        (
+            "XREAD",
+            args(&["STREAMS", "a", "b", "0", "0"]),
+            some(&["a", "b"]),
+        ),
+        (
+            "XREADGROUP",
+            args(&["GROUP", "g", "c", "STREAMS", "a", "b", ">", ">"]),
+            some(&["a", "b"]),
+        ),
+        (
+            "GEOSEARCHSTORE",
+            args(&["d", "a", "FROMMEMBER", "m"]),
+            some(&["d", "a"]),
+        ),
+        (
+            "EVAL",
+            args(&["return 1", "2", "a", "b"]),
+            some(&["a", "b"]),
+        ),
+        ("EVALSHA", args(&["sha", "2", "a", "b"]), some(&["a", "b"])),
+        ("FCALL", args(&["f", "2", "a", "b"]), some(&["a", "b"])),
+        ("FCALL_RO", args(&["f", "2", "a", "b"]), some(&["a", "b"])),
+        ("MEMORY", args(&["USAGE", "a"]), some(&["a"])),
+        ("MEMORY", args(&["STATS"]), None),
+        ("MEMORY", args(&["DOCTOR"]), None),
+        ("KEYS", args(&["*"]), None),
+        ("DBSIZE", args(&[]), None),
+        ("RANDOMKEY", args(&[]), None),
+        ("SCAN", args(&["0"]), None),
+        ("MOVE", args(&["a", "1"]), None),
+        ("SELECT", args(&["1"]), None),
+        ("SWAPDB", args(&["0", "1"]), None),
+        ("FLUSHDB", args(&[]), None),
+        ("FLUSHALL", args(&[]), None),
+    ];
+    for (method, args, keys) in commands {
+        assert_eq!(
+            command_keys(method, args.clone()),
+            keys,
+            "{} {:?}",
+            method,
+            args
+        );
+    }
+}